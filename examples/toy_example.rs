@@ -2,27 +2,16 @@
 //! Example Program completing the same ecs-toy example I did earlier on my GitHub, but now with my engine
 //! 
 
-use std::{io::{stdout, Result, Stdout}, time::Duration};
+use std::io::Result;
 
 use clap::Parser;
 
-use nate_engine::{Engine, Renderer, system, world};
+use nate_engine::{Engine, system, world};
+use nate_engine::glyph::{Color, Glyph, GlyphGrid};
+use nate_engine::terminal::{Input, Key, TerminalRenderer};
 
 use rand::random;
 
-use crossterm::{
-    event::{self, KeyCode, KeyEventKind},
-    terminal::{
-        disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
-        LeaveAlternateScreen,
-    },
-    ExecutableCommand,
-};
-use ratatui::{
-    prelude::*,
-    widgets::{canvas::Canvas, Block, Borders},
-};
-
 const WIDTH: isize = 212;
 const MIN_X: isize = -106;
 const MAX_X: isize = 105;
@@ -33,15 +22,13 @@ const MAX_Y: isize = 24;
 
 const MAX_HEALTH: usize = 10;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Status {
-    Dead,
-    Low,
-    Medium,
-    High,
-}
+const DEAD: Glyph = Glyph {
+    symbol: '_',
+    fg: Color::DarkGray,
+    bg: Color::Black,
+};
 
-#[world(singular=[living_entities, canvas])]
+#[world(singular=[living_entities, canvas, input])]
 pub struct ToyWorld {
     position: (isize, isize),
     velocity: (isize, isize),
@@ -50,7 +37,11 @@ pub struct ToyWorld {
     health_changes: isize,
 
     living_entities: usize,
-    canvas: [[Status; WIDTH as usize]; HEIGHT as usize],
+    // Redrawn from the positions every frame, so it isn't saved
+    #[skip_save]
+    canvas: GlyphGrid,
+    #[skip_save]
+    input: Input,
 }
 
 #[system(world=ToyWorld, write=[position, velocity, acceleration])]
@@ -74,16 +65,40 @@ fn position_update_system() {
     *position = (position.0 + velocity.0, position.1 + velocity.1);
 }
 
-#[system(world=ToyWorld, read=[position, health], _write=[canvas=[[Status::Dead; WIDTH as usize]; HEIGHT as usize]])]
+#[system(world=ToyWorld, read=[position, health], _write=[canvas])]
 fn update_canvas_system() {
+    // The top row is the title
     let x = (position.0.clamp(MIN_X, MAX_X) + WIDTH / 2) as usize;
-    let y = (position.1.clamp(MIN_Y, MAX_Y) + HEIGHT / 2) as usize;
+    let y = (position.1.clamp(MIN_Y, MAX_Y) + HEIGHT / 2) as usize + 1;
+
+    let fg = match health {
+        7.. => Color::Green,
+        4..=6 => Color::Yellow,
+        1..=3 => Color::Red,
+        _ => return,
+    };
+    canvas.set((x, y), Glyph::new('X').with_fg(fg).with_bg(Color::Black));
+}
 
-    match health {
-        7.. => canvas[y][x] = Status::High,
-        4..=6 => canvas[y][x] = Status::Medium,
-        1..=3 => canvas[y][x] = Status::Low,
-        _ => (),
+#[system(world=ToyWorld, _read=[living_entities], _write=[canvas])]
+fn title_system() {
+    canvas.put_str((0, 0), &format!("Living Entities: {:<10} (c to clear, q to quit)", living_entities), Color::White);
+}
+
+#[system(world=ToyWorld, _write=[input, canvas])]
+fn input_system() {
+    for key in input.drain() {
+        if key == Key::Char('c') {
+            clear_canvas(canvas);
+        }
+    }
+}
+
+fn clear_canvas(canvas: &mut GlyphGrid) {
+    for y in 1..canvas.height() {
+        for x in 0..canvas.width() {
+            canvas.set((x, y), DEAD);
+        }
     }
 }
 
@@ -106,71 +121,6 @@ fn alive_entities_display_system() {
     *living_entities = world.health.read().unwrap().iter().map(|v| v.is_some() && v.unwrap() > 0).count();
 }
 
-pub struct ToyTerminalRenderer {
-    terminal: Terminal<CrosstermBackend<Stdout>>,
-}
-
-impl ToyTerminalRenderer {
-    pub fn new(terminal: Terminal<CrosstermBackend<Stdout>>) -> Self {
-        Self {
-            terminal,
-        }
-    }
-}
-
-impl Renderer<ToyWorld> for ToyTerminalRenderer {
-    type Error = String;
-
-    fn render(&mut self, world: std::sync::Arc<std::sync::RwLock<ToyWorld>>) -> std::prelude::v1::Result<(), Self::Error> {
-        let world = world.read().unwrap();
-
-        let _err = self.terminal.draw(|frame| {
-            let area = frame.size();
-            frame.render_widget(
-                Canvas::default()
-                    .block(
-                        Block::default()
-                        .borders(Borders::ALL)
-                        .title(
-                            format!(
-                                "Living Entities: {}",
-                                (*world.living_entities.read().unwrap()).unwrap(),
-                            )
-                        )
-                    )
-                    .background_color(Color::Black)
-                    .x_bounds([0.0, WIDTH as f64])
-                    .y_bounds([0.0, HEIGHT as f64])
-                    .paint(|ctx| {
-                        let canvas = (*world.canvas.read().unwrap()).unwrap();
-                        for (y, row) in canvas.iter().enumerate() {
-                            for (x, item) in row.iter().enumerate() {
-                                match *item {
-                                    Status::Dead => ctx.print(x as f64, y as f64, "_".gray()),
-                                    Status::Low => ctx.print(x as f64, y as f64, "X".red()),
-                                    Status::Medium => ctx.print(x as f64, y as f64, "X".yellow()),
-                                    Status::High => ctx.print(x as f64, y as f64, "X".green()),
-                                }
-                            }
-                        }
-                    }),
-                area
-            )
-        });
-
-        if event::poll(Duration::from_millis(5)).unwrap() {
-            if let event::Event::Key(key) = event::read().unwrap() {
-                if key.kind == KeyEventKind::Press &&
-                    key.code == KeyCode::Char('q') {
-                    return Err("Interrupt Pressed".into());
-                }
-            }
-        }
-
-        Ok(())
-    }
-}
-
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
@@ -189,31 +139,25 @@ fn main() -> Result<()> {
     let world = ToyWorld::new();
     {
         let mut world = world.write().unwrap();
-        let entity_ids = world.add_entities(args.entities);
-
-        let positions = entity_ids.iter().map(|_v| (random::<isize>().clamp(MIN_X, MAX_X), random::<isize>().clamp(MIN_Y, MAX_Y))).collect();
-        world.set_positions(&entity_ids, positions);
-
-        let velocities = entity_ids.iter().map(|_v| (0, 0)).collect();
-        world.set_velocitys(&entity_ids, velocities);
-
-        let accelerations = entity_ids.iter().map(|_v| (0, 0)).collect();
-        world.set_accelerations(&entity_ids, accelerations);
-
-        let healths = entity_ids.iter().map(|_v| random::<usize>() % 100).collect();
-        world.set_healths(&entity_ids, healths);
-
-        let health_changes = entity_ids.iter().map(|_v| 0).collect();
-        world.set_health_changess(&entity_ids, health_changes);
+        world.spawn_batch((0..args.entities).map(|_v| {
+            ToyWorldBundle::default()
+                .with_position((random::<isize>().clamp(MIN_X, MAX_X), random::<isize>().clamp(MIN_Y, MAX_Y)))
+                .with_velocity((0, 0))
+                .with_acceleration((0, 0))
+                .with_health(random::<usize>() % 100)
+                .with_health_changes(0)
+        }));
 
         world.set_living_entities(args.entities);
-        world.set_canvas([[Status::Dead; WIDTH as usize]; HEIGHT as usize]);
+        let mut canvas = GlyphGrid::new(WIDTH as usize, HEIGHT as usize + 1);
+        clear_canvas(&mut canvas);
+        world.set_canvas(canvas);
+        world.set_input(Input::default());
     }
 
-    stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-    terminal.clear()?;
+    // The terminal is restored when the engine stops running (or on panic), before run returns
+    let renderer = TerminalRenderer::new(|world: &ToyWorld| world.canvas.clone())?
+        .with_input(|world: &ToyWorld| world.input.clone());
 
     let mut engine = Engine::new(
         30,
@@ -224,14 +168,13 @@ fn main() -> Result<()> {
             (update_canvas_system, 100_000),
             (health_update_system, 100_000),
             (alive_entities_display_system, 100_000),
+            (title_system, 100_000),
+            (input_system, 30_000),
         ],
-        Box::new(ToyTerminalRenderer::new(terminal))
+        Box::new(renderer)
     );
 
     engine.run();
 
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-
     Ok(())
 }
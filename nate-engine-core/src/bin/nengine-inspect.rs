@@ -0,0 +1,155 @@
+//!
+//! Command Line Client of the Inspector
+//!
+//! Connects to an inspector on localhost and prints the answer to one command (run it
+//! without a command for the list of commands).
+//!
+
+use std::process::ExitCode;
+
+use nate_engine_core::inspector::{Client, Request, Response, DEFAULT_PORT};
+use nate_engine_core::metrics::MetricsSnapshot;
+
+const USAGE: &str = "Usage: nengine-inspect [--port PORT] COMMAND
+
+Commands:
+    components                          List the components of the world
+    entities [OFFSET] [LIMIT]           List entities and the components they have
+    entity ENTITY                       Show the components of an entity
+    singulars                           Show the singular components
+    set ENTITY COMPONENT VALUE          Set a component (or tag) of an entity
+    set-singular COMPONENT VALUE        Set a singular component
+    enable SYSTEM / disable SYSTEM      Enable or disable a system
+    metrics                             Show the engine's metrics
+    watch [INTERVAL_MS]                 Show the engine's metrics until interrupted";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut port = DEFAULT_PORT;
+    if args.first().map(|v| v.as_str()) == Some("--port") {
+        match args.get(1).and_then(|v| v.parse().ok()) {
+            Some(value) => port = value,
+            None => return usage(),
+        }
+        args.drain(..2);
+    }
+
+    let request = match parse_request(&args) {
+        Some(request) => request,
+        None => return usage(),
+    };
+
+    let mut client = match Client::connect(("127.0.0.1", port)) {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("Unable to connect to the inspector on port {}: {}", port, err);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let streaming = matches!(request, Request::StreamMetrics { .. });
+    let mut response = client.request(&request);
+    loop {
+        match response {
+            Ok(Response::Error { message }) => {
+                eprintln!("{}", message);
+                return ExitCode::FAILURE;
+            },
+            Ok(response) => print_response(&response),
+            Err(err) => {
+                eprintln!("Inspector error: {}", err);
+                return ExitCode::FAILURE;
+            },
+        }
+
+        if !streaming {
+            return ExitCode::SUCCESS;
+        }
+        response = client.next_response();
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::FAILURE
+}
+
+/// The request for the command line arguments
+fn parse_request(args: &[String]) -> Option<Request> {
+    let args: Vec<&str> = args.iter().map(|v| v.as_str()).collect();
+    let request = match args.as_slice() {
+        ["components"] => Request::Components,
+        ["entities"] => Request::Entities { offset: 0, limit: None },
+        ["entities", offset] => Request::Entities { offset: offset.parse().ok()?, limit: None },
+        ["entities", offset, limit] => Request::Entities { offset: offset.parse().ok()?, limit: Some(limit.parse().ok()?) },
+        ["entity", entity] => Request::Entity { entity: entity.parse().ok()? },
+        ["singulars"] => Request::Singulars,
+        ["set", entity, component, value] => Request::Set {
+            entity: entity.parse().ok()?,
+            component: component.to_string(),
+            value: value.to_string(),
+        },
+        ["set-singular", component, value] => Request::SetSingular {
+            component: component.to_string(),
+            value: value.to_string(),
+        },
+        ["enable", system] => Request::Toggle { system: system.to_string(), enabled: true },
+        ["disable", system] => Request::Toggle { system: system.to_string(), enabled: false },
+        ["metrics"] => Request::Metrics,
+        ["watch"] => Request::StreamMetrics { interval_ms: 1_000 },
+        ["watch", interval_ms] => Request::StreamMetrics { interval_ms: interval_ms.parse().ok()? },
+        _ => return None,
+    };
+    Some(request)
+}
+
+fn print_response(response: &Response) {
+    match response {
+        Response::Components { components } => {
+            for component in components {
+                println!("{:<20} {:<30} {:?}", component.name, component.type_name, component.kind);
+            }
+        },
+        Response::Entities { total, entities } => {
+            for entity in entities {
+                println!("{:>8}  {}", entity.entity, entity.components.join(", "));
+            }
+            println!("({} of {} entities)", entities.len(), total);
+        },
+        Response::Entity { entity, components } => {
+            println!("Entity {}", entity);
+            for component in components {
+                println!("    {}: {}", component.name, component.value.as_deref().unwrap_or("None"));
+            }
+        },
+        Response::Singulars { singulars } => {
+            for singular in singulars {
+                println!("{}: {}", singular.name, singular.value.as_deref().unwrap_or("None"));
+            }
+        },
+        Response::Metrics(metrics) => print_metrics(metrics),
+        Response::Ok => println!("Ok"),
+        Response::Error { message } => eprintln!("{}", message),
+    }
+}
+
+fn print_metrics(metrics: &MetricsSnapshot) {
+    println!(
+        "uptime {:.1}s{}, {} frames ({:.1} fps), last frame {}us",
+        metrics.uptime_micros as f64 / 1_000_000.0,
+        if metrics.paused { " (paused)" } else { "" },
+        metrics.frames,
+        metrics.frame_rate,
+        metrics.last_frame_micros,
+    );
+    for system in metrics.systems.iter() {
+        println!(
+            "    {:<30} {:<8} {:>8} runs {:>8}us last {:>8}us average",
+            system.name,
+            if system.enabled { "enabled" } else { "disabled" },
+            system.runs,
+            system.last_micros,
+            system.average_micros,
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//!
+//! Developer Console Running Text Commands Against a World Between Ticks
+//!
+//! Commands are queued with [`Console::submit`] (from an input thread or a renderer) and run
+//! by the engine between ticks, or run directly with [`Console::execute`]. Their output is kept
+//! in a [`ConsoleOutput`] buffer of lines for whatever renders the console.
+//!
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, RwLock};
+
+use crate::error::{ConsoleError, RegistryError};
+use crate::metrics::EngineMetrics;
+use crate::registry::{ComponentKind, Reflect};
+
+/// The number of lines a console keeps by default
+pub const DEFAULT_OUTPUT_LINES: usize = 1_000;
+
+/// The most entity ids `list` prints
+const LIST_LIMIT: usize = 100;
+
+/// The commands every console has, with their usage and help
+const BUILTIN_COMMANDS: &[(&str, &str, &str)] = &[
+    ("help", "help", "List the commands"),
+    ("list", "list [with COMPONENT...] [without COMPONENT...]", "List the entities with and without components or tags"),
+    ("get", "get ENTITY [COMPONENT] | get SINGULAR", "Show the components of an entity or a singular component"),
+    ("set", "set ENTITY COMPONENT VALUE | set SINGULAR VALUE", "Set a component (tags take true or false) or a singular component"),
+    ("spawn", "spawn [TAG | COMPONENT=VALUE]...", "Spawn an entity with tags and components"),
+    ("pause", "pause", "Pause the systems"),
+    ("resume", "resume", "Resume the systems"),
+    ("step", "step [TICKS]", "Run every system once (or TICKS times) while paused"),
+    ("enable", "enable SYSTEM", "Enable a system"),
+    ("disable", "disable SYSTEM", "Disable a system"),
+];
+
+/// A custom command, called with the world, the command's arguments and the console's output
+pub type Command<W> = Box<dyn Fn(&mut W, &[&str], &mut ConsoleOutput) -> Result<(), ConsoleError> + Send + Sync>;
+
+/// A registered custom command
+struct CustomCommand<W> {
+    usage: String,
+    help: String,
+    run: Command<W>,
+}
+
+/// The last lines written to a console
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsoleOutput {
+    lines: VecDeque<String>,
+    // The most lines kept, dropping the oldest lines past it
+    capacity: usize,
+}
+
+impl ConsoleOutput {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Write a line, dropping the oldest line if the output is full
+    pub fn push(&mut self, line: impl Into<String>) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        if self.capacity > 0 {
+            self.lines.push_back(line.into());
+        }
+    }
+
+    /// The lines from oldest to newest
+    pub fn lines(&self) -> impl DoubleEndedIterator<Item = &str> + '_ {
+        self.lines.iter().map(|v| v.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+/// A developer console with the built-in commands and the game's custom commands
+pub struct Console<W> {
+    // Custom commands by name, which replace built-in commands of the same name
+    commands: BTreeMap<String, CustomCommand<W>>,
+    // Commands waiting for the engine to run them
+    pending: Mutex<VecDeque<String>>,
+    output: Mutex<ConsoleOutput>,
+}
+
+impl<W> Default for Console<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> Console<W> {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_OUTPUT_LINES)
+    }
+
+    /// A console keeping the given number of lines of output
+    pub fn with_capacity(lines: usize) -> Self {
+        Self {
+            commands: BTreeMap::new(),
+            pending: Mutex::new(VecDeque::new()),
+            output: Mutex::new(ConsoleOutput::new(lines)),
+        }
+    }
+
+    /// Register a custom command, replacing any command of the same name
+    pub fn with_command(
+        mut self,
+        name: &str,
+        usage: &str,
+        help: &str,
+        run: impl Fn(&mut W, &[&str], &mut ConsoleOutput) -> Result<(), ConsoleError> + Send + Sync + 'static,
+    ) -> Self {
+        self.commands.insert(name.to_string(), CustomCommand {
+            usage: usage.to_string(),
+            help: help.to_string(),
+            run: Box::new(run),
+        });
+        self
+    }
+
+    /// Queue a command for the engine to run between ticks
+    pub fn submit(&self, line: &str) {
+        self.pending.lock().unwrap().push_back(line.to_string());
+    }
+
+    /// The console's output, locked while it is held
+    pub fn output(&self) -> MutexGuard<'_, ConsoleOutput> {
+        self.output.lock().unwrap()
+    }
+}
+
+impl<W: Reflect> Console<W> {
+    /// Run the queued commands, locking the world only if there are any
+    pub fn run_pending(&self, world: &RwLock<W>, metrics: &EngineMetrics) {
+        let pending: Vec<String> = self.pending.lock().unwrap().drain(..).collect();
+        if pending.is_empty() {
+            return;
+        }
+
+        let mut world = world.write().unwrap();
+        for line in pending {
+            // Errors are written to the output
+            let _ = self.execute(&line, &mut world, metrics);
+        }
+    }
+
+    /// Run a command now, writing it, its output and any error to the console's output
+    pub fn execute(&self, line: &str, world: &mut W, metrics: &EngineMetrics) -> Result<(), ConsoleError> {
+        let mut output = self.output.lock().unwrap();
+        output.push(format!("> {}", line));
+
+        let result = split_command(line).and_then(|words| {
+            let words: Vec<&str> = words.iter().map(|v| v.as_str()).collect();
+            match words.split_first() {
+                Some((name, args)) => self.run_command(name, args, world, metrics, &mut output),
+                None => Ok(()),
+            }
+        });
+
+        if let Err(err) = &result {
+            output.push(format!("error: {}", err));
+        }
+        result
+    }
+
+    fn run_command(&self, name: &str, args: &[&str], world: &mut W, metrics: &EngineMetrics, output: &mut ConsoleOutput) -> Result<(), ConsoleError> {
+        if let Some(command) = self.commands.get(name) {
+            return (command.run)(world, args, output);
+        }
+
+        let usage = || ConsoleError::Usage(BUILTIN_COMMANDS.iter().find(|v| v.0 == name).map_or("", |v| v.1).to_string());
+        match (name, args) {
+            ("help", []) => {
+                let mut commands: BTreeMap<&str, (&str, &str)> = BUILTIN_COMMANDS.iter().map(|v| (v.0, (v.1, v.2))).collect();
+                commands.extend(self.commands.iter().map(|(name, v)| (name.as_str(), (v.usage.as_str(), v.help.as_str()))));
+                for (usage, help) in commands.values() {
+                    output.push(format!("{:<50} {}", usage, help));
+                }
+            },
+            ("list", filters) => {
+                let (with, without) = list_filters::<W>(filters).ok_or_else(usage)?;
+                let entities: Vec<usize> = (0..world.entity_count())
+                    .filter(|entity| with.iter().all(|v| world.has_component(*entity, v)) && !without.iter().any(|v| world.has_component(*entity, v)))
+                    .collect();
+                let shown: Vec<String> = entities.iter().take(LIST_LIMIT).map(|v| v.to_string()).collect();
+                output.push(format!("{} entities: {}{}", entities.len(), shown.join(", "), if entities.len() > LIST_LIMIT { ", ..." } else { "" }));
+            },
+            ("get", [singular]) if singular.parse::<usize>().is_err() => {
+                let value = world.get_singular_string(singular)?;
+                output.push(format!("{}: {}", singular, value.as_deref().unwrap_or("None")));
+            },
+            ("get", [entity, components @ ..]) if components.len() <= 1 => {
+                let entity = parse_entity(world, entity).map_err(|err| err.unwrap_or_else(usage))?;
+                let names: Vec<&str> = match components {
+                    [] => W::registry().iter().filter(|v| v.kind != ComponentKind::Singular).map(|v| v.name).collect(),
+                    _ => components.to_vec(),
+                };
+                output.push(format!("entity {}", entity));
+                for name in names {
+                    match world.get_string(entity, name)? {
+                        Some(value) => output.push(format!("    {}: {}", name, value)),
+                        None if !components.is_empty() => output.push(format!("    {}: None", name)),
+                        None => {},
+                    }
+                }
+            },
+            ("set", [entity, component, value @ ..]) if !value.is_empty() && entity.parse::<usize>().is_ok() => {
+                let entity = parse_entity(world, entity).map_err(|err| err.unwrap_or_else(usage))?;
+                world.set_from_str(entity, component, &value.join(" "))?;
+            },
+            ("set", [singular, value @ ..]) if !value.is_empty() => {
+                world.set_singular_from_str(singular, &value.join(" "))?;
+            },
+            ("spawn", components) => {
+                // Every value is parsed before the entity is spawned so a typo doesn't leave a
+                // half-spawned entity
+                let mut values = Vec::with_capacity(components.len());
+                for component in components.iter() {
+                    let (name, value) = component.split_once('=').unwrap_or((*component, "true"));
+                    match W::registry().iter().find(|v| v.name == name) {
+                        Some(info) if info.kind == ComponentKind::Tag || (info.kind == ComponentKind::Entity && component.contains('=')) => {},
+                        Some(_) => return Err(usage()),
+                        None => return Err(RegistryError::UnknownComponent(name.to_string()).into()),
+                    }
+                    values.push((name, value));
+                }
+
+                let entity = world.spawn_from_strs(&values)?;
+                output.push(format!("spawned entity {}", entity));
+            },
+            ("pause", []) => metrics.set_paused(true),
+            ("resume", []) => metrics.set_paused(false),
+            ("step", []) => metrics.step(1),
+            ("step", [ticks]) => metrics.step(ticks.parse().map_err(|_| usage())?),
+            ("enable", [system]) | ("disable", [system]) => {
+                if !metrics.set_enabled(system, name == "enable") {
+                    return Err(ConsoleError::Command(format!("Unknown system {}", system)));
+                }
+            },
+            _ if BUILTIN_COMMANDS.iter().any(|v| v.0 == name) => return Err(usage()),
+            _ => return Err(ConsoleError::UnknownCommand(name.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// Split a command into words, keeping words in double quotes together
+pub fn split_command(line: &str) -> Result<Vec<String>, ConsoleError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    for character in line.chars() {
+        match character {
+            '"' => {
+                quoted = !quoted;
+                in_word = true;
+            },
+            character if character.is_whitespace() && !quoted => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            },
+            character => {
+                word.push(character);
+                in_word = true;
+            },
+        }
+    }
+
+    if quoted {
+        return Err(ConsoleError::UnclosedQuote);
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// The components after `with` and after `without` in the arguments of `list`
+fn list_filters<'a, W: Reflect>(args: &[&'a str]) -> Option<(Vec<&'a str>, Vec<&'a str>)> {
+    let (mut with, mut without) = (Vec::new(), Vec::new());
+    let mut current = None;
+    for arg in args.iter() {
+        match *arg {
+            "with" => current = Some(true),
+            "without" => current = Some(false),
+            name => {
+                let known = W::registry().iter().any(|v| v.name == name && v.kind != ComponentKind::Singular);
+                match current {
+                    Some(true) if known => with.push(name),
+                    Some(false) if known => without.push(name),
+                    _ => return None,
+                }
+            },
+        }
+    }
+    Some((with, without))
+}
+
+/// The entity id of an argument, with no error if the argument isn't an entity id
+fn parse_entity<W: Reflect>(world: &W, entity: &str) -> Result<usize, Option<ConsoleError>> {
+    match entity.parse() {
+        Ok(entity) if entity < world.entity_count() => Ok(entity),
+        Ok(entity) => Err(Some(RegistryError::InvalidEntity(entity).into())),
+        Err(_) => Err(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::registry::test_world::FakeWorld;
+
+    fn run(console: &Console<FakeWorld>, world: &mut FakeWorld, metrics: &EngineMetrics, line: &str) -> Result<(), ConsoleError> {
+        console.output().clear();
+        console.execute(line, world, metrics)
+    }
+
+    fn output(console: &Console<FakeWorld>) -> Vec<String> {
+        console.output().lines().skip(1).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_split_command_keeps_quotes_together() {
+        assert_eq!(split_command(r#"  set 3 name "big goblin" "#).unwrap(), vec!["set", "3", "name", "big goblin"]);
+        assert_eq!(split_command(r#"set 3 name """#).unwrap(), vec!["set", "3", "name", ""]);
+        assert_eq!(split_command(r#"set 3 name "goblin"#), Err(ConsoleError::UnclosedQuote));
+    }
+
+    #[test]
+    fn test_builtin_commands() {
+        let console = Console::new();
+        let mut world = FakeWorld::new(vec![Some(5), None, Some(1)]);
+        let metrics = EngineMetrics::new(["movement"]);
+
+        run(&console, &mut world, &metrics, "spawn dead health=7").unwrap();
+        assert_eq!(output(&console), vec!["spawned entity 3"]);
+        assert_eq!((world.health[3], world.dead[3]), (Some(7), true));
+        assert_eq!(run(&console, &mut world, &metrics, "spawn mana=3"), Err(ConsoleError::Registry(RegistryError::UnknownComponent(String::from("mana")))));
+        assert!(matches!(run(&console, &mut world, &metrics, "spawn dead health=lots"), Err(ConsoleError::Registry(RegistryError::Parse { .. }))));
+        assert_eq!(world.entity_count(), 4);
+
+        run(&console, &mut world, &metrics, "set 1 health 10").unwrap();
+        run(&console, &mut world, &metrics, "set 2 dead true").unwrap();
+        run(&console, &mut world, &metrics, "set tick 12").unwrap();
+        assert_eq!((world.health[1], world.dead[2], world.tick), (Some(10), true, Some(12)));
+        assert!(matches!(run(&console, &mut world, &metrics, "set 1 health lots"), Err(ConsoleError::Registry(RegistryError::Parse { .. }))));
+        assert_eq!(run(&console, &mut world, &metrics, "set 9 health 1"), Err(ConsoleError::Registry(RegistryError::InvalidEntity(9))));
+
+        run(&console, &mut world, &metrics, "list with health without dead").unwrap();
+        assert_eq!(output(&console), vec!["2 entities: 0, 1"]);
+        assert!(matches!(run(&console, &mut world, &metrics, "list with mana"), Err(ConsoleError::Usage(_))));
+
+        run(&console, &mut world, &metrics, "get 3").unwrap();
+        assert_eq!(output(&console), vec!["entity 3", "    health: 7", "    dead: ()"]);
+        run(&console, &mut world, &metrics, "get tick").unwrap();
+        assert_eq!(output(&console), vec!["tick: 12"]);
+
+        run(&console, &mut world, &metrics, "pause").unwrap();
+        run(&console, &mut world, &metrics, "step 5").unwrap();
+        run(&console, &mut world, &metrics, "disable movement").unwrap();
+        assert!(metrics.paused() && !metrics.systems[0].enabled());
+        assert_eq!(run(&console, &mut world, &metrics, "jump"), Err(ConsoleError::UnknownCommand(String::from("jump"))));
+        assert_eq!(console.output().lines().last(), Some("error: Unknown command jump (try help)"));
+    }
+
+    #[test]
+    fn test_custom_commands_and_pending_commands() {
+        let console = Console::new().with_command("heal", "heal ENTITY", "Restore an entity's health", |world: &mut FakeWorld, args, output| {
+            let entity: usize = args.first().and_then(|v| v.parse().ok()).ok_or_else(|| ConsoleError::Usage(String::from("heal ENTITY")))?;
+            world.health[entity] = Some(100);
+            output.push(format!("healed {}", entity));
+            Ok(())
+        });
+        let world = RwLock::new(FakeWorld::new(vec![Some(5)]));
+        let metrics = EngineMetrics::new([]);
+
+        console.submit("heal 0");
+        console.submit("heal");
+        assert_eq!(world.read().unwrap().health[0], Some(5));
+        console.run_pending(&world, &metrics);
+        assert_eq!(world.read().unwrap().health[0], Some(100));
+        assert_eq!(
+            console.output().lines().collect::<Vec<&str>>(),
+            vec!["> heal 0", "healed 0", "> heal", "error: Usage: heal ENTITY"],
+        );
+
+        // A custom command replaces the built-in command of the same name, in help as well
+        let console = Console::new().with_command("spawn", "spawn enemy at X Y", "Spawn an enemy", |world: &mut FakeWorld, args, output| {
+            let usage = || ConsoleError::Usage(String::from("spawn enemy at X Y"));
+            let (x, y): (u32, u32) = match args {
+                ["enemy", "at", x, y] => (x.parse().map_err(|_| usage())?, y.parse().map_err(|_| usage())?),
+                _ => return Err(usage()),
+            };
+            let entity = world.spawn_from_strs(&[("health", "10")])?;
+            output.push(format!("spawned enemy {} at ({}, {})", entity, x, y));
+            Ok(())
+        });
+        let mut world = world.into_inner().unwrap();
+        run(&console, &mut world, &metrics, "spawn enemy at 3 4").unwrap();
+        assert_eq!(output(&console), vec!["spawned enemy 1 at (3, 4)"]);
+        assert_eq!(world.health[1], Some(10));
+        assert_eq!(run(&console, &mut world, &metrics, "spawn dead"), Err(ConsoleError::Usage(String::from("spawn enemy at X Y"))));
+        run(&console, &mut world, &metrics, "help").unwrap();
+        assert!(output(&console).iter().any(|v| v.starts_with("spawn enemy at X Y ")));
+
+        let mut output = ConsoleOutput::new(2);
+        for line in ["a", "b", "c"] {
+            output.push(line);
+        }
+        assert_eq!(output.lines().collect::<Vec<&str>>(), vec!["b", "c"]);
+    }
+}
@@ -0,0 +1,151 @@
+//!
+//! Differences Between Two States of a World
+//!
+
+use crate::error::WorldError;
+use crate::hierarchy::Hierarchy;
+use crate::storage::ComponentStorage;
+
+/// How the entities of one state of a world line up with the entities of a later state
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntityAlignment {
+    // Entity ids (in the earlier state) of the entities that were removed
+    pub despawned: Vec<usize>,
+    // The entity list values of the entities added at the end of the later state
+    pub spawned: Vec<usize>,
+    // For every entity of the later state, its entity id in the earlier state (if it existed)
+    pub previous: Vec<Option<usize>>,
+}
+
+/// Line up the entity lists of two states of a world.
+///
+/// Removing an entity keeps the order of the others and new entities are added at the end, so
+/// entities of the later state are matched in order with entities of the earlier state with the
+/// same entity list value, the unmatched entities of the earlier state were removed and the
+/// rest of the later state was spawned.
+pub fn align_entities(before: &[usize], after: &[usize]) -> EntityAlignment {
+    let mut alignment = EntityAlignment {
+        previous: Vec::with_capacity(after.len()),
+        ..Default::default()
+    };
+
+    let mut matched = 0;
+    for (entity_id, value) in before.iter().enumerate() {
+        if after.get(matched) == Some(value) {
+            alignment.previous.push(Some(entity_id));
+            matched += 1;
+        } else {
+            alignment.despawned.push(entity_id);
+        }
+    }
+
+    alignment.spawned.extend_from_slice(&after[matched..]);
+    alignment.previous.resize(after.len(), None);
+    alignment
+}
+
+/// The components of the later column that differ from the earlier column, as
+/// (entity id in the later state, component or `None` if removed) pairs
+pub fn column_changes<T, S>(before: &S, after: &S, previous: &[Option<usize>]) -> Vec<(usize, Option<T>)>
+where
+    T: Clone + PartialEq,
+    S: ComponentStorage<T>,
+{
+    let mut changes = Vec::new();
+    for (entity_id, previous) in previous.iter().enumerate() {
+        let component = after.get(entity_id);
+        if component != previous.and_then(|v| before.get(v)) {
+            changes.push((entity_id, component.cloned()));
+        }
+    }
+    changes
+}
+
+/// The entities of the later hierarchy whose parent differs from the earlier hierarchy, as
+/// (entity id, parent or `None` if detached) pairs with both ids in the later state
+pub fn parent_changes(before: &Hierarchy, after: &Hierarchy, previous: &[Option<usize>]) -> Vec<(usize, Option<usize>)> {
+    let mut changes = Vec::new();
+    for (entity_id, previous_id) in previous.iter().enumerate() {
+        let parent = after.parent(entity_id);
+        // Parents are compared by their ids in the earlier state, new parents are always changes
+        let unchanged = match (parent, previous_id.and_then(|v| before.parent(v))) {
+            (None, None) => true,
+            (Some(parent), Some(previous_parent)) => previous.get(parent) == Some(&Some(previous_parent)),
+            _ => false,
+        };
+        if !unchanged {
+            changes.push((entity_id, parent));
+        }
+    }
+    changes
+}
+
+/// Check that replaying the parent changes of a delta leaves no cycles, by replaying the
+/// despawns (in descending order), spawns and parent changes on a copy of the hierarchy.
+///
+/// Worlds check this before applying a delta so a cycle is found before anything changed.
+pub fn check_parent_changes(hierarchy: &Hierarchy, despawned: &[usize], spawned: usize, parents: &[(usize, Option<usize>)]) -> Result<(), WorldError> {
+    let mut hierarchy = hierarchy.clone();
+    for entity_id in despawned.iter().rev() {
+        hierarchy.remove_entity(*entity_id);
+    }
+    hierarchy.reserve(spawned);
+    for _ in 0..spawned {
+        hierarchy.push_entity();
+    }
+
+    for (child, _) in parents.iter() {
+        hierarchy.remove_parent(*child);
+    }
+    for (child, parent) in parents.iter() {
+        if let Some(parent) = parent {
+            hierarchy.set_parent(*child, *parent)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_entities() {
+        let alignment = align_entities(&[0, 1, 2, 3, 4], &[0, 2, 3, 5, 6]);
+        assert_eq!(alignment.despawned, vec![1, 4]);
+        assert_eq!(alignment.spawned, vec![5, 6]);
+        assert_eq!(alignment.previous, vec![Some(0), Some(2), Some(3), None, None]);
+
+        let alignment = align_entities(&[0, 1], &[0, 1]);
+        assert!(alignment.despawned.is_empty() && alignment.spawned.is_empty());
+    }
+
+    #[test]
+    fn test_column_changes() {
+        let before = vec![Some(1), Some(2), None, Some(4)];
+        let after = vec![Some(1), None, Some(5), Some(9)];
+        let alignment = align_entities(&[0, 1, 2, 3], &[0, 2, 3, 4]);
+
+        // Entity 1 was removed, so the entities after it moved down and entity 3 was spawned
+        assert_eq!(column_changes(&before, &after, &alignment.previous), vec![(2, Some(5)), (3, Some(9))]);
+    }
+
+    #[test]
+    fn test_parent_changes() {
+        let mut before = Hierarchy::with_slots(4);
+        let mut after = Hierarchy::with_slots(5);
+        before.set_parent(2, 0).unwrap();
+        before.set_parent(3, 0).unwrap();
+        // Entity 1 was removed, so entities 2 and 3 are now 1 and 2 and entity 3 was spawned
+        after.remove_entity(1);
+        after.set_parent(1, 0).unwrap();
+        after.set_parent(2, 3).unwrap();
+        let alignment = align_entities(&[0, 1, 2, 3], &[0, 2, 3, 4]);
+
+        assert_eq!(parent_changes(&before, &after, &alignment.previous), vec![(2, Some(3))]);
+
+        // Entity 0 can't become a child of its own child once entity 1 is gone
+        assert_eq!(check_parent_changes(&before, &[1], 1, &[(2, Some(3))]), Ok(()));
+        assert_eq!(check_parent_changes(&before, &[1], 1, &[(0, Some(1))]), Err(WorldError::HierarchyCycle { child: 0, parent: 1 }));
+    }
+}
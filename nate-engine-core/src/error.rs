@@ -0,0 +1,314 @@
+//!
+//! Errors Returned by Operations on Generated Worlds
+//!
+
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+/// Error from operating on the entities of a world
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorldError {
+    /// The given entity ids do not refer to entities in the world
+    InvalidEntities(Vec<usize>),
+    /// The parent is the child or one of the child's descendants
+    HierarchyCycle { child: usize, parent: usize },
+    /// The entities would all have the same value of a component with a unique index
+    DuplicateUnique { component: String, entities: Vec<usize> },
+}
+
+impl Display for WorldError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorldError::InvalidEntities(entity_ids) => write!(f, "Invalid Entity Ids: {:?}", entity_ids),
+            WorldError::HierarchyCycle { child, parent } => write!(f, "Entity {} can't be the parent of entity {}, it is the entity or one of its descendants", parent, child),
+            WorldError::DuplicateUnique { component, entities } => write!(f, "Entities {:?} can't all have the same {} (it has a unique index)", entities, component),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+/// Error from saving or loading a world
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SaveError {
+    /// The save is of a different world
+    WrongWorld { expected: String, found: String },
+    /// The save has a newer schema version than the world supports
+    NewerVersion { version: u32, supported: u32 },
+    /// No migration is registered to upgrade saves from the version
+    MissingMigration(u32),
+    /// The save has a component the world doesn't have
+    UnknownComponent(String),
+    /// The world has a component the save doesn't have
+    MissingComponent(String),
+    /// A component's type in the save doesn't match its type in the world
+    ComponentTypeMismatch { component: String, expected: String, found: String },
+    /// The world couldn't be converted to or from the save's data
+    Format(String),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::WrongWorld { expected, found } => write!(f, "Save is of world {}, expected world {}", found, expected),
+            SaveError::NewerVersion { version, supported } => write!(f, "Save has schema version {}, but only versions up to {} are supported", version, supported),
+            SaveError::MissingMigration(version) => write!(f, "No migration registered from schema version {}", version),
+            SaveError::UnknownComponent(component) => write!(f, "Save has unknown component {}", component),
+            SaveError::MissingComponent(component) => write!(f, "Save is missing component {}", component),
+            SaveError::ComponentTypeMismatch { component, expected, found } => write!(f, "Component {} has type {} in the save, expected {}", component, found, expected),
+            SaveError::Format(err) => write!(f, "Invalid save data: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// Error from loading or spawning prefabs and scenes, naming the file, entity and field it
+/// happened in (where known)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefabError {
+    // The file the error is in
+    pub file: Option<PathBuf>,
+    // The prefab or scene entity the error is in
+    pub entity: Option<String>,
+    // The component field the error is in
+    pub field: Option<String>,
+    // What went wrong
+    pub kind: PrefabErrorKind,
+}
+
+/// What went wrong loading or spawning a prefab or scene
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrefabErrorKind {
+    /// The file couldn't be read
+    Io(String),
+    /// The file isn't a valid prefab or scene
+    Parse(String),
+    /// The file's extension isn't a supported format
+    UnsupportedFormat,
+    /// A scene entity uses a prefab the scene doesn't load
+    UnknownPrefab(String),
+    /// Two prefabs loaded by a scene have the same name
+    DuplicatePrefab(String),
+    /// The world has no per-entity component or tag with the field's name
+    UnknownComponent,
+    /// The field's value isn't a valid value of the component
+    InvalidComponent(String),
+    /// The world rejected the entity
+    World(WorldError),
+}
+
+impl PrefabError {
+    pub fn new(kind: PrefabErrorKind) -> Self {
+        Self {
+            file: None,
+            entity: None,
+            field: None,
+            kind,
+        }
+    }
+
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    pub fn with_entity(mut self, entity: impl Into<String>) -> Self {
+        self.entity = Some(entity.into());
+        self
+    }
+
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+}
+
+impl Display for PrefabError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        if let Some(entity) = &self.entity {
+            write!(f, "entity {}: ", entity)?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, "field {}: ", field)?;
+        }
+        match &self.kind {
+            PrefabErrorKind::Io(err) => write!(f, "Couldn't read file: {}", err),
+            PrefabErrorKind::Parse(err) => write!(f, "Invalid file: {}", err),
+            PrefabErrorKind::UnsupportedFormat => write!(f, "Unsupported format, expected a .json file (or a .ron file with the `ron` feature)"),
+            PrefabErrorKind::UnknownPrefab(prefab) => write!(f, "Unknown prefab {}", prefab),
+            PrefabErrorKind::DuplicatePrefab(prefab) => write!(f, "Prefab {} is loaded more than once", prefab),
+            PrefabErrorKind::UnknownComponent => write!(f, "Unknown component"),
+            PrefabErrorKind::InvalidComponent(err) => write!(f, "Invalid component: {}", err),
+            PrefabErrorKind::World(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for PrefabError {}
+
+/// Error from loading a Tiled map, naming the file it happened in (where known)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TilemapError {
+    // The map or tileset file
+    pub file: Option<PathBuf>,
+    pub kind: TilemapErrorKind,
+}
+
+/// The kinds of error from loading a Tiled map
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TilemapErrorKind {
+    /// The file couldn't be read
+    Io(String),
+    /// The file isn't a valid Tiled JSON map or tileset
+    Parse(String),
+    /// Infinite maps (made of chunks) aren't supported
+    InfiniteMap,
+    /// The layer's tiles are encoded (base64 or compressed) instead of a list of tile ids
+    EncodedLayer(String),
+    /// The layer doesn't have a tile for every cell of the map
+    WrongLayerSize(String),
+    /// An external tileset is used by a map that wasn't loaded from a file
+    ExternalTileset(String),
+    /// A tile's id plus its tileset's first id doesn't fit in a tile id
+    InvalidTileId { firstgid: u32, id: u32 },
+}
+
+impl TilemapError {
+    pub fn new(kind: TilemapErrorKind) -> Self {
+        Self {
+            file: None,
+            kind,
+        }
+    }
+
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+impl Display for TilemapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        match &self.kind {
+            TilemapErrorKind::Io(err) => write!(f, "Couldn't read file: {}", err),
+            TilemapErrorKind::Parse(err) => write!(f, "Invalid Tiled file: {}", err),
+            TilemapErrorKind::InfiniteMap => write!(f, "Infinite maps aren't supported"),
+            TilemapErrorKind::EncodedLayer(layer) => write!(f, "Layer {} is encoded, save the map with the CSV tile layer format", layer),
+            TilemapErrorKind::WrongLayerSize(layer) => write!(f, "Layer {} doesn't have a tile for every cell of the map", layer),
+            TilemapErrorKind::ExternalTileset(source) => write!(f, "External tileset {} can only be loaded with maps loaded from files", source),
+            TilemapErrorKind::InvalidTileId { firstgid, id } => write!(f, "Tile {} of the tileset starting at {} doesn't fit in a tile id", id, firstgid),
+        }
+    }
+}
+
+impl std::error::Error for TilemapError {}
+
+/// Error ending a terminal renderer's rendering (and so the engine)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TerminalError {
+    /// Drawing to or reading input from the terminal failed
+    Io(String),
+    /// The player pressed the quit key or Ctrl+C
+    Quit,
+    /// The program panicked, so the terminal was restored and rendering stopped
+    Panicked,
+}
+
+impl TerminalError {
+    /// Whether the error is the player quitting rather than a failure
+    pub fn is_quit(&self) -> bool {
+        matches!(self, TerminalError::Quit)
+    }
+}
+
+impl Display for TerminalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TerminalError::Io(err) => write!(f, "Terminal error: {}", err),
+            TerminalError::Quit => write!(f, "Quit by the player"),
+            TerminalError::Panicked => write!(f, "Stopped rendering after a panic"),
+        }
+    }
+}
+
+impl std::error::Error for TerminalError {}
+
+/// Error from accessing a world's components by name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegistryError {
+    /// The world has no component with the name (of the kind accessed)
+    UnknownComponent(String),
+    /// The entity id does not refer to an entity in the world
+    InvalidEntity(usize),
+    /// The component's type doesn't implement `FromStr`
+    NotFromStr(String),
+    /// The component's type doesn't implement `Debug`
+    NotDebug(String),
+    /// The string isn't a valid value of the component
+    Parse { component: String, error: String },
+    /// The world rejected the change
+    World(WorldError),
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::UnknownComponent(component) => write!(f, "Unknown component {}", component),
+            RegistryError::InvalidEntity(entity_id) => write!(f, "Invalid Entity Id: {}", entity_id),
+            RegistryError::NotFromStr(component) => write!(f, "Component {} can't be parsed from a string (it doesn't implement FromStr)", component),
+            RegistryError::NotDebug(component) => write!(f, "Component {} can't be formatted (it doesn't implement Debug)", component),
+            RegistryError::Parse { component, error } => write!(f, "Invalid value for component {}: {}", component, error),
+            RegistryError::World(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl From<WorldError> for RegistryError {
+    fn from(err: WorldError) -> Self {
+        RegistryError::World(err)
+    }
+}
+
+/// Errors running console commands
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsoleError {
+    /// The console has no command with the name
+    UnknownCommand(String),
+    /// The command was given the wrong arguments, with the command's usage
+    Usage(String),
+    /// A quote in the command isn't closed
+    UnclosedQuote,
+    /// Reading or writing a component by name failed
+    Registry(RegistryError),
+    /// A custom command failed
+    Command(String),
+}
+
+impl Display for ConsoleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsoleError::UnknownCommand(command) => write!(f, "Unknown command {} (try help)", command),
+            ConsoleError::Usage(usage) => write!(f, "Usage: {}", usage),
+            ConsoleError::UnclosedQuote => write!(f, "Unclosed quote"),
+            ConsoleError::Registry(err) => write!(f, "{}", err),
+            ConsoleError::Command(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConsoleError {}
+
+impl From<RegistryError> for ConsoleError {
+    fn from(err: RegistryError) -> Self {
+        ConsoleError::Registry(err)
+    }
+}
@@ -0,0 +1,170 @@
+//!
+//! Grids of Colored Characters for Drawing Worlds in the Terminal
+//!
+
+/// The color of a glyph's character or background
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Color {
+    // The terminal's default color
+    #[default]
+    Reset,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    DarkGray,
+    White,
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Parse a `#RRGGBB` or `#AARRGGBB` color (like Tiled's), ignoring the alpha
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        let rgb = match hex.len() {
+            6 => hex,
+            8 => &hex[2..],
+            _ => return None,
+        };
+        let channel = |v: usize| rgb.get(v..v + 2).and_then(|v| u8::from_str_radix(v, 16).ok());
+        Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// A character with its colors, one cell of a `GlyphGrid`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Glyph {
+    pub symbol: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Glyph {
+    /// A blank cell in the terminal's default colors
+    pub const EMPTY: Glyph = Glyph {
+        symbol: ' ',
+        fg: Color::Reset,
+        bg: Color::Reset,
+    };
+
+    pub fn new(symbol: char) -> Self {
+        Self {
+            symbol,
+            ..Self::EMPTY
+        }
+    }
+
+    pub fn with_fg(mut self, fg: Color) -> Self {
+        self.fg = fg;
+        self
+    }
+
+    pub fn with_bg(mut self, bg: Color) -> Self {
+        self.bg = bg;
+        self
+    }
+}
+
+impl Default for Glyph {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+/// A grid of glyphs (row by row from the top left) to draw a world onto, meant to be a singular
+/// component of the world
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlyphGrid {
+    width: usize,
+    height: usize,
+    glyphs: Vec<Glyph>,
+}
+
+impl GlyphGrid {
+    /// A grid of blank glyphs
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            glyphs: vec![Glyph::EMPTY; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The glyph at a cell (`None` outside the grid)
+    pub fn get(&self, cell: (usize, usize)) -> Option<&Glyph> {
+        if cell.0 < self.width && cell.1 < self.height {
+            Some(&self.glyphs[cell.1 * self.width + cell.0])
+        } else {
+            None
+        }
+    }
+
+    /// Draw a glyph at a cell, returning whether the cell is inside the grid (glyphs outside the
+    /// grid are dropped, so callers don't need to clip)
+    pub fn set(&mut self, cell: (usize, usize), glyph: Glyph) -> bool {
+        if cell.0 < self.width && cell.1 < self.height {
+            self.glyphs[cell.1 * self.width + cell.0] = glyph;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Write a string from a cell to the right, in the given color
+    pub fn put_str(&mut self, cell: (usize, usize), text: &str, fg: Color) {
+        for (offset, symbol) in text.chars().enumerate() {
+            self.set((cell.0 + offset, cell.1), Glyph::new(symbol).with_fg(fg));
+        }
+    }
+
+    /// Blank every cell
+    pub fn clear(&mut self) {
+        self.glyphs.fill(Glyph::EMPTY);
+    }
+
+    /// Resize the grid (to the terminal's size for example), blanking every cell
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = Self::new(width, height);
+    }
+
+    /// The rows of the grid from the top
+    pub fn rows(&self) -> impl Iterator<Item = &[Glyph]> {
+        // Empty grids have no rows (and chunks can't be empty)
+        self.glyphs.chunks(self.width.max(1)).take(self.height)
+    }
+}
+
+crate::state_hash_with_hash!(Color, Glyph, GlyphGrid);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drawing_clips_to_the_grid() {
+        let mut grid = GlyphGrid::new(4, 2);
+        assert!(grid.set((3, 1), Glyph::new('@')));
+        assert!(!grid.set((4, 0), Glyph::new('@')));
+        grid.put_str((2, 0), "hey", Color::Red);
+
+        let rows: Vec<String> = grid.rows().map(|v| v.iter().map(|v| v.symbol).collect()).collect();
+        assert_eq!(rows, vec!["  he", "   @"]);
+        assert_eq!(grid.get((3, 0)).unwrap().fg, Color::Red);
+        assert_eq!(Color::from_hex("#ff10a0b0"), Some(Color::Rgb(0x10, 0xa0, 0xb0)));
+    }
+}
@@ -0,0 +1,303 @@
+//!
+//! Deterministic Hashing of World State
+//!
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::hash::Hasher;
+
+use crate::storage::ComponentStorage;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a hasher whose hashes are the same across runs, platforms and Rust versions (integers
+/// are always hashed as little-endian bytes, and sizes as 64 bits)
+#[derive(Clone, Copy, Debug)]
+pub struct StateHasher {
+    state: u64,
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+/// Components that can be hashed into the state hash of a world.
+///
+/// Unlike `Hash` this is implemented for floats (by their bits). Types implementing `Hash` can
+/// use their `Hash` implementation with `state_hash_with_hash!`.
+pub trait StateHash {
+    fn state_hash(&self, hasher: &mut StateHasher);
+}
+
+/// Implement `StateHash` for types through their `Hash` implementation.
+///
+/// Strings inside such types are hashed the way `Hash for str` writes them, which the standard
+/// library doesn't promise to keep the same across Rust versions.
+#[macro_export]
+macro_rules! state_hash_with_hash {
+    ($($ty:ty),* $(,)?) => {
+        $(impl $crate::hash::StateHash for $ty {
+            fn state_hash(&self, hasher: &mut $crate::hash::StateHasher) {
+                std::hash::Hash::hash(self, hasher);
+            }
+        })*
+    };
+}
+
+state_hash_with_hash!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, bool, char, ());
+
+impl StateHash for str {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        // The length and bytes, as `Hash for str`'s encoding isn't specified
+        hasher.write_usize(self.len());
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl StateHash for String {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.as_str().state_hash(hasher);
+    }
+}
+
+impl StateHash for f32 {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        // All NaNs hash the same, as do 0.0 and -0.0
+        let value = if self.is_nan() { f32::NAN } else if *self == 0.0 { 0.0 } else { *self };
+        hasher.write_u32(value.to_bits());
+    }
+}
+
+impl StateHash for f64 {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        // All NaNs hash the same, as do 0.0 and -0.0
+        let value = if self.is_nan() { f64::NAN } else if *self == 0.0 { 0.0 } else { *self };
+        hasher.write_u64(value.to_bits());
+    }
+}
+
+impl<T: StateHash + ?Sized> StateHash for &T {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        (**self).state_hash(hasher);
+    }
+}
+
+impl<T: StateHash + ?Sized> StateHash for Box<T> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        (**self).state_hash(hasher);
+    }
+}
+
+impl<T: StateHash> StateHash for Option<T> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        match self {
+            Some(value) => {
+                hasher.write_u8(1);
+                value.state_hash(hasher);
+            },
+            None => hasher.write_u8(0),
+        }
+    }
+}
+
+impl<T: StateHash> StateHash for [T] {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_usize(self.len());
+        for value in self.iter() {
+            value.state_hash(hasher);
+        }
+    }
+}
+
+impl<T: StateHash, const N: usize> StateHash for [T; N] {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.as_slice().state_hash(hasher);
+    }
+}
+
+impl<T: StateHash> StateHash for Vec<T> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.as_slice().state_hash(hasher);
+    }
+}
+
+impl<T: StateHash> StateHash for VecDeque<T> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_usize(self.len());
+        for value in self.iter() {
+            value.state_hash(hasher);
+        }
+    }
+}
+
+impl<T: StateHash> StateHash for BTreeSet<T> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_usize(self.len());
+        for value in self.iter() {
+            value.state_hash(hasher);
+        }
+    }
+}
+
+impl<K: StateHash, V: StateHash> StateHash for BTreeMap<K, V> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        hasher.write_usize(self.len());
+        for (key, value) in self.iter() {
+            key.state_hash(hasher);
+            value.state_hash(hasher);
+        }
+    }
+}
+
+macro_rules! state_hash_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: StateHash),+> StateHash for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn state_hash(&self, hasher: &mut StateHasher) {
+                let ($($name,)+) = self;
+                $($name.state_hash(hasher);)+
+            }
+        }
+    };
+}
+
+state_hash_tuple!(A);
+state_hash_tuple!(A, B);
+state_hash_tuple!(A, B, C);
+state_hash_tuple!(A, B, C, D);
+state_hash_tuple!(A, B, C, D, E);
+state_hash_tuple!(A, B, C, D, E, F);
+state_hash_tuple!(A, B, C, D, E, F, G);
+state_hash_tuple!(A, B, C, D, E, F, G, H);
+
+/// Hash the components of a column in entity order, so the same components hash the same way
+/// whatever storage the column uses
+pub fn hash_column<T: StateHash, S: ComponentStorage<T>>(column: &S, hasher: &mut StateHasher) {
+    let mut entity_ids: Vec<usize> = column.entity_ids().collect();
+    entity_ids.sort_unstable();
+    hasher.write_usize(entity_ids.len());
+    for entity_id in entity_ids {
+        if let Some(component) = column.get(entity_id) {
+            hasher.write_usize(entity_id);
+            component.state_hash(hasher);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::storage::SparseSet;
+
+    fn hash<T: StateHash>(value: &T) -> u64 {
+        let mut hasher = StateHasher::new();
+        value.state_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_state_hash_is_stable() {
+        assert_eq!(hash(&0u64), hash(&0usize));
+        assert_eq!(hash(&0.0f32), hash(&-0.0f32));
+        assert_eq!(hash(&f64::NAN), hash(&-f64::NAN));
+        assert_ne!(hash(&(1, 2)), hash(&(2, 1)));
+        assert_ne!(hash(&vec![vec![1], vec![]]), hash(&vec![vec![], vec![1]]));
+        assert_eq!(hash(&String::from("a")), hash(&"a"));
+        // FNV-1a of a single zero byte, of 1 as little-endian bytes and of a length and bytes
+        assert_eq!(hash(&0u8), 0xaf63_bd4c_8601_b7df);
+        assert_eq!(hash(&1u32), 0xad2a_ca77_4798_5764);
+        assert_eq!(hash(&"ab"), 0x9c60_01d3_28a9_4690);
+    }
+
+    #[test]
+    fn test_hash_column_ignores_storage() {
+        let mut slots = Vec::new();
+        let mut set = SparseSet::new();
+        for entity_id in 0..10 {
+            let component = (entity_id % 3 == 0).then_some(entity_id as f32 * 0.5);
+            ComponentStorage::push_slot(&mut slots, component);
+            set.push_slot(None);
+        }
+        for entity_id in [9, 0, 6, 3] {
+            set.insert(entity_id, entity_id as f32 * 0.5);
+        }
+
+        let mut slots_hasher = StateHasher::new();
+        hash_column(&slots, &mut slots_hasher);
+        let mut set_hasher = StateHasher::new();
+        hash_column(&set, &mut set_hasher);
+        assert_eq!(slots_hasher.finish(), set_hasher.finish());
+    }
+}
@@ -0,0 +1,218 @@
+//!
+//! Parent and Child Relations Between the Entities of a World
+//!
+
+use crate::error::WorldError;
+
+/// The parent and children of every entity of a world, indexed by entity id like the world's
+/// components and kept consistent in both directions
+#[derive(Clone, Debug, Default)]
+pub struct Hierarchy {
+    // The parent of each entity
+    parents: Vec<Option<usize>>,
+    // The children of each entity in the order they were added
+    children: Vec<Vec<usize>>,
+    // Whether each entity was added or its parent changed since the last `clear_reparented`
+    reparented: Vec<bool>,
+}
+
+impl Hierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A hierarchy of `entities` entities without parents
+    pub fn with_slots(entities: usize) -> Self {
+        Self {
+            parents: vec![None; entities],
+            children: vec![Vec::new(); entities],
+            reparented: vec![true; entities],
+        }
+    }
+
+    /// The number of entity slots in the hierarchy
+    pub fn slots(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Add a slot for a new entity without a parent or children
+    pub fn push_entity(&mut self) {
+        self.parents.push(None);
+        self.children.push(Vec::new());
+        self.reparented.push(true);
+    }
+
+    /// Reserve space for at least `additional` more entity slots
+    pub fn reserve(&mut self, additional: usize) {
+        self.parents.reserve(additional);
+        self.children.reserve(additional);
+        self.reparented.reserve(additional);
+    }
+
+    /// Remove the entity's slot, detaching it from its parent and children (which are left
+    /// without a parent) and shifting the ids of every later entity down by one like the world's
+    /// other storages
+    pub fn remove_entity(&mut self, entity_id: usize) {
+        if entity_id >= self.slots() {
+            return;
+        }
+
+        self.remove_parent(entity_id);
+        for child in std::mem::take(&mut self.children[entity_id]) {
+            self.parents[child] = None;
+            self.reparented[child] = true;
+        }
+        self.parents.remove(entity_id);
+        self.children.remove(entity_id);
+        self.reparented.remove(entity_id);
+
+        let shift = |v: &mut usize| if *v > entity_id {
+            *v -= 1;
+        };
+        self.parents.iter_mut().flatten().for_each(shift);
+        self.children.iter_mut().flatten().for_each(shift);
+    }
+
+    /// Make an entity a child of another, returning its previous parent.
+    ///
+    /// Fails without changing anything if either entity doesn't exist or if the parent is the
+    /// child or one of its descendants.
+    pub fn set_parent(&mut self, child: usize, parent: usize) -> Result<Option<usize>, WorldError> {
+        let invalid_entity_ids: Vec<usize> = [child, parent].into_iter().filter(|v| *v >= self.slots()).collect();
+        if !invalid_entity_ids.is_empty() {
+            return Err(WorldError::InvalidEntities(invalid_entity_ids));
+        }
+        if parent == child || self.ancestors(parent).any(|v| v == child) {
+            return Err(WorldError::HierarchyCycle { child, parent });
+        }
+
+        let previous = self.remove_parent(child);
+        self.parents[child] = Some(parent);
+        self.children[parent].push(child);
+        self.reparented[child] = true;
+        Ok(previous)
+    }
+
+    /// Detach an entity from its parent, returning the parent
+    pub fn remove_parent(&mut self, child: usize) -> Option<usize> {
+        let parent = self.parents.get_mut(child)?.take()?;
+        self.children[parent].retain(|v| *v != child);
+        self.reparented[child] = true;
+        Some(parent)
+    }
+
+    /// The parent of an entity
+    pub fn parent(&self, entity_id: usize) -> Option<usize> {
+        self.parents.get(entity_id).copied().flatten()
+    }
+
+    /// The children of an entity in the order they were added
+    pub fn children(&self, entity_id: usize) -> &[usize] {
+        self.children.get(entity_id).map_or(&[], |v| v.as_slice())
+    }
+
+    /// The parent, grandparent, ... of an entity
+    pub fn ancestors(&self, entity_id: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.parent(entity_id), |v| self.parent(*v))
+    }
+
+    /// The children, grandchildren, ... of an entity, every entity before its children
+    pub fn descendants(&self, entity_id: usize) -> Vec<usize> {
+        let mut descendants = Vec::new();
+        let mut stack: Vec<usize> = self.children(entity_id).iter().rev().copied().collect();
+        while let Some(entity_id) = stack.pop() {
+            descendants.push(entity_id);
+            stack.extend(self.children(entity_id).iter().rev());
+        }
+        descendants
+    }
+
+    /// The entities without a parent
+    pub fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.parents.iter().enumerate().filter(|v| v.1.is_none()).map(|v| v.0)
+    }
+
+    /// The parent of every entity, indexed by entity id
+    pub fn parents(&self) -> &Vec<Option<usize>> {
+        &self.parents
+    }
+
+    /// Whether the entity was added or its parent changed since the last `clear_reparented`
+    pub fn reparented(&self, entity_id: usize) -> bool {
+        self.reparented.get(entity_id).copied().unwrap_or(false)
+    }
+
+    /// Forget which entities were added or had their parent changed
+    pub fn clear_reparented(&mut self) {
+        self.reparented.fill(false);
+    }
+
+    /// Every (child, parent) pair, ordered by child
+    pub fn relations(&self) -> Vec<(usize, usize)> {
+        self.parents.iter().enumerate().filter_map(|v| v.1.map(|parent| (v.0, parent))).collect()
+    }
+}
+
+impl PartialEq for Hierarchy {
+    fn eq(&self, other: &Self) -> bool {
+        self.parents == other.parents && self.children == other.children
+    }
+}
+
+impl Eq for Hierarchy {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_parent_keeps_both_directions() {
+        let mut hierarchy = Hierarchy::with_slots(5);
+        assert_eq!(hierarchy.set_parent(1, 0), Ok(None));
+        assert_eq!(hierarchy.set_parent(2, 0), Ok(None));
+        assert_eq!(hierarchy.set_parent(3, 1), Ok(None));
+        assert_eq!(hierarchy.set_parent(2, 4), Ok(Some(0)));
+
+        assert_eq!(hierarchy.children(0), &[1]);
+        assert_eq!(hierarchy.children(4), &[2]);
+        assert_eq!(hierarchy.descendants(0), vec![1, 3]);
+        assert_eq!(hierarchy.ancestors(3).collect::<Vec<usize>>(), vec![1, 0]);
+        assert_eq!(hierarchy.roots().collect::<Vec<usize>>(), vec![0, 4]);
+        assert_eq!(hierarchy.relations(), vec![(1, 0), (2, 4), (3, 1)]);
+
+        assert_eq!(hierarchy.set_parent(0, 3), Err(WorldError::HierarchyCycle { child: 0, parent: 3 }));
+        assert_eq!(hierarchy.set_parent(1, 1), Err(WorldError::HierarchyCycle { child: 1, parent: 1 }));
+        assert_eq!(hierarchy.set_parent(1, 7), Err(WorldError::InvalidEntities(vec![7])));
+        assert_eq!(hierarchy.parent(1), Some(0));
+    }
+
+    #[test]
+    fn test_remove_entity_shifts_ids() {
+        let mut hierarchy = Hierarchy::with_slots(5);
+        hierarchy.set_parent(1, 0).unwrap();
+        hierarchy.set_parent(4, 1).unwrap();
+        hierarchy.set_parent(3, 2).unwrap();
+
+        hierarchy.remove_entity(1);
+        assert_eq!(hierarchy.slots(), 4);
+        assert_eq!(hierarchy.children(0), &[] as &[usize]);
+        // Entity 4 is now entity 3 and lost its parent, entity 3 is now entity 2
+        assert_eq!(hierarchy.parent(3), None);
+        assert_eq!(hierarchy.parent(2), Some(1));
+        assert_eq!(hierarchy.children(1), &[2]);
+    }
+
+    #[test]
+    fn test_reparented() {
+        let mut hierarchy = Hierarchy::with_slots(3);
+        assert!(hierarchy.reparented(2));
+        hierarchy.clear_reparented();
+        hierarchy.set_parent(2, 0).unwrap();
+        assert!(!hierarchy.reparented(0) && hierarchy.reparented(2));
+
+        hierarchy.clear_reparented();
+        hierarchy.remove_entity(0);
+        // Entity 2 is now entity 1 and lost its parent
+        assert!(!hierarchy.reparented(0) && hierarchy.reparented(1));
+    }
+}
@@ -0,0 +1,282 @@
+//!
+//! Secondary Indexes from Component Values to the Entities With Them
+//!
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::RwLock;
+
+use crate::error::WorldError;
+use crate::storage::ComponentStorage;
+
+/// The entities with each value of a component, kept up to date by the world's methods for
+/// components marked `#[index]` or `#[unique_index]`
+#[derive(Clone, Debug)]
+pub struct ComponentIndex<T> {
+    // The name of the indexed component
+    name: &'static str,
+    // Whether at most one entity can have each value
+    unique: bool,
+    // The value each entity is indexed under
+    keys: Vec<Option<T>>,
+    // The entities with each value in the order they were indexed
+    entities: HashMap<T, Vec<usize>>,
+}
+
+impl<T: Clone + Eq + Hash> ComponentIndex<T> {
+    pub fn new(name: &'static str, unique: bool) -> Self {
+        Self {
+            name,
+            unique,
+            keys: Vec::new(),
+            entities: HashMap::new(),
+        }
+    }
+
+    /// Whether at most one entity can have each value
+    pub fn unique(&self) -> bool {
+        self.unique
+    }
+
+    /// The entities with the value
+    pub fn get<Q: Eq + Hash + ?Sized>(&self, value: &Q) -> &[usize]
+    where
+        T: Borrow<Q>,
+    {
+        self.entities.get(value).map_or(&[], |v| v.as_slice())
+    }
+
+    /// The number of distinct values
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Add a slot for a new entity, with its component if it has one (check unique values with
+    /// [`ComponentIndex::check`] first)
+    pub fn push_slot(&mut self, component: Option<&T>) {
+        self.keys.push(None);
+        if let Some(component) = component {
+            self.insert(self.keys.len() - 1, component);
+        }
+    }
+
+    pub fn reserve_slots(&mut self, additional: usize) {
+        self.keys.reserve(additional);
+    }
+
+    /// Check that the entity can be given the component's value, which fails if the index is
+    /// unique and another entity has the value
+    pub fn check(&self, entity_id: usize, component: &T) -> Result<(), WorldError> {
+        match self.get(component).iter().find(|v| self.unique && **v != entity_id) {
+            Some(other) => Err(self.duplicate(vec![entity_id.min(*other), entity_id.max(*other)])),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that no two entities would have the same value of a unique index after the
+    /// entities in `despawned` (sorted, in the current ids) are removed and the (entity id,
+    /// component) changes (in the ids after the removal) are made, so entities can swap values
+    pub fn check_changes<'a>(&self, despawned: &[usize], changes: impl IntoIterator<Item = (usize, Option<&'a T>)>) -> Result<(), WorldError>
+    where
+        T: 'a,
+    {
+        if !self.unique {
+            return Ok(());
+        }
+
+        // The last change of each entity is the one that counts
+        let changes: BTreeMap<usize, Option<&T>> = changes.into_iter().collect();
+        let mut holders: HashMap<&T, Vec<usize>> = HashMap::new();
+        for (entity_id, component) in changes.iter() {
+            if let Some(component) = component {
+                holders.entry(*component).or_default().push(*entity_id);
+            }
+        }
+
+        for component in changes.values().flatten() {
+            let mut entities = holders[component].clone();
+            for entity_id in self.get(*component).iter() {
+                if despawned.binary_search(entity_id).is_err() {
+                    let entity_id = entity_id - despawned.partition_point(|v| v < entity_id);
+                    if !changes.contains_key(&entity_id) {
+                        entities.push(entity_id);
+                    }
+                }
+            }
+
+            if entities.len() > 1 {
+                entities.sort_unstable();
+                return Err(self.duplicate(entities));
+            }
+        }
+        Ok(())
+    }
+
+    fn duplicate(&self, entities: Vec<usize>) -> WorldError {
+        WorldError::DuplicateUnique { component: self.name.to_string(), entities }
+    }
+
+    /// Index the entity under the component's value (instead of its previous value).
+    ///
+    /// Unique values aren't checked here (see [`ComponentIndex::check`]), a duplicate value is
+    /// indexed under every entity with it.
+    pub fn insert(&mut self, entity_id: usize, component: &T) {
+        if self.keys[entity_id].as_ref() == Some(component) {
+            return;
+        }
+
+        self.remove(entity_id);
+        self.entities.entry(component.clone()).or_default().push(entity_id);
+        self.keys[entity_id] = Some(component.clone());
+    }
+
+    /// Stop indexing the entity
+    pub fn remove(&mut self, entity_id: usize) {
+        let Some(key) = self.keys.as_mut_slice().get_mut(entity_id).and_then(|v| v.take()) else {
+            return;
+        };
+        if let Some(entities) = self.entities.get_mut(&key) {
+            entities.retain(|v| *v != entity_id);
+            if entities.is_empty() {
+                self.entities.remove(&key);
+            }
+        }
+    }
+
+    /// Remove the entity's slot, shifting the ids of every later entity down by one like the
+    /// world's storages
+    pub fn remove_slot(&mut self, entity_id: usize) {
+        if entity_id >= self.keys.len() {
+            return;
+        }
+
+        self.remove(entity_id);
+        self.keys.remove(entity_id);
+        for entity in self.entities.values_mut().flatten() {
+            if *entity > entity_id {
+                *entity -= 1;
+            }
+        }
+    }
+
+    /// Re-index the entities whose component changed without going through the index (for
+    /// example written by a system).
+    ///
+    /// The entities are indexed under their new values either way, if the index is unique and
+    /// some of them now share a value with another entity the entities with it are returned
+    /// in the error.
+    pub fn sync<S: ComponentStorage<T>>(&mut self, column: &S, entity_ids: impl IntoIterator<Item = usize>) -> Result<(), WorldError> {
+        let mut synced = Vec::new();
+        let slots = self.keys.len();
+        for entity_id in entity_ids.into_iter().filter(|v| *v < slots) {
+            match column.get(entity_id) {
+                Some(component) if self.keys[entity_id].as_ref() != Some(component) => {
+                    // Moving the entity out first lets two entities swap values
+                    self.remove(entity_id);
+                    self.entities.entry(component.clone()).or_default().push(entity_id);
+                    self.keys[entity_id] = Some(component.clone());
+                    synced.push(entity_id);
+                },
+                None => self.remove(entity_id),
+                _ => (),
+            }
+        }
+
+        match synced.into_iter().filter_map(|v| self.keys[v].as_ref()).find(|v| self.unique && self.get(*v).len() > 1) {
+            Some(component) => {
+                let mut entities = self.get(component).to_vec();
+                entities.sort_unstable();
+                Err(self.duplicate(entities))
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+/// Updates the indexes of the components a system wrote once dropped, so they are updated
+/// however the system's body returns, keeping the error (if any) in the world's `index_error`
+pub struct Reindex<'a, F: FnMut(&[usize]) -> Result<(), WorldError>> {
+    // The entities the system wrote
+    pub written: Vec<usize>,
+    reindex: F,
+    // Where the error updating the indexes is kept
+    error: &'a RwLock<Option<WorldError>>,
+}
+
+impl<'a, F: FnMut(&[usize]) -> Result<(), WorldError>> Reindex<'a, F> {
+    pub fn new(error: &'a RwLock<Option<WorldError>>, reindex: F) -> Self {
+        Self {
+            written: Vec::new(),
+            reindex,
+            error,
+        }
+    }
+}
+
+impl<F: FnMut(&[usize]) -> Result<(), WorldError>> Drop for Reindex<'_, F> {
+    fn drop(&mut self) {
+        // A panicking system leaves its columns poisoned, and panicking again here would abort
+        if std::thread::panicking() {
+            return;
+        }
+        if let Err(err) = (self.reindex)(&self.written) {
+            *self.error.write().unwrap() = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_reindexes_written_components() {
+        let mut index = ComponentIndex::new("name", true);
+        let mut column = vec![Some(String::from("player")), Some(String::from("enemy")), None];
+        for name in column.iter() {
+            index.push_slot(name.as_ref());
+        }
+        assert_eq!(index.get("player"), &[0]);
+
+        column.swap(0, 1);
+        column[2] = Some(String::from("boss"));
+        index.sync(&column, 0..3).unwrap();
+        assert_eq!(index.get("player"), &[1]);
+        assert_eq!(index.get("enemy"), &[0]);
+        assert_eq!(index.get("boss"), &[2]);
+
+        // Only the given entities are re-indexed, and a duplicate is reported after indexing it
+        column[0] = Some(String::from("boss"));
+        column[1] = Some(String::from("wizard"));
+        let err = WorldError::DuplicateUnique { component: String::from("name"), entities: vec![0, 2] };
+        assert_eq!(index.sync(&column, [0]), Err(err));
+        assert_eq!(index.get("boss"), &[2, 0]);
+        assert_eq!(index.get("player"), &[1]);
+    }
+
+    #[test]
+    fn test_unique_index_rejects_duplicates() {
+        let mut index = ComponentIndex::new("name", true);
+        for name in ["player", "goblin", "orc"] {
+            index.push_slot(Some(&name));
+        }
+        let duplicate = |entities| Err(WorldError::DuplicateUnique { component: String::from("name"), entities });
+
+        assert_eq!(index.check(3, &"player"), duplicate(vec![0, 3]));
+        assert_eq!(index.check(0, &"player"), Ok(()));
+
+        // Swapping values is fine, and so is taking the value of a removed entity (the orc is
+        // entity 1 once the goblin is removed)
+        assert_eq!(index.check_changes(&[], [(0, Some(&"goblin")), (1, Some(&"player"))]), Ok(()));
+        assert_eq!(index.check_changes(&[], [(0, Some(&"goblin"))]), duplicate(vec![0, 1]));
+        assert_eq!(index.check_changes(&[1], [(0, Some(&"goblin"))]), Ok(()));
+        assert_eq!(index.check_changes(&[1], [(2, Some(&"orc"))]), duplicate(vec![1, 2]));
+        assert_eq!(index.check_changes(&[], [(3, Some(&"troll")), (4, Some(&"troll"))]), duplicate(vec![3, 4]));
+        assert_eq!(index.get("player"), &[0]);
+    }
+}
@@ -0,0 +1,310 @@
+//!
+//! Inspector Server for Looking at and Changing a Running World over a Local Socket
+//!
+//! The inspector listens on a localhost TCP port and speaks newline delimited JSON: each line a
+//! client sends is a [`Request`] and the server answers each request with a [`Response`] line
+//! (or a line every interval for [`Request::StreamMetrics`]).
+//!
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::{EngineMetrics, MetricsSnapshot};
+use crate::registry::{ComponentKind, Reflect};
+use crate::error::RegistryError;
+
+/// The port inspectors usually listen on and clients connect to
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// A request to the inspector
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /// List the components of the world
+    Components,
+    /// List entities (starting at `offset`, at most `limit` of them) with the components they have
+    Entities {
+        #[serde(default)]
+        offset: usize,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// The `Debug` output of every component of an entity
+    Entity { entity: usize },
+    /// The `Debug` output of every singular component
+    Singulars,
+    /// Set a component of an entity from a string parsed with its `FromStr` implementation
+    /// (tags are set from `true` or `false`)
+    Set { entity: usize, component: String, value: String },
+    /// Set a singular component from a string parsed with its `FromStr` implementation
+    SetSingular { component: String, value: String },
+    /// Enable or disable a system by name
+    Toggle { system: String, enabled: bool },
+    /// The metrics of the engine
+    Metrics,
+    /// The metrics of the engine every `interval_ms` until the client disconnects
+    StreamMetrics { interval_ms: u64 },
+}
+
+/// A component of the world as listed by the inspector
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComponentEntry {
+    pub name: String,
+    pub type_name: String,
+    pub kind: ComponentKind,
+}
+
+/// An entity and the names of the components it has
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntityEntry {
+    pub entity: usize,
+    pub components: Vec<String>,
+}
+
+/// The `Debug` output of a component (or its type if it doesn't implement `Debug`), which is
+/// `None` for singular components that aren't set
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComponentValue {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+/// The inspector's answer to a request
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Components { components: Vec<ComponentEntry> },
+    Entities { total: usize, entities: Vec<EntityEntry> },
+    Entity { entity: usize, components: Vec<ComponentValue> },
+    Singulars { singulars: Vec<ComponentValue> },
+    Metrics(MetricsSnapshot),
+    /// The request succeeded without anything to return
+    Ok,
+    Error { message: String },
+}
+
+/// Inspector server running on background threads until the program exits
+pub struct Inspector {
+    address: SocketAddr,
+}
+
+impl Inspector {
+    /// Listen for inspector clients on the given localhost port (or a free port if it is 0)
+    pub fn start<W: Reflect + Send + Sync + 'static>(port: u16, world: Arc<RwLock<W>>, metrics: Arc<EngineMetrics>) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let address = listener.local_addr()?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let world = world.clone();
+                let metrics = metrics.clone();
+                // A client that fails only ends its own connection
+                thread::spawn(move || serve(stream, &world, &metrics));
+            }
+        });
+
+        Ok(Self { address })
+    }
+
+    /// The address the inspector listens on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+/// Answer a client's requests until it disconnects
+fn serve<W: Reflect>(stream: TcpStream, world: &RwLock<W>, metrics: &EngineMetrics) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<Request>(&line) {
+            Ok(Request::StreamMetrics { interval_ms }) => loop {
+                send(&mut writer, &Response::Metrics(metrics.snapshot()))?;
+                thread::sleep(Duration::from_millis(interval_ms.max(1)));
+            },
+            Ok(request) => send(&mut writer, &handle(request, world, metrics))?,
+            Err(err) => send(&mut writer, &Response::Error { message: format!("Invalid request: {}", err) })?,
+        }
+    }
+    Ok(())
+}
+
+/// Write a response as a line of JSON
+fn send(writer: &mut impl Write, response: &Response) -> io::Result<()> {
+    let mut line = serde_json::to_vec(response).map_err(io::Error::other)?;
+    line.push(b'\n');
+    writer.write_all(&line)
+}
+
+/// The inspector's answer to a request (other than streaming metrics)
+pub fn handle<W: Reflect>(request: Request, world: &RwLock<W>, metrics: &EngineMetrics) -> Response {
+    let result = match request {
+        Request::Components => Ok(Response::Components {
+            components: W::registry().iter().map(|v| ComponentEntry {
+                name: v.name.to_string(),
+                type_name: v.type_name.to_string(),
+                kind: v.kind,
+            }).collect(),
+        }),
+        Request::Entities { offset, limit } => {
+            let world = world.read().unwrap();
+            let total = world.entity_count();
+            let end = limit.map_or(total, |v| offset.saturating_add(v).min(total));
+            Ok(Response::Entities {
+                total,
+                entities: (offset..end).map(|entity| EntityEntry {
+                    entity,
+                    components: W::registry()
+                        .iter()
+                        .filter(|v| v.kind != ComponentKind::Singular && world.has_component(entity, v.name))
+                        .map(|v| v.name.to_string())
+                        .collect(),
+                }).collect(),
+            })
+        },
+        Request::Entity { entity } => {
+            let world = world.read().unwrap();
+            if entity >= world.entity_count() {
+                Err(RegistryError::InvalidEntity(entity))
+            } else {
+                component_values::<W>(false, |name| world.get_string(entity, name))
+                    .map(|components| Response::Entity { entity, components })
+            }
+        },
+        Request::Singulars => {
+            let world = world.read().unwrap();
+            component_values::<W>(true, |name| world.get_singular_string(name))
+                .map(|singulars| Response::Singulars { singulars })
+        },
+        Request::Set { entity, component, value } => world.write().unwrap()
+            .set_from_str(entity, &component, &value)
+            .map(|_| Response::Ok),
+        Request::SetSingular { component, value } => world.write().unwrap()
+            .set_singular_from_str(&component, &value)
+            .map(|_| Response::Ok),
+        Request::Toggle { system, enabled } => Ok(if metrics.set_enabled(&system, enabled) {
+            Response::Ok
+        } else {
+            Response::Error { message: format!("Unknown system {}", system) }
+        }),
+        Request::Metrics | Request::StreamMetrics { .. } => Ok(Response::Metrics(metrics.snapshot())),
+    };
+
+    result.unwrap_or_else(|err| Response::Error { message: err.to_string() })
+}
+
+/// The values of the singular components, or of the per-entity components and tags an entity
+/// has
+fn component_values<W: Reflect>(
+    singular: bool,
+    get: impl Fn(&str) -> Result<Option<String>, RegistryError>,
+) -> Result<Vec<ComponentValue>, RegistryError> {
+    let mut values = Vec::new();
+    for info in W::registry().iter().filter(|v| (v.kind == ComponentKind::Singular) == singular) {
+        let value = match get(info.name) {
+            Ok(value) => value,
+            Err(RegistryError::NotDebug(_)) => Some(format!("<{}>", info.type_name)),
+            Err(err) => return Err(err),
+        };
+        if value.is_some() || singular {
+            values.push(ComponentValue { name: info.name.to_string(), value });
+        }
+    }
+    Ok(values)
+}
+
+/// Client of an inspector server
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let writer = TcpStream::connect(address)?;
+        Ok(Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Send a request and wait for the (first) response
+    pub fn request(&mut self, request: &Request) -> io::Result<Response> {
+        let mut line = serde_json::to_vec(request).map_err(io::Error::other)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.next_response()
+    }
+
+    /// Wait for the next response, like the next metrics of a stream
+    pub fn next_response(&mut self) -> io::Result<Response> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "The inspector closed the connection"));
+        }
+        serde_json::from_str(&line).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::registry::test_world::FakeWorld;
+
+    #[test]
+    fn test_inspector_reads_and_writes_world() {
+        let world = Arc::new(RwLock::new(FakeWorld::new(vec![Some(5), None])));
+        let metrics = Arc::new(EngineMetrics::new(["movement"]));
+        let inspector = Inspector::start(0, world.clone(), metrics.clone()).unwrap();
+        let mut client = Client::connect(inspector.address()).unwrap();
+
+        let response = client.request(&Request::Entities { offset: 0, limit: None }).unwrap();
+        assert_eq!(response, Response::Entities {
+            total: 2,
+            entities: vec![
+                EntityEntry { entity: 0, components: vec![String::from("health")] },
+                EntityEntry { entity: 1, components: vec![] },
+            ],
+        });
+
+        assert_eq!(client.request(&Request::Set { entity: 1, component: String::from("health"), value: String::from("7") }).unwrap(), Response::Ok);
+        assert_eq!(world.read().unwrap().health[1], Some(7));
+        assert!(matches!(
+            client.request(&Request::Set { entity: 1, component: String::from("health"), value: String::from("lots") }).unwrap(),
+            Response::Error { .. }
+        ));
+
+        let response = client.request(&Request::Singulars).unwrap();
+        assert_eq!(response, Response::Singulars { singulars: vec![ComponentValue { name: String::from("tick"), value: None }] });
+
+        assert_eq!(client.request(&Request::Toggle { system: String::from("movement"), enabled: false }).unwrap(), Response::Ok);
+        assert!(!metrics.systems[0].enabled());
+        match client.request(&Request::StreamMetrics { interval_ms: 1 }).unwrap() {
+            Response::Metrics(snapshot) => assert!(!snapshot.systems[0].enabled),
+            response => panic!("Expected metrics, got {:?}", response),
+        }
+        assert!(matches!(client.next_response().unwrap(), Response::Metrics(_)));
+    }
+
+    #[test]
+    fn test_invalid_requests_get_errors() {
+        let world = RwLock::new(FakeWorld::new(vec![]));
+        let metrics = EngineMetrics::new([]);
+        assert_eq!(
+            handle(Request::Entity { entity: 4 }, &world, &metrics),
+            Response::Error { message: RegistryError::InvalidEntity(4).to_string() },
+        );
+        assert!(matches!(handle(Request::Toggle { system: String::from("physics"), enabled: true }, &world, &metrics), Response::Error { .. }));
+        assert_eq!(serde_json::to_string(&Request::Entity { entity: 4 }).unwrap(), r#"{"command":"entity","entity":4}"#);
+    }
+}
@@ -6,7 +6,7 @@ use std::sync::atomic::Ordering;
 use std::thread;
 use std::sync::{Arc, RwLock, atomic::AtomicBool};
 use std::collections::BinaryHeap;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::fmt::Debug;
 
 use threadpool::ThreadPool;
@@ -17,6 +17,41 @@ use system_wrapper::SystemWrapper;
 mod renderer;
 pub use renderer::Renderer;
 
+pub mod console;
+pub mod delta;
+pub mod error;
+pub mod glyph;
+pub mod hash;
+pub mod hierarchy;
+pub mod index;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod metrics;
+pub mod navigation;
+pub mod parallel;
+pub mod physics;
+pub mod registry;
+#[cfg(feature = "serde")]
+pub mod prefab;
+#[cfg(feature = "serde")]
+pub mod save;
+pub mod spatial;
+pub mod storage;
+#[cfg(feature = "tui")]
+pub mod terminal;
+pub mod tilemap;
+pub mod transform;
+
+/// Re-export of serde for worlds generated with saving and loading
+#[cfg(feature = "serde")]
+pub use serde;
+/// Re-export of serde_json, whose values hold the data of saved worlds
+#[cfg(feature = "serde")]
+pub use serde_json;
+
+/// Runs queued console commands against the world between ticks
+type ConsoleRunner<WORLD> = Box<dyn Fn(&RwLock<WORLD>, &metrics::EngineMetrics) + Send>;
+
 /// The basic Engine schedules systems to run at given time intervals in a
 /// a threadpool with a singular thread reserved for UI rendering at a given frame rate
 pub struct Engine<WORLD, E> {
@@ -31,6 +66,10 @@ pub struct Engine<WORLD, E> {
     pool: ThreadPool,
     // Scheduling Queue (Binary Heap)
     scheduling_queue: BinaryHeap<SystemWrapper<WORLD>>,
+    // Metrics of the systems and rendering
+    metrics: Arc<metrics::EngineMetrics>,
+    // Console whose commands run between ticks
+    console: Option<ConsoleRunner<WORLD>>,
 }
 
 impl<WORLD: Send + Sync + 'static, E: Debug + 'static> Engine<WORLD, E> {
@@ -48,13 +87,15 @@ impl<WORLD: Send + Sync + 'static, E: Debug + 'static> Engine<WORLD, E> {
         }
 
         let mut scheduling_queue = BinaryHeap::new();
-        for (system, update_rate) in systems.drain(..) {
+        for (index, (system, update_rate)) in systems.drain(..).enumerate() {
             scheduling_queue.push(SystemWrapper{
                 system,
+                index,
                 update_rate,
                 priority: update_rate,
             })
         }
+        let system_names: Vec<String> = (0..scheduling_queue.len()).map(|v| format!("system {}", v)).collect();
 
         Self {
             target_frame_rate: frame_rate,
@@ -62,9 +103,29 @@ impl<WORLD: Send + Sync + 'static, E: Debug + 'static> Engine<WORLD, E> {
             pool: ThreadPool::new(workers - 1),
             scheduling_queue,
             renderer: Some(renderer),
+            metrics: Arc::new(metrics::EngineMetrics::new(system_names.iter().map(|v| v.as_str()))),
+            console: None,
         }
     }
 
+    /// Name the systems (in the order they were given to the engine) in the engine's metrics,
+    /// which otherwise calls them "system 0", "system 1", ...
+    ///
+    /// This replaces the metrics, so it should be called before the metrics are shared.
+    pub fn with_system_names<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut names = names.into_iter();
+        let names: Vec<String> = (0..self.metrics.systems.len())
+            .map(|v| names.next().map_or_else(|| format!("system {}", v), String::from))
+            .collect();
+        self.metrics = Arc::new(metrics::EngineMetrics::new(names.iter().map(|v| v.as_str())));
+        self
+    }
+
+    /// The metrics of the engine, which are also used to enable and disable its systems
+    pub fn metrics(&self) -> Arc<metrics::EngineMetrics> {
+        self.metrics.clone()
+    }
+
     /// Run the Executor
     pub fn run(&mut self) {
         let start_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros();
@@ -81,6 +142,7 @@ impl<WORLD: Send + Sync + 'static, E: Debug + 'static> Engine<WORLD, E> {
         }).expect("Unable to Set Ctrl-C Handler");
 
         let mut renderer = self.renderer.take().unwrap();
+        let render_metrics = self.metrics.clone();
         let render_thread_handle = thread::spawn(move || {
             let mut last_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros();
             while running.load(Ordering::SeqCst) {
@@ -93,24 +155,48 @@ impl<WORLD: Send + Sync + 'static, E: Debug + 'static> Engine<WORLD, E> {
                     thread::sleep(Duration::from_micros(sleep_time as u64));
                 }
 
-                if let Err(err) = renderer.render(cc_world) {
+                let render_start = Instant::now();
+                let render_result = renderer.render(cc_world);
+                render_metrics.record_frame(render_start.elapsed());
+                if let Err(err) = render_result {
                     running.store(false, Ordering::SeqCst);
-                    // TODO: Add Logger
-                    println!("Error Occurred in Rendering: {:?}", err);
+                    // Release the renderer first so the error isn't printed into the terminal
+                    // it's drawing to
+                    renderer.finish();
+                    if !renderer.is_quit(&err) {
+                        // TODO: Add Logger
+                        println!("Error Occurred in Rendering: {:?}", err);
+                    }
                     return renderer;
                 }
 
                 last_time = current_time;
             }
-            return renderer
+            renderer.finish();
+            renderer
         });
 
         while c_running.load(Ordering::SeqCst) {
             if let (sleep_time, Some(mut system_wrapper)) = Self::get_next_job(&mut self.scheduling_queue, start_time) {
                 thread::sleep(Duration::from_micros(sleep_time as u64));
 
+                if let Some(console) = &self.console {
+                    console(&self.world, &self.metrics);
+                }
+
                 let c_world = self.world.clone();
-                self.pool.execute(move || (system_wrapper.system)(c_world));
+                let metrics = self.metrics.clone();
+                let (system, index) = (system_wrapper.system, system_wrapper.index);
+                // Parallel systems split their entities across the same pool
+                let pool = self.pool.clone();
+                self.pool.execute(move || {
+                    let system_metrics = &metrics.systems[index];
+                    if system_metrics.should_run(metrics.paused()) {
+                        let system_start = Instant::now();
+                        parallel::with_pool(&pool, || system(c_world));
+                        system_metrics.record_run(system_start.elapsed());
+                    }
+                });
                 system_wrapper.priority += system_wrapper.update_rate;
                 self.scheduling_queue.push(system_wrapper);
             }
@@ -139,6 +225,21 @@ impl<WORLD: Send + Sync + 'static, E: Debug + 'static> Engine<WORLD, E> {
     }
 }
 
+impl<WORLD: registry::Reflect + Send + Sync + 'static, E: Debug + 'static> Engine<WORLD, E> {
+    /// Run the console's queued commands against the world between ticks
+    pub fn with_console(mut self, console: Arc<console::Console<WORLD>>) -> Self {
+        self.console = Some(Box::new(move |world, metrics| console.run_pending(world, metrics)));
+        self
+    }
+
+    /// Start an inspector for the engine's world and metrics on the given localhost port (or a
+    /// free port if it is 0)
+    #[cfg(feature = "inspector")]
+    pub fn start_inspector(&self, port: u16) -> std::io::Result<inspector::Inspector> {
+        inspector::Inspector::start(port, self.world.clone(), self.metrics.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,6 +258,7 @@ mod tests {
         let mut scheduling_queue: BinaryHeap<SystemWrapper<FakeWorld>> = BinaryHeap::new();
         scheduling_queue.push(SystemWrapper{
             system: test,
+            index: 0,
             update_rate: 1_000_000,
             priority: 1_000_000,
         });
@@ -0,0 +1,211 @@
+//!
+//! Metrics of a Running Engine and Switches to Enable, Disable, Pause and Step its Systems
+//!
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Timings of a system the engine runs and whether it is enabled
+#[derive(Debug)]
+pub struct SystemMetrics {
+    // The name of the system
+    pub name: String,
+    // Whether the engine runs the system
+    enabled: AtomicBool,
+    // Runs left for the system while the engine is paused
+    steps: AtomicU64,
+    // The number of times the system has run
+    runs: AtomicU64,
+    // The time spent running the system (in us)
+    total_micros: AtomicU64,
+    // The time the last run of the system took (in us)
+    last_micros: AtomicU64,
+}
+
+impl SystemMetrics {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: AtomicBool::new(true),
+            steps: AtomicU64::new(0),
+            runs: AtomicU64::new(0),
+            total_micros: AtomicU64::new(0),
+            last_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether the engine runs the system
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Whether the system should run now, using up one of its steps if the engine is paused
+    pub fn should_run(&self, paused: bool) -> bool {
+        self.enabled() && (!paused || self.steps.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| v.checked_sub(1)).is_ok())
+    }
+
+    /// Record a run of the system
+    pub fn record_run(&self, duration: Duration) {
+        let micros = duration.as_micros() as u64;
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.last_micros.store(micros, Ordering::Relaxed);
+    }
+
+    /// The metrics of the system at this moment
+    pub fn snapshot(&self) -> SystemSnapshot {
+        let runs = self.runs.load(Ordering::Relaxed);
+        SystemSnapshot {
+            name: self.name.clone(),
+            enabled: self.enabled(),
+            runs,
+            last_micros: self.last_micros.load(Ordering::Relaxed),
+            average_micros: self.total_micros.load(Ordering::Relaxed).checked_div(runs).unwrap_or(0),
+        }
+    }
+}
+
+/// Metrics of an engine, shared between the engine's threads and anything watching it
+#[derive(Debug)]
+pub struct EngineMetrics {
+    // When the metrics started being recorded
+    started: Instant,
+    // Whether the systems only run when stepped
+    paused: AtomicBool,
+    // The number of frames rendered
+    frames: AtomicU64,
+    // The time rendering the last frame took (in us)
+    last_frame_micros: AtomicU64,
+    // The systems of the engine in the order they were given to the engine
+    pub systems: Vec<SystemMetrics>,
+}
+
+impl EngineMetrics {
+    pub fn new<'a>(system_names: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            started: Instant::now(),
+            paused: AtomicBool::new(false),
+            frames: AtomicU64::new(0),
+            last_frame_micros: AtomicU64::new(0),
+            systems: system_names.into_iter().map(SystemMetrics::new).collect(),
+        }
+    }
+
+    /// Record a rendered frame
+    pub fn record_frame(&self, duration: Duration) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        self.last_frame_micros.store(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// The metrics of a system by name
+    pub fn system(&self, name: &str) -> Option<&SystemMetrics> {
+        self.systems.iter().find(|v| v.name == name)
+    }
+
+    /// Enable or disable a system by name, returning whether the engine has the system.
+    ///
+    /// Disabled systems are still scheduled but skipped when their turn comes.
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        match self.system(name) {
+            Some(system) => {
+                system.enabled.store(enabled, Ordering::Relaxed);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Whether the systems only run when stepped
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume every system
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Let every system run `steps` more times while the engine is paused
+    pub fn step(&self, steps: u64) {
+        for system in self.systems.iter() {
+            system.steps.fetch_add(steps, Ordering::Relaxed);
+        }
+    }
+
+    /// The metrics of the engine at this moment
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let uptime = self.started.elapsed();
+        let frames = self.frames.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            paused: self.paused(),
+            uptime_micros: uptime.as_micros() as u64,
+            frames,
+            frame_rate: frames as f64 / uptime.as_secs_f64().max(f64::EPSILON),
+            last_frame_micros: self.last_frame_micros.load(Ordering::Relaxed),
+            systems: self.systems.iter().map(|v| v.snapshot()).collect(),
+        }
+    }
+}
+
+/// The metrics of a system at a moment
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SystemSnapshot {
+    pub name: String,
+    pub enabled: bool,
+    pub runs: u64,
+    pub last_micros: u64,
+    pub average_micros: u64,
+}
+
+/// The metrics of an engine at a moment
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricsSnapshot {
+    pub paused: bool,
+    // The time since the engine was created (in us)
+    pub uptime_micros: u64,
+    pub frames: u64,
+    // Frames rendered per second since the engine was created
+    pub frame_rate: f64,
+    pub last_frame_micros: u64,
+    pub systems: Vec<SystemSnapshot>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_metrics_average_runs() {
+        let metrics = EngineMetrics::new(["movement", "render_map"]);
+        metrics.systems[0].record_run(Duration::from_micros(10));
+        metrics.systems[0].record_run(Duration::from_micros(30));
+        assert!(metrics.set_enabled("render_map", false));
+        assert!(!metrics.set_enabled("physics", false));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.systems[0], SystemSnapshot {
+            name: String::from("movement"),
+            enabled: true,
+            runs: 2,
+            last_micros: 30,
+            average_micros: 20,
+        });
+        assert!(!snapshot.systems[1].enabled);
+        assert_eq!(snapshot.systems[1].average_micros, 0);
+    }
+
+    #[test]
+    fn test_paused_systems_run_their_steps() {
+        let metrics = EngineMetrics::new(["movement"]);
+        assert!(metrics.systems[0].should_run(metrics.paused()));
+
+        metrics.set_paused(true);
+        assert!(!metrics.systems[0].should_run(metrics.paused()));
+        metrics.step(2);
+        assert!(metrics.systems[0].should_run(metrics.paused()));
+        assert!(metrics.systems[0].should_run(metrics.paused()));
+        assert!(!metrics.systems[0].should_run(metrics.paused()));
+    }
+}
@@ -0,0 +1,388 @@
+//!
+//! Pathfinding on Tile Grids with A* and Cached Flow Fields
+//!
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+use crate::hash::{StateHash, StateHasher};
+
+/// The tiles an agent can step to from its tile
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Neighbours {
+    // Up, down, left and right
+    #[default]
+    Four,
+    // Also the diagonals, costing one and a half times the tile's cost (rounded down, so
+    // diagonals onto tiles costing 1 cost 1 like in most roguelikes) and not cutting the
+    // corners of blocked tiles
+    Eight,
+}
+
+crate::state_hash_with_hash!(Neighbours);
+
+/// A grid of tiles with the cost of stepping onto each tile (blocked tiles have no cost), meant
+/// to be a singular component of the world.
+///
+/// Flow fields are cached per goal and the cache is cleared whenever the grid changes, so
+/// systems only reading the grid share the fields.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavGrid {
+    width: usize,
+    height: usize,
+    // The cost of stepping onto each tile (row by row), at least 1
+    costs: Vec<Option<u32>>,
+    neighbours: Neighbours,
+    // Flow fields to each goal computed since the grid last changed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    fields: Mutex<HashMap<(usize, usize), Arc<FlowField>>>,
+}
+
+impl NavGrid {
+    /// A grid where every tile costs 1
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::from_costs(width, vec![Some(1); width * height])
+    }
+
+    /// A grid from the costs of its tiles row by row (costs below 1 are raised to 1).
+    ///
+    /// Panics if the number of costs isn't a multiple of the width.
+    pub fn from_costs(width: usize, costs: Vec<Option<u32>>) -> Self {
+        assert!(width > 0 && costs.len() % width == 0, "Navigation grid costs must fill whole rows of {} tiles", width);
+        Self {
+            width,
+            height: costs.len() / width,
+            costs: costs.into_iter().map(|v| v.map(|cost| cost.max(1))).collect(),
+            neighbours: Neighbours::Four,
+            fields: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_neighbours(mut self, neighbours: Neighbours) -> Self {
+        self.set_neighbours(neighbours);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn neighbours(&self) -> Neighbours {
+        self.neighbours
+    }
+
+    pub fn set_neighbours(&mut self, neighbours: Neighbours) {
+        self.neighbours = neighbours;
+        self.invalidate();
+    }
+
+    pub fn contains(&self, tile: (usize, usize)) -> bool {
+        tile.0 < self.width && tile.1 < self.height
+    }
+
+    /// The cost of stepping onto a tile (`None` if it is blocked or outside the grid)
+    pub fn cost(&self, tile: (usize, usize)) -> Option<u32> {
+        if self.contains(tile) {
+            self.costs[tile.1 * self.width + tile.0]
+        } else {
+            None
+        }
+    }
+
+    /// Set the cost of stepping onto a tile (`None` blocks it, costs below 1 are raised to 1),
+    /// clearing the cached flow fields if it changed.
+    ///
+    /// Panics if the tile is outside the grid.
+    pub fn set_cost(&mut self, tile: (usize, usize), cost: Option<u32>) {
+        assert!(self.contains(tile), "Tile {:?} is outside the {}x{} navigation grid", tile, self.width, self.height);
+        let cost = cost.map(|v| v.max(1));
+        let index = tile.1 * self.width + tile.0;
+        if self.costs[index] != cost {
+            self.costs[index] = cost;
+            self.invalidate();
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.fields.get_mut().unwrap().clear();
+    }
+
+    /// The number of flow fields cached since the grid last changed
+    pub fn cached_fields(&self) -> usize {
+        self.fields.lock().unwrap().len()
+    }
+
+    /// The tiles that can be stepped to from a tile with the cost of each step
+    fn steps(&self, tile: (usize, usize)) -> impl Iterator<Item = ((usize, usize), u32)> + '_ {
+        let (x, y) = (tile.0 as isize, tile.1 as isize);
+        let diagonal = self.neighbours == Neighbours::Eight;
+        [(0, -1), (-1, 0), (1, 0), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .into_iter()
+            .take(if diagonal { 8 } else { 4 })
+            .filter_map(move |(dx, dy)| {
+                let next = (usize::try_from(x + dx).ok()?, usize::try_from(y + dy).ok()?);
+                let cost = self.cost(next)?;
+                if dx != 0 && dy != 0 {
+                    // Diagonal steps can't squeeze between two blocked tiles or around a corner
+                    self.cost((next.0, tile.1))?;
+                    self.cost((tile.0, next.1))?;
+                    return Some((next, cost + cost / 2));
+                }
+                Some((next, cost))
+            })
+    }
+
+    /// A lower bound of the cost from one tile to another (every tile costs at least 1)
+    fn heuristic(&self, from: (usize, usize), to: (usize, usize)) -> u32 {
+        let (dx, dy) = (from.0.abs_diff(to.0) as u32, from.1.abs_diff(to.1) as u32);
+        match self.neighbours {
+            Neighbours::Four => dx + dy,
+            Neighbours::Eight => dx.max(dy),
+        }
+    }
+
+    /// The cheapest path from the start to the goal with A* (including both), or `None` if the
+    /// goal can't be reached
+    pub fn find_path(&self, start: (usize, usize), goal: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        self.cost(start)?;
+        self.cost(goal)?;
+
+        let index = |tile: (usize, usize)| tile.1 * self.width + tile.0;
+        let mut costs: Vec<Option<u32>> = vec![None; self.costs.len()];
+        let mut previous: Vec<Option<(usize, usize)>> = vec![None; self.costs.len()];
+        // (Estimated total cost, cost so far, tile), ties broken by the lowest tile
+        let mut open = BinaryHeap::new();
+        costs[index(start)] = Some(0);
+        open.push(Reverse((self.heuristic(start, goal), 0, (start.1, start.0))));
+        while let Some(Reverse((_, cost, (y, x)))) = open.pop() {
+            let tile = (x, y);
+            if tile == goal {
+                let mut path = vec![goal];
+                while let Some(tile) = previous[index(*path.last().unwrap())] {
+                    path.push(tile);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if costs[index(tile)] != Some(cost) {
+                continue;
+            }
+
+            for (next, step) in self.steps(tile) {
+                let next_cost = cost + step;
+                if !matches!(costs[index(next)], Some(v) if v <= next_cost) {
+                    costs[index(next)] = Some(next_cost);
+                    previous[index(next)] = Some(tile);
+                    open.push(Reverse((next_cost + self.heuristic(next, goal), next_cost, (next.1, next.0))));
+                }
+            }
+        }
+        None
+    }
+
+    /// The flow field to the goal, computed the first time it is asked for after the grid
+    /// changed
+    pub fn flow_field(&self, goal: (usize, usize)) -> Arc<FlowField> {
+        if let Some(field) = self.fields.lock().unwrap().get(&goal) {
+            return field.clone();
+        }
+
+        // The field is computed without holding the cache's lock so threads asking for other
+        // fields aren't held up, and if two threads compute the same field the first one is kept
+        let field = Arc::new(FlowField::new(self, goal));
+        self.fields.lock().unwrap().entry(goal).or_insert(field).clone()
+    }
+}
+
+impl Clone for NavGrid {
+    fn clone(&self) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            costs: self.costs.clone(),
+            neighbours: self.neighbours,
+            fields: Mutex::new(self.fields.lock().unwrap().clone()),
+        }
+    }
+}
+
+impl Debug for NavGrid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NavGrid")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("neighbours", &self.neighbours)
+            .field("cached_fields", &self.cached_fields())
+            .finish()
+    }
+}
+
+impl PartialEq for NavGrid {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.costs == other.costs && self.neighbours == other.neighbours
+    }
+}
+
+impl StateHash for NavGrid {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.width.state_hash(hasher);
+        self.costs.state_hash(hasher);
+        self.neighbours.state_hash(hasher);
+    }
+}
+
+/// The cost from every tile to a goal and the step to take towards it (a Dijkstra map), shared
+/// by every agent heading to the goal
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlowField {
+    width: usize,
+    goal: (usize, usize),
+    // The cost of the cheapest path from each tile to the goal
+    costs: Vec<Option<u32>>,
+    // The next tile on the cheapest path from each tile
+    next: Vec<Option<(usize, usize)>>,
+}
+
+impl FlowField {
+    /// Compute the flow field to the goal with Dijkstra's algorithm from the goal outwards
+    pub fn new(grid: &NavGrid, goal: (usize, usize)) -> Self {
+        let index = |tile: (usize, usize)| tile.1 * grid.width + tile.0;
+        let mut costs: Vec<Option<u32>> = vec![None; grid.costs.len()];
+        let mut next: Vec<Option<(usize, usize)>> = vec![None; grid.costs.len()];
+        let mut open = BinaryHeap::new();
+        if grid.cost(goal).is_some() {
+            costs[index(goal)] = Some(0);
+            open.push(Reverse((0, (goal.1, goal.0))));
+        }
+        while let Some(Reverse((cost, (y, x)))) = open.pop() {
+            let tile = (x, y);
+            if costs[index(tile)] != Some(cost) {
+                continue;
+            }
+
+            // Steps are reversible, with the cost of stepping back onto this tile
+            for (previous, _) in grid.steps(tile) {
+                let Some(step) = grid.steps(previous).find(|v| v.0 == tile).map(|v| v.1) else {
+                    continue;
+                };
+                let previous_cost = cost + step;
+                if !matches!(costs[index(previous)], Some(v) if v <= previous_cost) {
+                    costs[index(previous)] = Some(previous_cost);
+                    next[index(previous)] = Some(tile);
+                    open.push(Reverse((previous_cost, (previous.1, previous.0))));
+                }
+            }
+        }
+
+        Self {
+            width: grid.width,
+            goal,
+            costs,
+            next,
+        }
+    }
+
+    pub fn goal(&self) -> (usize, usize) {
+        self.goal
+    }
+
+    /// The cost of the cheapest path from the tile to the goal (`None` if it can't be reached)
+    pub fn cost(&self, tile: (usize, usize)) -> Option<u32> {
+        if tile.0 < self.width {
+            self.costs.get(tile.1 * self.width + tile.0).copied().flatten()
+        } else {
+            None
+        }
+    }
+
+    /// The tile to step to from the tile towards the goal (`None` at the goal or if it can't be
+    /// reached)
+    pub fn next(&self, tile: (usize, usize)) -> Option<(usize, usize)> {
+        if tile.0 < self.width {
+            self.next.get(tile.1 * self.width + tile.0).copied().flatten()
+        } else {
+            None
+        }
+    }
+
+    /// The tiles from the tile to the goal following the field (including both)
+    pub fn path(&self, from: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+        self.cost(from)?;
+        let mut path = vec![from];
+        while let Some(tile) = self.next(*path.last().unwrap()) {
+            path.push(tile);
+        }
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> NavGrid {
+        let costs = rows.iter()
+            .flat_map(|v| v.chars())
+            .map(|v| v.to_digit(10))
+            .collect();
+        NavGrid::from_costs(rows[0].len(), costs)
+    }
+
+    #[test]
+    fn test_find_path_avoids_walls_and_expensive_tiles() {
+        let grid = grid(&[
+            "1111",
+            "1#91",
+            "1#11",
+        ]);
+        assert_eq!(grid.find_path((0, 2), (2, 2)), Some(vec![(0, 2), (0, 1), (0, 0), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2), (2, 2)]));
+        assert_eq!(grid.find_path((0, 0), (1, 1)), None);
+
+        let grid = grid.with_neighbours(Neighbours::Eight);
+        // Diagonal steps can't cut the wall's corner
+        assert_eq!(grid.find_path((0, 1), (2, 0)), Some(vec![(0, 1), (0, 0), (1, 0), (2, 0)]));
+        assert_eq!(grid.find_path((2, 2), (3, 1)), Some(vec![(2, 2), (3, 1)]));
+    }
+
+    #[test]
+    fn test_flow_field_matches_find_path() {
+        let grid = grid(&[
+            "11111",
+            "1###1",
+            "11121",
+        ]);
+        let field = grid.flow_field((4, 2));
+        assert_eq!(field.cost((0, 2)), Some(5));
+        assert_eq!(field.cost((1, 1)), None);
+        assert_eq!(field.next((4, 2)), None);
+        for start in [(0, 0), (0, 2), (2, 0)] {
+            let path = field.path(start).unwrap();
+            let cost: u32 = path[1..].iter().map(|v| grid.cost(*v).unwrap()).sum();
+            assert_eq!(Some(cost), field.cost(start));
+            assert_eq!(path.len(), grid.find_path(start, (4, 2)).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_changing_the_grid_clears_cached_fields() {
+        let mut grid = NavGrid::new(3, 3);
+        let field = grid.flow_field((2, 2));
+        assert!(Arc::ptr_eq(&field, &grid.flow_field((2, 2))));
+        grid.flow_field((0, 0));
+        assert_eq!(grid.cached_fields(), 2);
+
+        // Setting a tile to the cost it already has keeps the fields
+        grid.set_cost((1, 1), Some(1));
+        assert_eq!(grid.cached_fields(), 2);
+        grid.set_cost((1, 1), None);
+        assert_eq!(grid.cached_fields(), 0);
+        assert_eq!(grid.flow_field((2, 2)).next((1, 0)), Some((2, 0)));
+    }
+}
@@ -0,0 +1,201 @@
+//!
+//! Chunked Execution of Parallel Systems Across Worker Threads
+//!
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ops::AddAssign;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+pub use threadpool::ThreadPool;
+
+/// The smallest chunk handed to a worker when the chunk size is picked automatically
+const MIN_CHUNK_SIZE: usize = 64;
+
+thread_local! {
+    /// The pool parallel systems running on this thread split their entities across
+    static POOL: RefCell<Option<ThreadPool>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with the pool parallel systems called inside it split their entities across.
+///
+/// The engine runs every system inside its own pool, systems called outside of an engine
+/// without a pool run their chunks one after another on the calling thread.
+pub fn with_pool<R>(pool: &ThreadPool, f: impl FnOnce() -> R) -> R {
+    /// Puts the previous pool back however `f` returns
+    struct Restore(Option<ThreadPool>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            POOL.with(|v| *v.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(POOL.with(|v| v.borrow_mut().replace(pool.clone())));
+    f()
+}
+
+/// The number of threads parallel systems on this thread split their entities across (the
+/// pool's workers and the calling thread)
+pub fn workers() -> usize {
+    POOL.with(|v| v.borrow().as_ref().map_or(1, |pool| pool.max_count() + 1))
+}
+
+/// The chunk size that gives every worker a few chunks of the entities to balance the load
+pub fn chunk_size(entities: usize) -> usize {
+    entities.div_ceil(workers() * 4).max(MIN_CHUNK_SIZE)
+}
+
+/// State shared between the thread running a parallel system and the pool's workers helping it
+struct Chunks {
+    // The chunk work of the system, only valid while the system waits for its chunks
+    work: *const (dyn Fn(usize) + Sync),
+    // The number of chunks
+    count: usize,
+    // The index of the next chunk to hand out
+    next: AtomicUsize,
+    // The number of chunks that are finished
+    done: Mutex<usize>,
+    // Signalled when the last chunk finishes
+    finished: Condvar,
+    // The panic of the first chunk that panicked
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+// SAFETY: `work` is only called while the thread that owns it waits in `for_each_chunk`, and
+// the work itself is `Sync`
+unsafe impl Send for Chunks {}
+unsafe impl Sync for Chunks {}
+
+impl Chunks {
+    /// Run chunks until none are left to hand out
+    fn help(&self) {
+        loop {
+            let index = self.next.fetch_add(1, Ordering::Relaxed);
+            if index >= self.count {
+                return;
+            }
+
+            // SAFETY: the chunk isn't marked done until it returns, and the owning thread
+            // doesn't return before every chunk is done
+            let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe { (*self.work)(index) }));
+            if let Err(err) = result {
+                self.panic.lock().unwrap().get_or_insert(err);
+            }
+
+            let mut done = self.done.lock().unwrap();
+            *done += 1;
+            if *done == self.count {
+                self.finished.notify_all();
+            }
+        }
+    }
+}
+
+/// Run `f` on every chunk of the entity ids across the pool of the current thread (see
+/// [`with_pool`]), returning the result of each chunk in chunk order.
+///
+/// The calling thread works through chunks alongside the pool's workers, and chunks are handed
+/// out one at a time so workers that finish early pick up the remaining chunks. Workers that
+/// only get to the system once every chunk is handed out return straight away, so a busy pool
+/// never holds the system up.
+pub fn for_each_chunk<R, F>(entity_ids: &[usize], chunk_size: usize, f: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(&[usize]) -> R + Sync,
+{
+    let chunks: Vec<&[usize]> = entity_ids.chunks(chunk_size.max(1)).collect();
+    let pool = POOL.with(|v| v.borrow().clone());
+    let helpers = pool.as_ref().map_or(0, |v| v.max_count()).min(chunks.len().saturating_sub(1));
+    let (Some(pool), true) = (pool, helpers > 0) else {
+        return chunks.into_iter().map(f).collect();
+    };
+
+    let results: Vec<Mutex<Option<R>>> = chunks.iter().map(|_v| Mutex::new(None)).collect();
+    let work = |index: usize| {
+        let result = f(chunks[index]);
+        *results[index].lock().unwrap() = Some(result);
+    };
+    let work: &(dyn Fn(usize) + Sync) = &work;
+    let shared = Arc::new(Chunks {
+        // SAFETY: only the lifetime is erased, and this function waits for every chunk before
+        // `work` goes out of scope
+        work: unsafe { std::mem::transmute::<&(dyn Fn(usize) + Sync), &'static (dyn Fn(usize) + Sync)>(work) },
+        count: chunks.len(),
+        next: AtomicUsize::new(0),
+        done: Mutex::new(0),
+        finished: Condvar::new(),
+        panic: Mutex::new(None),
+    });
+
+    for _ in 0..helpers {
+        let shared = shared.clone();
+        pool.execute(move || shared.help());
+    }
+    shared.help();
+
+    let mut done = shared.done.lock().unwrap();
+    while *done < shared.count {
+        done = shared.finished.wait(done).unwrap();
+    }
+    drop(done);
+
+    if let Some(err) = shared.panic.lock().unwrap().take() {
+        panic::resume_unwind(err);
+    }
+    results.into_iter().map(|v| v.into_inner().unwrap().unwrap()).collect()
+}
+
+/// A default value of the same type as `value`, used to start each chunk's reduction
+pub fn default_like<T: Default>(_value: &T) -> T {
+    T::default()
+}
+
+/// The default reduction of a chunk's value into the total
+pub fn add_assign<T: AddAssign>(total: &mut T, value: T) {
+    *total += value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_each_chunk_keeps_chunk_order() {
+        let entity_ids: Vec<usize> = (0..10_000).collect();
+        let sums = for_each_chunk(&entity_ids, 100, |chunk| chunk.iter().sum::<usize>());
+
+        assert_eq!(workers(), 1);
+        assert_eq!(sums.len(), 100);
+        assert_eq!(sums[0], (0..100).sum::<usize>());
+        assert_eq!(sums[99], (9_900..10_000).sum::<usize>());
+        assert_eq!(sums.iter().sum::<usize>(), entity_ids.iter().sum::<usize>());
+    }
+
+    #[test]
+    fn test_for_each_chunk_runs_on_the_pool() {
+        let pool = ThreadPool::new(3);
+        let entity_ids: Vec<usize> = (0..10_000).collect();
+        let threads = Mutex::new(std::collections::HashSet::new());
+        let sums = with_pool(&pool, || {
+            assert_eq!(workers(), 4);
+            for_each_chunk(&entity_ids, 10, |chunk| {
+                threads.lock().unwrap().insert(std::thread::current().id());
+                std::thread::sleep(std::time::Duration::from_micros(50));
+                chunk.iter().sum::<usize>()
+            })
+        });
+
+        assert_eq!(workers(), 1);
+        assert_eq!(sums.iter().sum::<usize>(), entity_ids.iter().sum::<usize>());
+        assert!(threads.lock().unwrap().len() > 1);
+
+        // A panicking chunk is resumed on the calling thread once every chunk is done
+        let result = panic::catch_unwind(|| with_pool(&pool, || for_each_chunk(&entity_ids, 10, |chunk| assert!(chunk[0] != 5_000))));
+        assert!(result.is_err());
+        assert_eq!(pool.panic_count(), 0);
+    }
+}
@@ -0,0 +1,458 @@
+//!
+//! Fixed Step 2D Motion and Axis-Aligned Box Collisions
+//!
+
+use std::collections::BTreeSet;
+
+use crate::hash::{StateHash, StateHasher};
+use crate::storage::ComponentStorage;
+use crate::transform::{LocalTransform, Transform2D};
+
+/// Position components that physics can move
+pub trait PhysicsPosition {
+    fn position(&self) -> (f32, f32);
+
+    fn set_position(&mut self, position: (f32, f32));
+}
+
+impl PhysicsPosition for (f32, f32) {
+    fn position(&self) -> (f32, f32) {
+        *self
+    }
+
+    fn set_position(&mut self, position: (f32, f32)) {
+        *self = position;
+    }
+}
+
+impl PhysicsPosition for [f32; 2] {
+    fn position(&self) -> (f32, f32) {
+        (self[0], self[1])
+    }
+
+    fn set_position(&mut self, position: (f32, f32)) {
+        *self = [position.0, position.1];
+    }
+}
+
+impl PhysicsPosition for Transform2D {
+    fn position(&self) -> (f32, f32) {
+        self.position
+    }
+
+    fn set_position(&mut self, position: (f32, f32)) {
+        self.position = position;
+    }
+}
+
+/// Moving a local transform marks it, so the move is propagated to the entity's children
+impl PhysicsPosition for LocalTransform<Transform2D> {
+    fn position(&self) -> (f32, f32) {
+        self.position
+    }
+
+    fn set_position(&mut self, position: (f32, f32)) {
+        self.position = position;
+    }
+}
+
+/// The velocity of an entity (in units per second)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Velocity(pub f32, pub f32);
+
+/// The acceleration of an entity (in units per second squared), on top of gravity for dynamic
+/// bodies
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Acceleration(pub f32, pub f32);
+
+/// An axis-aligned box around an entity's position that collides with the boxes of other
+/// entities
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Collider {
+    // Half the width and height of the box
+    pub half_extents: (f32, f32),
+    // Offset of the center of the box from the entity's position
+    pub offset: (f32, f32),
+    // Sensors report collisions without pushing or being pushed
+    pub sensor: bool,
+}
+
+impl Collider {
+    /// A box of the given width and height centered on the entity's position
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            half_extents: (width / 2.0, height / 2.0),
+            offset: (0.0, 0.0),
+            sensor: false,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: (f32, f32)) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Report collisions with the box without pushing or being pushed
+    pub fn sensor(mut self) -> Self {
+        self.sensor = true;
+        self
+    }
+
+    /// The box around an entity at the position
+    pub fn aabb(&self, position: (f32, f32)) -> Aabb {
+        let center = (position.0 + self.offset.0, position.1 + self.offset.1);
+        Aabb {
+            min: (center.0 - self.half_extents.0, center.1 - self.half_extents.1),
+            max: (center.0 + self.half_extents.0, center.1 + self.half_extents.1),
+        }
+    }
+}
+
+/// How an entity responds to collisions (entities without a rigid body move like kinematic
+/// bodies)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RigidBody {
+    // Falls with gravity and is pushed out of the bodies it collides with
+    #[default]
+    Dynamic,
+    // Moves with its velocity but is never pushed
+    Kinematic,
+    // Never moves
+    Static,
+}
+
+impl StateHash for Velocity {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.0.state_hash(hasher);
+        self.1.state_hash(hasher);
+    }
+}
+
+impl StateHash for Acceleration {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.0.state_hash(hasher);
+        self.1.state_hash(hasher);
+    }
+}
+
+impl StateHash for Collider {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.half_extents.state_hash(hasher);
+        self.offset.state_hash(hasher);
+        self.sensor.state_hash(hasher);
+    }
+}
+
+crate::state_hash_with_hash!(RigidBody);
+
+/// An axis-aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: (f32, f32),
+    pub max: (f32, f32),
+}
+
+impl Aabb {
+    /// Whether the boxes overlap (touching boxes count as overlapping, so resting bodies stay
+    /// in contact)
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.0 <= other.max.0 && other.min.0 <= self.max.0 && self.min.1 <= other.max.1 && other.min.1 <= self.max.1
+    }
+
+    /// The shortest move of this box out of the other box
+    pub fn separation(&self, other: &Aabb) -> (f32, f32) {
+        let x = if self.min.0 + self.max.0 < other.min.0 + other.max.0 { other.min.0 - self.max.0 } else { other.max.0 - self.min.0 };
+        let y = if self.min.1 + self.max.1 < other.min.1 + other.max.1 { other.min.1 - self.max.1 } else { other.max.1 - self.min.1 };
+        if x.abs() < y.abs() {
+            (x, 0.0)
+        } else {
+            (0.0, y)
+        }
+    }
+
+    fn translate(&mut self, by: (f32, f32)) {
+        self.min = (self.min.0 + by.0, self.min.1 + by.1);
+        self.max = (self.max.0 + by.0, self.max.1 + by.1);
+    }
+}
+
+/// A pair of entities (lowest id first) whose colliders started or stopped overlapping
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionEvent {
+    Started(usize, usize),
+    Ended(usize, usize),
+}
+
+/// The settings of a world's physics, the pairs of entities currently colliding and the
+/// collision events not yet drained
+#[derive(Clone, Debug)]
+pub struct Physics {
+    // Seconds simulated by each step
+    pub step: f32,
+    // Acceleration of every dynamic body (in units per second squared)
+    pub gravity: (f32, f32),
+    // Pairs of entities (lowest id first) whose colliders overlapped in the last step
+    contacts: BTreeSet<(usize, usize)>,
+    // Collision events in the order they happened
+    events: Vec<CollisionEvent>,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self::new(1.0 / 60.0)
+    }
+}
+
+impl Physics {
+    /// Physics simulating `step` seconds each step, without gravity
+    pub fn new(step: f32) -> Self {
+        Self {
+            step,
+            gravity: (0.0, 0.0),
+            contacts: BTreeSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn with_gravity(mut self, gravity: (f32, f32)) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// The pairs of entities (lowest id first) whose colliders overlapped in the last step
+    pub fn contacts(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.contacts.iter().copied()
+    }
+
+    /// Whether the colliders of the entities overlapped in the last step
+    pub fn touching(&self, a: usize, b: usize) -> bool {
+        self.contacts.contains(&(a.min(b), a.max(b)))
+    }
+
+    /// The collision events since they were last drained
+    pub fn events(&self) -> &[CollisionEvent] {
+        &self.events
+    }
+
+    /// Take the collision events since they were last drained.
+    ///
+    /// Events are kept until drained, so games should drain them regularly.
+    pub fn drain_events(&mut self) -> Vec<CollisionEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Forget the contacts and events, for example after loading a world
+    pub fn clear(&mut self) {
+        self.contacts.clear();
+        self.events.clear();
+    }
+
+    /// Forget the contacts and events of a removed entity, shifting the ids of every later
+    /// entity down by one like the world's storages
+    pub fn remove_entity(&mut self, entity_id: usize) {
+        let shift = |v: usize| if v > entity_id { v - 1 } else { v };
+        self.contacts = self.contacts.iter()
+            .filter(|v| v.0 != entity_id && v.1 != entity_id)
+            .map(|v| (shift(v.0), shift(v.1)))
+            .collect();
+        self.events.retain(|v| match v {
+            CollisionEvent::Started(a, b) | CollisionEvent::Ended(a, b) => *a != entity_id && *b != entity_id,
+        });
+        for event in self.events.iter_mut() {
+            *event = match *event {
+                CollisionEvent::Started(a, b) => CollisionEvent::Started(shift(a), shift(b)),
+                CollisionEvent::Ended(a, b) => CollisionEvent::Ended(shift(a), shift(b)),
+            };
+        }
+    }
+
+    /// Simulate one step: integrate the velocities and positions (semi-implicit Euler), find
+    /// the overlapping colliders (sweep and prune along x, then box overlap), push dynamic
+    /// bodies out of the solid colliders they overlap and queue the collisions that started or
+    /// ended
+    pub fn step<P, PS, VS, AS, CS, RS>(&mut self, positions: &mut PS, velocities: &mut VS, accelerations: &AS, colliders: &CS, bodies: &RS)
+    where
+        P: PhysicsPosition,
+        PS: ComponentStorage<P>,
+        VS: ComponentStorage<Velocity>,
+        AS: ComponentStorage<Acceleration>,
+        CS: ComponentStorage<Collider>,
+        RS: ComponentStorage<RigidBody>,
+    {
+        let dt = self.step;
+        let body = |entity_id: usize| bodies.get(entity_id).copied().unwrap_or(RigidBody::Kinematic);
+
+        let moving: Vec<usize> = velocities.entity_ids().collect();
+        for entity_id in moving {
+            let body = body(entity_id);
+            if body == RigidBody::Static {
+                continue;
+            }
+            let mut acceleration = accelerations.get(entity_id).map_or((0.0, 0.0), |v| (v.0, v.1));
+            if body == RigidBody::Dynamic {
+                acceleration = (acceleration.0 + self.gravity.0, acceleration.1 + self.gravity.1);
+            }
+            let velocity = velocities.get_mut(entity_id).unwrap();
+            velocity.0 += acceleration.0 * dt;
+            velocity.1 += acceleration.1 * dt;
+            let velocity = *velocity;
+            if let Some(position) = positions.get_mut(entity_id) {
+                if velocity != Velocity(0.0, 0.0) {
+                    let (x, y) = position.position();
+                    position.set_position((x + velocity.0 * dt, y + velocity.1 * dt));
+                }
+            }
+        }
+
+        // Broadphase: boxes sorted by their left edge only need checking against the boxes
+        // still open when they start
+        let mut boxes: Vec<(usize, Aabb)> = colliders.entity_ids()
+            .filter_map(|v| positions.get(v).map(|position| (v, colliders.get(v).unwrap().aabb(position.position()))))
+            .collect();
+        boxes.sort_unstable_by(|a, b| a.1.min.0.total_cmp(&b.1.min.0).then(a.0.cmp(&b.0)));
+        let mut candidates = Vec::new();
+        let mut open: Vec<usize> = Vec::new();
+        for (index, (_, aabb)) in boxes.iter().enumerate() {
+            open.retain(|v| boxes[*v].1.max.0 >= aabb.min.0);
+            candidates.extend(open.iter().map(|v| (*v, index)));
+            open.push(index);
+        }
+
+        // Narrowphase and response, with the boxes moved along with the bodies they push
+        let mut contacts = BTreeSet::new();
+        for (a, b) in candidates {
+            if !boxes[a].1.overlaps(&boxes[b].1) {
+                continue;
+            }
+            let (a, b) = if boxes[a].0 < boxes[b].0 { (a, b) } else { (b, a) };
+            let (entity_a, entity_b) = (boxes[a].0, boxes[b].0);
+            contacts.insert((entity_a, entity_b));
+            if colliders.get(entity_a).unwrap().sensor || colliders.get(entity_b).unwrap().sensor {
+                continue;
+            }
+
+            let share = match (body(entity_a) == RigidBody::Dynamic, body(entity_b) == RigidBody::Dynamic) {
+                (true, true) => (0.5, 0.5),
+                (true, false) => (1.0, 0.0),
+                (false, true) => (0.0, 1.0),
+                (false, false) => continue,
+            };
+            let separation = boxes[a].1.separation(&boxes[b].1);
+            for (index, entity_id, scale) in [(a, entity_a, share.0), (b, entity_b, -share.1)] {
+                if scale == 0.0 {
+                    continue;
+                }
+                let by = (separation.0 * scale, separation.1 * scale);
+                boxes[index].1.translate(by);
+                let position = positions.get_mut(entity_id).unwrap();
+                let (x, y) = position.position();
+                position.set_position((x + by.0, y + by.1));
+                // Stop moving into the other body
+                if let Some(velocity) = velocities.get_mut(entity_id) {
+                    if velocity.0 * by.0 < 0.0 {
+                        velocity.0 = 0.0;
+                    }
+                    if velocity.1 * by.1 < 0.0 {
+                        velocity.1 = 0.0;
+                    }
+                }
+            }
+        }
+
+        self.events.extend(self.contacts.difference(&contacts).map(|v| CollisionEvent::Ended(v.0, v.1)));
+        self.events.extend(contacts.difference(&self.contacts).map(|v| CollisionEvent::Started(v.0, v.1)));
+        self.contacts = contacts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Bodies {
+        positions: Vec<Option<(f32, f32)>>,
+        velocities: Vec<Option<Velocity>>,
+        accelerations: Vec<Option<Acceleration>>,
+        colliders: Vec<Option<Collider>>,
+        bodies: Vec<Option<RigidBody>>,
+    }
+
+    impl Bodies {
+        fn step(&mut self, physics: &mut Physics) {
+            physics.step(&mut self.positions, &mut self.velocities, &self.accelerations, &self.colliders, &self.bodies);
+        }
+    }
+
+    #[test]
+    fn test_fixed_step_integration() {
+        let mut physics = Physics::new(0.5).with_gravity((0.0, -2.0));
+        let mut bodies = Bodies {
+            positions: vec![Some((0.0, 0.0)), Some((0.0, 0.0)), Some((0.0, 0.0))],
+            velocities: vec![Some(Velocity(1.0, 0.0)), Some(Velocity(1.0, 0.0)), Some(Velocity(1.0, 0.0))],
+            accelerations: vec![None, Some(Acceleration(2.0, 0.0)), None],
+            colliders: vec![None, None, None],
+            bodies: vec![Some(RigidBody::Dynamic), None, Some(RigidBody::Static)],
+        };
+        bodies.step(&mut physics);
+        bodies.step(&mut physics);
+
+        // Velocities change before positions, so gravity moves the body in the first step
+        assert_eq!(bodies.positions[0], Some((1.0, -1.5)));
+        assert_eq!(bodies.velocities[0], Some(Velocity(1.0, -2.0)));
+        // Entities without a rigid body ignore gravity
+        assert_eq!(bodies.positions[1], Some((2.5, 0.0)));
+        assert_eq!(bodies.positions[2], Some((0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_collisions_push_dynamic_bodies_and_emit_events() {
+        let mut physics = Physics::new(1.0).with_gravity((0.0, -1.0));
+        let mut bodies = Bodies {
+            // A box falling onto the ground, a sensor it falls through and a far away box
+            positions: vec![Some((0.0, 1.5)), Some((0.0, 0.0)), Some((0.0, 1.0)), Some((50.0, 0.0))],
+            velocities: vec![Some(Velocity(0.0, 0.0)), None, None, None],
+            accelerations: vec![None; 4],
+            colliders: vec![Some(Collider::new(1.0, 1.0)), Some(Collider::new(10.0, 1.0)), Some(Collider::new(1.0, 1.0).sensor()), Some(Collider::new(1.0, 1.0))],
+            bodies: vec![Some(RigidBody::Dynamic), Some(RigidBody::Static), None, Some(RigidBody::Static)],
+        };
+
+        bodies.step(&mut physics);
+        assert_eq!(bodies.positions[0], Some((0.0, 1.0)));
+        assert_eq!(bodies.velocities[0], Some(Velocity(0.0, 0.0)));
+        assert_eq!(physics.drain_events(), vec![
+            CollisionEvent::Started(0, 1),
+            CollisionEvent::Started(0, 2),
+            CollisionEvent::Started(1, 2),
+        ]);
+
+        // Resting on the ground keeps the contact without new events
+        bodies.step(&mut physics);
+        assert_eq!(bodies.positions[0], Some((0.0, 1.0)));
+        assert!(physics.events().is_empty());
+
+        bodies.velocities[0] = Some(Velocity(20.0, 0.0));
+        bodies.step(&mut physics);
+        assert_eq!(physics.drain_events(), vec![CollisionEvent::Ended(0, 1), CollisionEvent::Ended(0, 2)]);
+        assert!(physics.touching(2, 1));
+    }
+
+    #[test]
+    fn test_removing_an_entity_shifts_contacts() {
+        let mut physics = Physics::default();
+        let mut bodies = Bodies {
+            positions: vec![Some((0.0, 0.0)), Some((0.5, 0.0)), Some((10.0, 0.0)), Some((10.5, 0.0))],
+            velocities: vec![None; 4],
+            accelerations: vec![None; 4],
+            colliders: vec![Some(Collider::new(1.0, 1.0).sensor()); 4],
+            bodies: vec![None; 4],
+        };
+        bodies.step(&mut physics);
+        physics.remove_entity(0);
+        assert_eq!(physics.contacts().collect::<Vec<_>>(), vec![(1, 2)]);
+        assert_eq!(physics.events(), &[CollisionEvent::Started(1, 2)]);
+    }
+}
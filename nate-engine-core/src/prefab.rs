@@ -0,0 +1,271 @@
+//!
+//! Entity Templates (Prefabs) and Scenes of Prefabs Loaded from Data Files
+//!
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{PrefabError, PrefabErrorKind};
+
+/// A template of an entity's components, keyed by the names of the world's component fields.
+///
+/// Tags are set with `true` in the components.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    // The name scene entities refer to the prefab by
+    pub name: String,
+    // The value of each component of the entity
+    pub components: BTreeMap<String, Value>,
+    // The file the prefab was loaded from
+    #[serde(skip)]
+    pub file: Option<PathBuf>,
+}
+
+/// A component of a prefab or scene entity along with the file its value came from
+#[derive(Clone, Copy, Debug)]
+pub struct PrefabComponent<'a> {
+    pub name: &'a str,
+    pub value: &'a Value,
+    pub file: Option<&'a Path>,
+}
+
+impl Prefab {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the value of a component of the prefab
+    pub fn with(mut self, component: &str, value: Value) -> Self {
+        self.components.insert(component.to_string(), value);
+        self
+    }
+
+    /// Load a prefab from a `.json` or `.ron` file.
+    ///
+    /// Components are read without knowing their types, so enum variants are written as strings
+    /// in RON files too (RON drops the names of bare variants like `Blue` when read that way).
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PrefabError> {
+        let path = path.as_ref();
+        let mut prefab: Prefab = read_file(path)?;
+        prefab.file = Some(path.to_path_buf());
+        Ok(prefab)
+    }
+
+    /// The components of the prefab
+    pub fn components(&self) -> impl Iterator<Item = PrefabComponent<'_>> {
+        self.components.iter().map(|(name, value)| PrefabComponent {
+            name,
+            value,
+            file: self.file.as_deref(),
+        })
+    }
+}
+
+/// An instance of a prefab in a scene
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneEntity {
+    // The name of the prefab the entity is made from
+    pub prefab: String,
+    // The name of the entity in errors (defaults to the prefab name and entity index)
+    #[serde(default)]
+    pub name: Option<String>,
+    // Components replacing the prefab's components (or removing them if null)
+    #[serde(default)]
+    pub overrides: BTreeMap<String, Value>,
+}
+
+/// The contents of a scene file
+#[derive(Deserialize)]
+struct SceneFile {
+    // Prefab files relative to the scene file
+    #[serde(default)]
+    prefabs: Vec<PathBuf>,
+    entities: Vec<SceneEntity>,
+}
+
+/// Many instances of prefabs, each with their own overrides
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scene {
+    // The file the scene was loaded from
+    pub file: Option<PathBuf>,
+    // The prefabs of the scene by name
+    pub prefabs: BTreeMap<String, Prefab>,
+    // The entities of the scene
+    pub entities: Vec<SceneEntity>,
+}
+
+impl Scene {
+    /// Load a scene and the prefab files it lists (relative to the scene file) from `.json` or
+    /// `.ron` files
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PrefabError> {
+        let path = path.as_ref();
+        let scene_file: SceneFile = read_file(path)?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+
+        let mut scene = Scene {
+            file: Some(path.to_path_buf()),
+            ..Default::default()
+        };
+        for prefab_path in scene_file.prefabs.iter() {
+            let prefab = Prefab::load(directory.join(prefab_path))?;
+            if scene.prefabs.contains_key(&prefab.name) {
+                return Err(PrefabError::new(PrefabErrorKind::DuplicatePrefab(prefab.name.clone())).with_file(path));
+            }
+            scene.prefabs.insert(prefab.name.clone(), prefab);
+        }
+        scene.entities = scene_file.entities;
+
+        for entity in 0..scene.entities.len() {
+            scene.prefab(entity)?;
+        }
+        Ok(scene)
+    }
+
+    /// Add a prefab to the scene
+    pub fn with_prefab(mut self, prefab: Prefab) -> Self {
+        self.prefabs.insert(prefab.name.clone(), prefab);
+        self
+    }
+
+    /// Add an entity to the scene
+    pub fn with_entity(mut self, entity: SceneEntity) -> Self {
+        self.entities.push(entity);
+        self
+    }
+
+    /// The name of an entity of the scene in errors
+    pub fn entity_name(&self, entity: usize) -> String {
+        let scene_entity = &self.entities[entity];
+        match &scene_entity.name {
+            Some(name) => name.clone(),
+            None => format!("{}[{}]", scene_entity.prefab, entity),
+        }
+    }
+
+    /// The prefab an entity of the scene is made from
+    pub fn prefab(&self, entity: usize) -> Result<&Prefab, PrefabError> {
+        let scene_entity = &self.entities[entity];
+        self.prefabs.get(&scene_entity.prefab).ok_or_else(|| {
+            let err = PrefabError::new(PrefabErrorKind::UnknownPrefab(scene_entity.prefab.clone()))
+                .with_entity(self.entity_name(entity));
+            match &self.file {
+                Some(file) => err.with_file(file),
+                None => err,
+            }
+        })
+    }
+
+    /// The components of an entity of the scene, with its overrides replacing (or removing) the
+    /// prefab's components
+    pub fn components(&self, entity: usize) -> Result<Vec<PrefabComponent<'_>>, PrefabError> {
+        let overrides = &self.entities[entity].overrides;
+        let mut components: Vec<PrefabComponent<'_>> = self.prefab(entity)?
+            .components()
+            .filter(|v| !overrides.contains_key(v.name))
+            .collect();
+        components.extend(overrides.iter().filter(|v| !v.1.is_null()).map(|(name, value)| PrefabComponent {
+            name,
+            value,
+            file: self.file.as_deref(),
+        }));
+        Ok(components)
+    }
+}
+
+/// Read a `.json` or `.ron` file
+fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T, PrefabError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| PrefabError::new(PrefabErrorKind::Io(err.to_string())).with_file(path))?;
+    let parsed = match path.extension().and_then(|v| v.to_str()) {
+        Some("json") => serde_json::from_str(&contents).map_err(|err| err.to_string()),
+        #[cfg(feature = "ron")]
+        Some("ron") => ron::from_str(&contents).map_err(|err| err.to_string()),
+        _ => return Err(PrefabError::new(PrefabErrorKind::UnsupportedFormat).with_file(path)),
+    };
+    parsed.map_err(|err| PrefabError::new(PrefabErrorKind::Parse(err)).with_file(path))
+}
+
+/// The error for a component of a prefab or scene entity
+fn component_error(entity: &str, component: &PrefabComponent<'_>, kind: PrefabErrorKind) -> PrefabError {
+    let err = PrefabError::new(kind).with_entity(entity).with_field(component.name);
+    match component.file {
+        Some(file) => err.with_file(file),
+        None => err,
+    }
+}
+
+/// The value of a component of a prefab or scene entity
+pub fn component<T: DeserializeOwned>(entity: &str, component: &PrefabComponent<'_>) -> Result<T, PrefabError> {
+    T::deserialize(component.value)
+        .map_err(|err| component_error(entity, component, PrefabErrorKind::InvalidComponent(err.to_string())))
+}
+
+/// Whether a tag of a prefab or scene entity is set
+pub fn tag(entity: &str, component: &PrefabComponent<'_>) -> Result<bool, PrefabError> {
+    component.value.as_bool().ok_or_else(|| {
+        component_error(entity, component, PrefabErrorKind::InvalidComponent(String::from("expected a tag to be true or false")))
+    })
+}
+
+/// The error for a component the world doesn't have
+pub fn unknown_component(entity: &str, component: &PrefabComponent<'_>) -> PrefabError {
+    component_error(entity, component, PrefabErrorKind::UnknownComponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene_overrides_replace_prefab_components() {
+        let scene = Scene::default()
+            .with_prefab(Prefab::new("goblin")
+                .with("position", serde_json::json!([0, 0]))
+                .with("health", serde_json::json!(5))
+                .with("enemy", serde_json::json!(true)))
+            .with_entity(SceneEntity {
+                prefab: String::from("goblin"),
+                name: None,
+                overrides: BTreeMap::from([
+                    (String::from("position"), serde_json::json!([3, 4])),
+                    (String::from("enemy"), Value::Null),
+                ]),
+            })
+            .with_entity(SceneEntity {
+                prefab: String::from("orc"),
+                name: Some(String::from("boss")),
+                overrides: BTreeMap::new(),
+            });
+
+        let components = scene.components(0).unwrap();
+        let mut names: Vec<&str> = components.iter().map(|v| v.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["health", "position"]);
+        let position = components.iter().find(|v| v.name == "position").unwrap();
+        assert_eq!(component::<(i32, i32)>("goblin[0]", position), Ok((3, 4)));
+
+        let err = scene.components(1).unwrap_err();
+        assert_eq!(err.kind, PrefabErrorKind::UnknownPrefab(String::from("orc")));
+        assert_eq!(err.entity.as_deref(), Some("boss"));
+    }
+
+    #[test]
+    fn test_component_errors_name_file_entity_and_field() {
+        let mut prefab = Prefab::new("tower").with("range", serde_json::json!("far"));
+        prefab.file = Some(PathBuf::from("prefabs/tower.json"));
+
+        let range = prefab.components().next().unwrap();
+        let err = component::<u32>("tower", &range).unwrap_err();
+        assert_eq!(err.file, Some(PathBuf::from("prefabs/tower.json")));
+        assert_eq!(err.entity.as_deref(), Some("tower"));
+        assert_eq!(err.field.as_deref(), Some("range"));
+        assert!(err.to_string().starts_with("prefabs/tower.json: entity tower: field range: Invalid component"));
+    }
+}
@@ -0,0 +1,367 @@
+//!
+//! Runtime Registry of a World's Components and Access to Components by Name
+//!
+
+use std::any::{Any, TypeId};
+use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::RwLockReadGuard;
+
+use crate::error::RegistryError;
+use crate::storage::ComponentStorage;
+
+/// How a component is stored in the world
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ComponentKind {
+    // A component entities can have
+    Entity,
+    // A zero-sized marker entities can have
+    Tag,
+    // A single component of the world
+    Singular,
+}
+
+/// A component of a world as listed in the world's `REGISTRY`
+#[derive(Clone, Copy, Debug)]
+pub struct ComponentInfo {
+    // The name of the component's field on the world
+    pub name: &'static str,
+    // The component's type as written on the world
+    pub type_name: &'static str,
+    // How the component is stored
+    pub kind: ComponentKind,
+    // The `TypeId` of the component's type
+    pub type_id: fn() -> TypeId,
+}
+
+impl ComponentInfo {
+    /// Whether the component is of type `T`
+    pub fn is<T: Any>(&self) -> bool {
+        (self.type_id)() == TypeId::of::<T>()
+    }
+}
+
+/// Access to the components of a world by name, implemented by every generated world so tools
+/// like the inspector can work with any world
+pub trait Reflect {
+    /// Every component of the world
+    fn registry() -> &'static [ComponentInfo];
+
+    /// The number of entities in the world
+    fn entity_count(&self) -> usize;
+
+    /// Add an entity without any components, returning its id
+    fn add_entity(&mut self) -> usize;
+
+    /// Whether the entity has a per-entity component or tag
+    fn has_component(&self, entity_id: usize, name: &str) -> bool;
+
+    /// The `Debug` output of a per-entity component or tag of an entity
+    fn get_string(&self, entity_id: usize, name: &str) -> Result<Option<String>, RegistryError>;
+
+    /// The `Debug` output of a singular component
+    fn get_singular_string(&self, name: &str) -> Result<Option<String>, RegistryError>;
+
+    /// Set a per-entity component or tag of an entity from a string
+    fn set_from_str(&mut self, entity_id: usize, name: &str, value: &str) -> Result<(), RegistryError>;
+
+    /// Set a singular component from a string
+    fn set_singular_from_str(&mut self, name: &str, value: &str) -> Result<(), RegistryError>;
+
+    /// Spawn an entity with per-entity components and tags set from `(name, value)` strings,
+    /// returning its id. Nothing is spawned if any value is invalid.
+    fn spawn_from_strs(&mut self, components: &[(&str, &str)]) -> Result<usize, RegistryError>;
+}
+
+/// A column of components (or a singular component) kept locked for reading
+trait AnyColumn {
+    fn get_any(&self, entity_id: usize) -> Option<&dyn Any>;
+}
+
+struct ColumnGuard<'a, T, S> {
+    guard: RwLockReadGuard<'a, S>,
+    _component: PhantomData<fn() -> T>,
+}
+
+impl<T: Any, S: ComponentStorage<T>> AnyColumn for ColumnGuard<'_, T, S> {
+    fn get_any(&self, entity_id: usize) -> Option<&dyn Any> {
+        self.guard.get(entity_id).map(|v| v as &dyn Any)
+    }
+}
+
+struct SingularGuard<'a, T> {
+    guard: RwLockReadGuard<'a, Option<T>>,
+}
+
+impl<T: Any> AnyColumn for SingularGuard<'_, T> {
+    fn get_any(&self, _entity_id: usize) -> Option<&dyn Any> {
+        self.guard.as_ref().map(|v| v as &dyn Any)
+    }
+}
+
+/// A component looked up by name, which keeps its column locked for reading while it is held
+/// and dereferences to the component as `dyn Any`
+pub struct ComponentRef<'a> {
+    column: Box<dyn AnyColumn + 'a>,
+    entity_id: usize,
+}
+
+impl<'a> ComponentRef<'a> {
+    /// The component of the entity in the locked column, if the entity has it
+    pub fn column<T: Any, S: ComponentStorage<T> + 'a>(guard: RwLockReadGuard<'a, S>, entity_id: usize) -> Option<Self> {
+        if !guard.contains(entity_id) {
+            return None;
+        }
+
+        Some(Self {
+            column: Box::new(ColumnGuard { guard, _component: PhantomData }),
+            entity_id,
+        })
+    }
+
+    /// The locked singular component, if it is set
+    pub fn singular<T: Any>(guard: RwLockReadGuard<'a, Option<T>>) -> Option<Self> {
+        guard.as_ref()?;
+        Some(Self {
+            column: Box::new(SingularGuard { guard }),
+            entity_id: 0,
+        })
+    }
+}
+
+impl Deref for ComponentRef<'_> {
+    type Target = dyn Any;
+
+    fn deref(&self) -> &Self::Target {
+        // The component was present when the reference was made and its column has been locked
+        // since
+        self.column.get_any(self.entity_id).unwrap()
+    }
+}
+
+/// Picks between the traits below by autoref, so generated worlds can parse and format the
+/// components that implement `FromStr` and `Debug` and report the others as unsupported
+pub struct Probe<T>(PhantomData<T>);
+
+impl<T> Probe<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for Probe<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses components implementing `FromStr`, called as `(&&Probe::<T>::new()).parse_component(s)`
+pub trait ParseComponent<T> {
+    fn parse_component(&self, value: &str) -> Option<Result<T, String>>;
+}
+
+impl<T: FromStr> ParseComponent<T> for &Probe<T>
+where
+    T::Err: Display,
+{
+    fn parse_component(&self, value: &str) -> Option<Result<T, String>> {
+        Some(value.parse::<T>().map_err(|err| err.to_string()))
+    }
+}
+
+/// Fallback for components that can't be parsed
+pub trait ParseComponentFallback<T> {
+    fn parse_component(&self, _value: &str) -> Option<Result<T, String>> {
+        None
+    }
+}
+
+impl<T> ParseComponentFallback<T> for Probe<T> {}
+
+/// Formats components implementing `Debug`, called as `(&&Probe::<T>::new()).debug_component(c)`
+pub trait DebugComponent<T> {
+    fn debug_component(&self, component: &T) -> Option<String>;
+}
+
+impl<T: Debug> DebugComponent<T> for &Probe<T> {
+    fn debug_component(&self, component: &T) -> Option<String> {
+        Some(format!("{:?}", component))
+    }
+}
+
+/// Fallback for components that can't be formatted
+pub trait DebugComponentFallback<T> {
+    fn debug_component(&self, _component: &T) -> Option<String> {
+        None
+    }
+}
+
+impl<T> DebugComponentFallback<T> for Probe<T> {}
+
+/// Components by name along with their already formatted values, printed as a map whose names
+/// and values aren't quoted
+pub struct DebugEntries(pub Vec<(&'static str, String)>);
+
+impl Debug for DebugEntries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut map = f.debug_map();
+        for (name, value) in self.0.iter() {
+            map.entry(&format_args!("{}", name), &format_args!("{}", value));
+        }
+        map.finish()
+    }
+}
+
+/// World with a health component, a dead tag and a singular tick, reflected as a generated world
+/// would be
+#[cfg(test)]
+pub(crate) mod test_world {
+    use super::*;
+
+    pub(crate) struct FakeWorld {
+        pub health: Vec<Option<u32>>,
+        pub dead: Vec<bool>,
+        pub tick: Option<u64>,
+    }
+
+    impl FakeWorld {
+        pub fn new(health: Vec<Option<u32>>) -> Self {
+            Self {
+                dead: vec![false; health.len()],
+                health,
+                tick: None,
+            }
+        }
+    }
+
+    fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, RegistryError>
+    where
+        T::Err: Display,
+    {
+        value.parse().map_err(|err: T::Err| RegistryError::Parse { component: name.to_string(), error: err.to_string() })
+    }
+
+    impl Reflect for FakeWorld {
+        fn registry() -> &'static [ComponentInfo] {
+            &[
+                ComponentInfo { name: "health", type_name: "u32", kind: ComponentKind::Entity, type_id: TypeId::of::<u32> },
+                ComponentInfo { name: "dead", type_name: "()", kind: ComponentKind::Tag, type_id: TypeId::of::<()> },
+                ComponentInfo { name: "tick", type_name: "u64", kind: ComponentKind::Singular, type_id: TypeId::of::<u64> },
+            ]
+        }
+
+        fn entity_count(&self) -> usize {
+            self.health.len()
+        }
+
+        fn add_entity(&mut self) -> usize {
+            self.health.push(None);
+            self.dead.push(false);
+            self.health.len() - 1
+        }
+
+        fn has_component(&self, entity_id: usize, name: &str) -> bool {
+            match name {
+                "health" => self.health[entity_id].is_some(),
+                "dead" => self.dead[entity_id],
+                _ => false,
+            }
+        }
+
+        fn get_string(&self, entity_id: usize, name: &str) -> Result<Option<String>, RegistryError> {
+            match name {
+                "health" => Ok(self.health[entity_id].map(|v| format!("{:?}", v))),
+                "dead" => Ok(self.dead[entity_id].then(|| String::from("()"))),
+                _ => Err(RegistryError::UnknownComponent(name.to_string())),
+            }
+        }
+
+        fn get_singular_string(&self, name: &str) -> Result<Option<String>, RegistryError> {
+            match name {
+                "tick" => Ok(self.tick.map(|v| format!("{:?}", v))),
+                _ => Err(RegistryError::UnknownComponent(name.to_string())),
+            }
+        }
+
+        fn set_from_str(&mut self, entity_id: usize, name: &str, value: &str) -> Result<(), RegistryError> {
+            if entity_id >= self.health.len() {
+                return Err(RegistryError::InvalidEntity(entity_id));
+            }
+            match name {
+                "health" => self.health[entity_id] = Some(parse(name, value)?),
+                "dead" => self.dead[entity_id] = parse(name, value)?,
+                _ => return Err(RegistryError::UnknownComponent(name.to_string())),
+            }
+            Ok(())
+        }
+
+        fn set_singular_from_str(&mut self, name: &str, value: &str) -> Result<(), RegistryError> {
+            match name {
+                "tick" => self.tick = Some(parse(name, value)?),
+                _ => return Err(RegistryError::UnknownComponent(name.to_string())),
+            }
+            Ok(())
+        }
+
+        fn spawn_from_strs(&mut self, components: &[(&str, &str)]) -> Result<usize, RegistryError> {
+            let (mut health, mut dead) = (None, false);
+            for (name, value) in components.iter() {
+                match *name {
+                    "health" => health = Some(parse(name, value)?),
+                    "dead" => dead = parse(name, value)?,
+                    _ => return Err(RegistryError::UnknownComponent(name.to_string())),
+                }
+            }
+            self.health.push(health);
+            self.dead.push(dead);
+            Ok(self.health.len() - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::RwLock;
+
+    struct Opaque;
+
+    // The double borrow is what lets the probes fall back for types without the implementations
+    #[allow(clippy::needless_borrow)]
+    #[test]
+    fn test_probes_pick_implementations() {
+        assert_eq!((&&Probe::<u32>::new()).parse_component("12"), Some(Ok(12)));
+        assert!(matches!((&&Probe::<u32>::new()).parse_component("twelve"), Some(Err(_))));
+        assert!((&&Probe::<(u32, u32)>::new()).parse_component("1, 2").is_none());
+
+        assert_eq!((&&Probe::<(u32, u32)>::new()).debug_component(&(1, 2)), Some(String::from("(1, 2)")));
+        assert_eq!((&&Probe::<Opaque>::new()).debug_component(&Opaque), None);
+    }
+
+    #[test]
+    fn test_component_ref_downcasts() {
+        let column = RwLock::new(vec![Some(1u32), None]);
+        let component = ComponentRef::column(column.read().unwrap(), 0).unwrap();
+        assert_eq!(component.downcast_ref::<u32>(), Some(&1));
+        assert!(component.downcast_ref::<i32>().is_none());
+        drop(component);
+        assert!(ComponentRef::column(column.read().unwrap(), 1).is_none());
+
+        let singular = RwLock::new(Some(String::from("a")));
+        assert_eq!(ComponentRef::singular(singular.read().unwrap()).unwrap().downcast_ref::<String>().map(|v| v.as_str()), Some("a"));
+
+        let info = ComponentInfo { name: "health", type_name: "u32", kind: ComponentKind::Entity, type_id: TypeId::of::<u32> };
+        assert!(info.is::<u32>() && !info.is::<u64>());
+    }
+
+    #[test]
+    fn test_debug_entries_are_unquoted() {
+        let entries = DebugEntries(vec![("position", String::from("(1, 2)")), ("name", String::from("\"goblin\""))]);
+        assert_eq!(format!("{:?}", entries), "{position: (1, 2), name: \"goblin\"}");
+        assert_eq!(format!("{:#?}", entries), "{\n    position: (1, 2),\n    name: \"goblin\",\n}");
+    }
+}
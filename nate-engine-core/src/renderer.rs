@@ -11,4 +11,13 @@ pub trait Renderer<WORLD: Send + Sync + 'static>: Send + Sync {
 
     /// Render the necessary contents of the world
     fn render(&mut self, world: Arc<RwLock<WORLD>>) -> Result<(), Self::Error>;
+
+    /// Whether the error ends rendering as a normal shutdown (like the player quitting) rather
+    /// than a failure
+    fn is_quit(&self, _err: &Self::Error) -> bool {
+        false
+    }
+
+    /// Release what rendering holds (like the terminal) once the engine stops running
+    fn finish(&mut self) {}
 }
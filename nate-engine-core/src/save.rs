@@ -0,0 +1,280 @@
+//!
+//! Saving and Loading Worlds in a Versioned Container
+//!
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::SaveError;
+pub use crate::registry::ComponentKind;
+use crate::storage::{BitSet, ComponentStorage};
+
+/// The name and type of a saved component
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentSchema {
+    // The name of the component's field on the world
+    pub name: String,
+    // The component's type as written on the world
+    pub type_name: String,
+    // How the component is stored
+    pub kind: ComponentKind,
+}
+
+impl ComponentSchema {
+    pub fn new(name: &str, type_name: &str, kind: ComponentKind) -> Self {
+        Self {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            kind,
+        }
+    }
+}
+
+/// A saved world, along with the world's name, schema version and the components it was saved
+/// with so older saves can be migrated to newer versions of the world
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveFile {
+    // The name of the saved world
+    pub world: String,
+    // The schema version of the world when it was saved
+    pub version: u32,
+    // The saved components
+    pub components: Vec<ComponentSchema>,
+    // The saved entities and components, keyed by component name
+    pub data: Value,
+}
+
+impl SaveFile {
+    /// Rename a component in the save
+    pub fn rename_component(&mut self, from: &str, to: &str) -> Result<(), SaveError> {
+        let component = self.components.iter_mut()
+            .find(|v| v.name == from)
+            .ok_or_else(|| SaveError::MissingComponent(from.to_string()))?;
+        component.name = to.to_string();
+        if let Some(data) = self.data.as_object_mut() {
+            if let Some(value) = data.remove(from) {
+                data.insert(to.to_string(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a component from the save, returning its saved value
+    pub fn remove_component(&mut self, name: &str) -> Result<Value, SaveError> {
+        let index = self.components.iter()
+            .position(|v| v.name == name)
+            .ok_or_else(|| SaveError::MissingComponent(name.to_string()))?;
+        self.components.remove(index);
+        Ok(self.data.as_object_mut().and_then(|v| v.remove(name)).unwrap_or(Value::Null))
+    }
+
+    /// Add a component to the save.
+    ///
+    /// Entity components are saved as a list of `[entity id, component]` pairs, tags as a list
+    /// of entity ids and singular components as the component (or null).
+    pub fn add_component(&mut self, component: ComponentSchema, value: Value) {
+        if let Some(data) = self.data.as_object_mut() {
+            data.insert(component.name.clone(), value);
+        }
+        self.components.retain(|v| v.name != component.name);
+        self.components.push(component);
+    }
+
+    /// The saved value of a component
+    pub fn component_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.data.get_mut(name)
+    }
+
+    /// Upgrade the save to the given schema version of the world with the registered migrations
+    pub fn migrate(&mut self, world: &str, version: u32, migrations: &Migrations) -> Result<(), SaveError> {
+        if self.world != world {
+            return Err(SaveError::WrongWorld { expected: world.to_string(), found: self.world.clone() });
+        }
+        if self.version > version {
+            return Err(SaveError::NewerVersion { version: self.version, supported: version });
+        }
+
+        while self.version < version {
+            let migration = migrations.migrations.get(&self.version)
+                .ok_or(SaveError::MissingMigration(self.version))?;
+            migration(self)?;
+            self.version += 1;
+        }
+        Ok(())
+    }
+
+    /// Check that the save has exactly the components of the world's schema with the same types
+    pub fn check_components(&self, schema: &[ComponentSchema]) -> Result<(), SaveError> {
+        for component in self.components.iter() {
+            let expected = schema.iter()
+                .find(|v| v.name == component.name)
+                .ok_or_else(|| SaveError::UnknownComponent(component.name.clone()))?;
+            if expected.type_name != component.type_name || expected.kind != component.kind {
+                return Err(SaveError::ComponentTypeMismatch {
+                    component: component.name.clone(),
+                    expected: expected.type_name.clone(),
+                    found: component.type_name.clone(),
+                });
+            }
+        }
+
+        match schema.iter().find(|v| !self.components.iter().any(|component| component.name == v.name)) {
+            Some(missing) => Err(SaveError::MissingComponent(missing.name.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A migration upgrading a save by one schema version
+pub type Migration = Box<dyn Fn(&mut SaveFile) -> Result<(), SaveError> + Send + Sync>;
+
+/// Migrations registered to upgrade saves from older schema versions of a world
+#[derive(Default)]
+pub struct Migrations {
+    migrations: HashMap<u32, Migration>,
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the migration upgrading saves from the given version to the next version
+    pub fn register<F>(&mut self, from_version: u32, migration: F) -> &mut Self
+    where
+        F: Fn(&mut SaveFile) -> Result<(), SaveError> + Send + Sync + 'static,
+    {
+        self.migrations.insert(from_version, Box::new(migration));
+        self
+    }
+}
+
+/// Serializes the components in a column as (entity id, component) pairs in entity order, so
+/// the same world saves the same way whatever storage its columns use
+pub struct SerializeComponents<'a, T, S> {
+    column: &'a S,
+    _component: PhantomData<T>,
+}
+
+impl<'a, T, S: ComponentStorage<T>> SerializeComponents<'a, T, S> {
+    pub fn new(column: &'a S) -> Self {
+        Self {
+            column,
+            _component: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize, S: ComponentStorage<T>> Serialize for SerializeComponents<'_, T, S> {
+    fn serialize<SE: Serializer>(&self, serializer: SE) -> Result<SE::Ok, SE::Error> {
+        let mut entity_ids: Vec<usize> = self.column.entity_ids().collect();
+        entity_ids.sort_unstable();
+        serializer.collect_seq(entity_ids.into_iter().filter_map(|entity_id| {
+            self.column.get(entity_id).map(|component| (entity_id, component))
+        }))
+    }
+}
+
+/// Serializes the entities with a tag as a list of entity ids in entity order
+pub struct SerializeTags<'a>(pub &'a BitSet);
+
+impl Serialize for SerializeTags<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::storage::SparseSet;
+
+    #[test]
+    fn test_components_serialize_in_entity_order() {
+        let mut column = SparseSet::new();
+        for _ in 0..4 {
+            column.push_slot(None);
+        }
+        column.insert(3, "c");
+        column.insert(0, "a");
+        column.insert(2, "b");
+
+        let json = serde_json::to_string(&SerializeComponents::new(&column)).unwrap();
+        assert_eq!(json, r#"[[0,"a"],[2,"b"],[3,"c"]]"#);
+
+        let mut tags = BitSet::new();
+        for _ in 0..6 {
+            tags.push_slot(None);
+        }
+        tags.set(5);
+        tags.set(1);
+        assert_eq!(serde_json::to_string(&SerializeTags(&tags)).unwrap(), "[1,5]");
+    }
+
+    fn save_file() -> SaveFile {
+        SaveFile {
+            world: String::from("World"),
+            version: 0,
+            components: vec![
+                ComponentSchema::new("pos", "(isize,isize)", ComponentKind::Entity),
+                ComponentSchema::new("enemy", "()", ComponentKind::Tag),
+            ],
+            data: serde_json::json!({
+                "entities": [0, 1],
+                "pos": [[0, [1, 2]], [1, [3, 4]]],
+                "enemy": [1],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_migrate_upgrades_through_each_version() {
+        let mut migrations = Migrations::new();
+        migrations.register(0, |save| save.rename_component("pos", "position"));
+        migrations.register(1, |save| {
+            save.add_component(ComponentSchema::new("health", "u32", ComponentKind::Entity), serde_json::json!([]));
+            Ok(())
+        });
+
+        let mut save = save_file();
+        save.migrate("World", 2, &migrations).unwrap();
+        assert_eq!(save.version, 2);
+        assert_eq!(save.data["position"], serde_json::json!([[0, [1, 2]], [1, [3, 4]]]));
+        assert!(save.data.get("pos").is_none());
+
+        let schema = vec![
+            ComponentSchema::new("position", "(isize,isize)", ComponentKind::Entity),
+            ComponentSchema::new("enemy", "()", ComponentKind::Tag),
+            ComponentSchema::new("health", "u32", ComponentKind::Entity),
+        ];
+        assert_eq!(save.check_components(&schema), Ok(()));
+
+        let mut save = save_file();
+        assert_eq!(save.migrate("World", 3, &migrations), Err(SaveError::MissingMigration(2)));
+        assert!(matches!(save_file().migrate("Other", 2, &migrations), Err(SaveError::WrongWorld { .. })));
+        assert!(matches!(save_file().migrate("World", 0, &Migrations::new()), Ok(())));
+    }
+
+    #[test]
+    fn test_check_components_reports_mismatches() {
+        let save = save_file();
+        let mut schema = vec![
+            ComponentSchema::new("pos", "(isize,isize)", ComponentKind::Entity),
+            ComponentSchema::new("enemy", "()", ComponentKind::Tag),
+        ];
+        assert_eq!(save.check_components(&schema), Ok(()));
+
+        schema.push(ComponentSchema::new("health", "u32", ComponentKind::Entity));
+        assert_eq!(save.check_components(&schema), Err(SaveError::MissingComponent(String::from("health"))));
+
+        schema.remove(1);
+        assert_eq!(save.check_components(&schema), Err(SaveError::UnknownComponent(String::from("enemy"))));
+
+        schema[0].type_name = String::from("(f32,f32)");
+        assert!(matches!(save.check_components(&schema), Err(SaveError::ComponentTypeMismatch { .. })));
+    }
+}
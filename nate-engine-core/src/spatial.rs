@@ -0,0 +1,290 @@
+//!
+//! Uniform Spatial Hash Grid for Neighbour Queries Over Position Components
+//!
+
+use std::collections::HashMap;
+
+use crate::storage::ComponentStorage;
+use crate::transform::{GlobalTransform, GridTransform, LocalTransform, Transform2D};
+
+/// Components with a 2D position that can be put in a `SpatialGrid`
+pub trait SpatialPosition {
+    fn point(&self) -> (f64, f64);
+}
+
+macro_rules! spatial_position_for_numbers {
+    ($($ty:ty),*) => {
+        $(impl SpatialPosition for ($ty, $ty) {
+            fn point(&self) -> (f64, f64) {
+                (self.0 as f64, self.1 as f64)
+            }
+        }
+
+        impl SpatialPosition for [$ty; 2] {
+            fn point(&self) -> (f64, f64) {
+                (self[0] as f64, self[1] as f64)
+            }
+        })*
+    };
+}
+
+spatial_position_for_numbers!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl SpatialPosition for Transform2D {
+    fn point(&self) -> (f64, f64) {
+        self.position.point()
+    }
+}
+
+impl SpatialPosition for GridTransform {
+    fn point(&self) -> (f64, f64) {
+        self.position.point()
+    }
+}
+
+impl<T: SpatialPosition> SpatialPosition for LocalTransform<T> {
+    fn point(&self) -> (f64, f64) {
+        (**self).point()
+    }
+}
+
+impl<T: SpatialPosition> SpatialPosition for GlobalTransform<T> {
+    fn point(&self) -> (f64, f64) {
+        self.0.point()
+    }
+}
+
+/// The entities with a position component bucketed into square cells, kept up to date by the
+/// world's methods for components marked `#[spatial]`.
+///
+/// Queries only look at the cells they overlap, so cells should be about the size of the
+/// usual query radius.
+#[derive(Clone, Debug)]
+pub struct SpatialGrid {
+    // The width and height of a cell
+    cell_size: f64,
+    // The point each entity is in the grid at
+    points: Vec<Option<(f64, f64)>>,
+    // The entities in each cell in the order they entered it
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    // The number of entities in the grid
+    len: usize,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f64) -> Self {
+        assert!(cell_size > 0.0, "Spatial grid cells must have a positive size");
+        Self {
+            cell_size,
+            points: Vec::new(),
+            cells: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// The number of entities in the grid
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The point an entity is in the grid at
+    pub fn point(&self, entity_id: usize) -> Option<(f64, f64)> {
+        self.points.as_slice().get(entity_id).copied().flatten()
+    }
+
+    fn cell(&self, point: (f64, f64)) -> (i64, i64) {
+        ((point.0 / self.cell_size).floor() as i64, (point.1 / self.cell_size).floor() as i64)
+    }
+
+    /// Add a slot for a new entity, with its position if it has one
+    pub fn push_slot<P: SpatialPosition>(&mut self, position: Option<&P>) {
+        self.points.push(None);
+        if let Some(position) = position {
+            self.insert(self.points.len() - 1, position);
+        }
+    }
+
+    pub fn reserve_slots(&mut self, additional: usize) {
+        self.points.reserve(additional);
+    }
+
+    /// Move the entity to its position (adding it to the grid if it isn't in it)
+    pub fn insert<P: SpatialPosition + ?Sized>(&mut self, entity_id: usize, position: &P) {
+        let point = position.point();
+        match self.points[entity_id] {
+            Some(previous) if self.cell(previous) == self.cell(point) => (),
+            _ => {
+                self.remove(entity_id);
+                self.cells.entry(self.cell(point)).or_default().push(entity_id);
+                self.len += 1;
+            },
+        }
+        self.points[entity_id] = Some(point);
+    }
+
+    /// Take the entity out of the grid
+    pub fn remove(&mut self, entity_id: usize) {
+        let Some(point) = self.points.as_mut_slice().get_mut(entity_id).and_then(|v| v.take()) else {
+            return;
+        };
+        let cell = self.cell(point);
+        if let Some(entities) = self.cells.get_mut(&cell) {
+            entities.retain(|v| *v != entity_id);
+            if entities.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+        self.len -= 1;
+    }
+
+    /// Remove the entity's slot, shifting the ids of every later entity down by one like the
+    /// world's storages
+    pub fn remove_slot(&mut self, entity_id: usize) {
+        if entity_id >= self.points.len() {
+            return;
+        }
+
+        self.remove(entity_id);
+        self.points.remove(entity_id);
+        for entity in self.cells.values_mut().flatten() {
+            if *entity > entity_id {
+                *entity -= 1;
+            }
+        }
+    }
+
+    /// Move the given entities if their position changed without going through the grid (for
+    /// example written by a system)
+    pub fn sync<P: SpatialPosition, S: ComponentStorage<P>>(&mut self, column: &S, entity_ids: impl IntoIterator<Item = usize>) {
+        let slots = self.points.len();
+        for entity_id in entity_ids.into_iter().filter(|v| *v < slots) {
+            match column.get(entity_id) {
+                Some(position) if self.points[entity_id] != Some(position.point()) => self.insert(entity_id, position),
+                None => self.remove(entity_id),
+                _ => (),
+            }
+        }
+    }
+
+    /// The entities in the cells from `min` to `max` (inclusive) with their points
+    fn cells_in(&self, min: (i64, i64), max: (i64, i64)) -> impl Iterator<Item = (usize, (f64, f64))> + '_ {
+        // Big ranges over few occupied cells go through the occupied cells instead
+        let range = (max.0 - min.0 + 1).saturating_mul(max.1 - min.1 + 1);
+        let cells: Vec<&Vec<usize>> = if range as usize > self.cells.len() {
+            let mut cells: Vec<(&(i64, i64), &Vec<usize>)> = self.cells.iter()
+                .filter(|v| (min.0..=max.0).contains(&v.0.0) && (min.1..=max.1).contains(&v.0.1))
+                .collect();
+            // Sorted so queries return the same order however the map is laid out
+            cells.sort_unstable_by_key(|v| (v.0.1, v.0.0));
+            cells.into_iter().map(|v| v.1).collect()
+        } else {
+            (min.1..=max.1).flat_map(|y| (min.0..=max.0).map(move |x| (x, y))).filter_map(|v| self.cells.get(&v)).collect()
+        };
+        cells.into_iter().flatten().map(|v| (*v, self.points[*v].unwrap()))
+    }
+
+    /// The entities at most `radius` from the center
+    pub fn within_radius<P: SpatialPosition + ?Sized>(&self, center: &P, radius: f64) -> Vec<usize> {
+        let center = center.point();
+        let min = self.cell((center.0 - radius, center.1 - radius));
+        let max = self.cell((center.0 + radius, center.1 + radius));
+        self.cells_in(min, max)
+            .filter(|v| distance_squared(center, v.1) <= radius * radius)
+            .map(|v| v.0)
+            .collect()
+    }
+
+    /// The entities inside the rectangle from `min` to `max` (inclusive)
+    pub fn in_rect<P: SpatialPosition + ?Sized>(&self, min: &P, max: &P) -> Vec<usize> {
+        let (min, max) = (min.point(), max.point());
+        self.cells_in(self.cell(min), self.cell(max))
+            .filter(|v| (min.0..=max.0).contains(&v.1.0) && (min.1..=max.1).contains(&v.1.1))
+            .map(|v| v.0)
+            .collect()
+    }
+
+    /// The `k` entities nearest to the center, nearest first (ties broken by entity id)
+    pub fn nearest_k<P: SpatialPosition + ?Sized>(&self, center: &P, k: usize) -> Vec<usize> {
+        let center = center.point();
+        let (x, y) = self.cell(center);
+        let mut candidates: Vec<(f64, usize)> = Vec::new();
+        let by_distance = |a: &(f64, usize), b: &(f64, usize)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1));
+        // Rings of cells around the center's cell are searched until every entity outside the
+        // searched cells is further than the k-th nearest one found
+        let mut ring: i64 = 0;
+        while k > 0 && candidates.len() < self.len {
+            if ring > 0 && 8 * ring as usize > self.cells.len() {
+                // Going round rings bigger than the number of occupied cells is slower than
+                // checking every entity
+                candidates = self.points.iter()
+                    .enumerate()
+                    .filter_map(|v| v.1.map(|point| (distance_squared(center, point), v.0)))
+                    .collect();
+                break;
+            }
+
+            for cell in ring_cells((x, y), ring) {
+                if let Some(entities) = self.cells.get(&cell) {
+                    candidates.extend(entities.iter().map(|v| (distance_squared(center, self.points[*v].unwrap()), *v)));
+                }
+            }
+            let reach = ring as f64 * self.cell_size;
+            if candidates.len() >= k {
+                candidates.sort_unstable_by(by_distance);
+                if candidates[k - 1].0 <= reach * reach {
+                    break;
+                }
+            }
+            ring += 1;
+        }
+
+        candidates.sort_unstable_by(by_distance);
+        candidates.into_iter().take(k).map(|v| v.1).collect()
+    }
+}
+
+/// The cells `ring` cells away from the center cell (in both directions)
+fn ring_cells(center: (i64, i64), ring: i64) -> Vec<(i64, i64)> {
+    if ring == 0 {
+        return vec![center];
+    }
+    let (x, y) = center;
+    let rows = (-ring..=ring).flat_map(|dx| [(x + dx, y - ring), (x + dx, y + ring)]);
+    let columns = (1 - ring..ring).flat_map(|dy| [(x - ring, y + dy), (x + ring, y + dy)]);
+    rows.chain(columns).collect()
+}
+
+fn distance_squared(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(positions: &[(f32, f32)]) -> SpatialGrid {
+        let mut grid = SpatialGrid::new(4.0);
+        for position in positions {
+            grid.push_slot(Some(position));
+        }
+        grid
+    }
+
+    #[test]
+    fn test_queries() {
+        let grid = grid(&[(0.0, 0.0), (3.0, 4.0), (-6.0, 1.0), (20.0, 20.0), (1.0, -1.0)]);
+        assert_eq!(grid.within_radius(&(0.0, 0.0), 5.0), vec![4, 0, 1]);
+        assert_eq!(grid.in_rect(&(-10.0, -1.0), &(1.0, 1.0)), vec![4, 2, 0]);
+        assert_eq!(grid.nearest_k(&(0.0, 0.0), 3), vec![0, 4, 1]);
+        assert_eq!(grid.nearest_k(&(19.0, 19.0), 2), vec![3, 1]);
+        assert_eq!(grid.nearest_k(&(0.0, 0.0), 10).len(), 5);
+    }
+}
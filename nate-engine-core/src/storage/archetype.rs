@@ -0,0 +1,478 @@
+//!
+//! Archetype Tables Grouping Entities by Component Signature
+//!
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use super::{ComponentStorage, ParallelStorage, Run, SharedComponents, Signature};
+
+/// Marker row for an entity that does not have a component
+const EMPTY: u32 = u32::MAX;
+
+/// The table (archetype) and row an entity is stored in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Location {
+    // The archetype id of the table
+    pub archetype: u32,
+    // The row of the entity in the table
+    pub row: u32,
+}
+
+/// Registry of the distinct component signatures (archetypes) in a world and the entities
+/// that have each one.
+///
+/// Entities are swap-removed from their old archetype and pushed onto their new archetype
+/// whenever their signature changes, which is the same order [`ArchetypeColumn`]s move their
+/// components in, so an archetype's rows line up with the rows of its component tables.
+#[derive(Clone, Debug, Default)]
+pub struct Archetypes {
+    // Signature -> archetype id
+    index: HashMap<Signature, u32>,
+    // The signature of each archetype
+    signatures: Vec<Signature>,
+    // The entities in each archetype, in row order
+    entities: Vec<Vec<usize>>,
+    // The location of every entity
+    locations: Vec<Location>,
+}
+
+impl Archetypes {
+    /// Create an empty archetype registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of distinct archetypes seen so far
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Whether no archetypes have been created
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// The signature of an archetype
+    pub fn signature(&self, archetype: u32) -> Signature {
+        self.signatures[archetype as usize]
+    }
+
+    /// The entities in an archetype, in row order
+    pub fn entities(&self, archetype: u32) -> &[usize] {
+        &self.entities[archetype as usize]
+    }
+
+    /// The location of an entity
+    pub fn location(&self, entity_id: usize) -> Option<Location> {
+        self.locations.get(entity_id).copied()
+    }
+
+    /// The archetype with the given signature, if any entity has ever had it
+    pub fn archetype(&self, signature: Signature) -> Option<u32> {
+        self.index.get(&signature).copied()
+    }
+
+    /// Iterate over the archetypes with every required component and none of the excluded
+    /// components
+    pub fn matching(&self, required: Signature, excluded: Signature) -> impl Iterator<Item = u32> + '_ {
+        self.signatures.iter().enumerate()
+            .filter(move |v| *v.1 & required == required && *v.1 & excluded == 0)
+            .map(|v| v.0 as u32)
+    }
+
+    /// Add a new entity with the given signature
+    pub fn push_entity(&mut self, signature: Signature) {
+        let entity_id = self.locations.len();
+        let location = self.push_row(entity_id, signature);
+        self.locations.push(location);
+    }
+
+    /// Reserve space for at least `additional` more entities
+    pub fn reserve(&mut self, additional: usize) {
+        self.locations.reserve(additional);
+    }
+
+    /// Move an entity to the archetype with the given signature, returning its new location
+    pub fn set_signature(&mut self, entity_id: usize, signature: Signature) -> Location {
+        let location = self.locations[entity_id];
+        if self.signatures[location.archetype as usize] == signature {
+            return location;
+        }
+
+        self.swap_remove_row(location);
+        let location = self.push_row(entity_id, signature);
+        self.locations[entity_id] = location;
+        location
+    }
+
+    /// Remove an entity, shifting the ids of every later entity down by one and returning the
+    /// signature of the removed entity.
+    ///
+    /// Only the entity's own table and the location of the entity swapped into its row change,
+    /// but the later entities' ids in every table still have to be shifted down.
+    pub fn remove_entity(&mut self, entity_id: usize) -> Signature {
+        let location = self.locations.remove(entity_id);
+        let entities = &mut self.entities[location.archetype as usize];
+        entities.swap_remove(location.row as usize);
+        if let Some(moved_entity) = entities.get(location.row as usize).copied() {
+            // The moved entity's id hasn't been shifted yet
+            self.locations[moved_entity - (moved_entity > entity_id) as usize].row = location.row;
+        }
+
+        for entities in self.entities.iter_mut() {
+            for entity in entities.iter_mut().filter(|v| **v > entity_id) {
+                *entity -= 1;
+            }
+        }
+        self.signatures[location.archetype as usize]
+    }
+
+    fn push_row(&mut self, entity_id: usize, signature: Signature) -> Location {
+        let archetype = match self.index.get(&signature) {
+            Some(archetype) => *archetype,
+            None => {
+                let archetype = self.signatures.len() as u32;
+                self.index.insert(signature, archetype);
+                self.signatures.push(signature);
+                self.entities.push(Vec::new());
+                archetype
+            },
+        };
+
+        let entities = &mut self.entities[archetype as usize];
+        entities.push(entity_id);
+        Location {
+            archetype,
+            row: entities.len() as u32 - 1,
+        }
+    }
+
+    fn swap_remove_row(&mut self, location: Location) {
+        let entities = &mut self.entities[location.archetype as usize];
+        entities.swap_remove(location.row as usize);
+        if let Some(moved_entity) = entities.get(location.row as usize).copied() {
+            self.locations[moved_entity].row = location.row;
+        }
+    }
+}
+
+/// Component storage split into one dense table per archetype.
+///
+/// The world moves each component between tables as the entity's signature changes (see
+/// [`ArchetypeColumn::migrate`]), so entities with the same components are packed together and
+/// iterating an archetype reads its table front to back. The rows of each table line up with the
+/// rows of the world's [`Archetypes`], which is how the column finds the entity whose component
+/// was swapped into a removed row without keeping an entity id per row.
+#[derive(Clone, Debug)]
+pub struct ArchetypeColumn<T> {
+    // The table and row of each entity's component (the row is `EMPTY` if the entity doesn't
+    // have the component)
+    locations: Vec<Location>,
+    // The components of each archetype, indexed by archetype id
+    tables: Vec<Vec<T>>,
+}
+
+impl<T> Default for ArchetypeColumn<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ArchetypeColumn<T> {
+    /// Create a new empty column
+    pub fn new() -> Self {
+        Self {
+            locations: Vec::new(),
+            tables: Vec::new(),
+        }
+    }
+
+    /// The components in an archetype's table, in row order
+    pub fn table(&self, archetype: u32) -> &[T] {
+        match self.tables.get(archetype as usize) {
+            Some(table) => table,
+            None => &[],
+        }
+    }
+
+    /// The components in an archetype's table, in row order (mutably)
+    pub fn table_mut(&mut self, archetype: u32) -> &mut [T] {
+        match self.tables.get_mut(archetype as usize) {
+            Some(table) => table,
+            None => &mut [],
+        }
+    }
+
+    /// Set the entity's component, storing it in the table of the entity's archetype and
+    /// returning the previous component
+    pub fn insert_into(&mut self, entity_id: usize, archetypes: &Archetypes, component: T) -> Option<T> {
+        self.migrate(entity_id, archetypes);
+        ComponentStorage::insert(self, entity_id, component)
+    }
+
+    /// Move the entity's component (if it has one) to the table of the archetype the entity is
+    /// in now that `archetypes` has moved it
+    pub fn migrate(&mut self, entity_id: usize, archetypes: &Archetypes) {
+        let Some(location) = self.locations.get(entity_id).copied() else {
+            return;
+        };
+        let Some(archetype) = archetypes.location(entity_id).map(|v| v.archetype) else {
+            return;
+        };
+        if location.archetype == archetype {
+            return;
+        }
+
+        let component = self.take(location, Some(archetypes));
+        self.locations[entity_id] = Location { archetype, row: EMPTY };
+        if let Some(component) = component {
+            self.push_row(entity_id, component);
+        }
+    }
+
+    /// Remove the entity's component after `archetypes` has moved the entity to the archetype
+    /// without it
+    pub fn remove_with(&mut self, entity_id: usize, archetypes: &Archetypes) -> Option<T> {
+        let location = *self.locations.get(entity_id)?;
+        let component = self.take(location, Some(archetypes));
+        self.locations[entity_id] = Location {
+            archetype: archetypes.location(entity_id).map_or(location.archetype, |v| v.archetype),
+            row: EMPTY,
+        };
+        component
+    }
+
+    /// Remove the entity's slot after `archetypes` has removed the entity, shifting the ids of
+    /// every later entity down by one
+    pub fn remove_slot_with(&mut self, entity_id: usize, archetypes: &Archetypes) -> Option<T> {
+        let location = self.locations.remove(entity_id);
+        self.take(location, Some(archetypes))
+    }
+
+    /// Swap-remove the component at the location, fixing the row of the entity whose component
+    /// took its place. That entity is read from the archetypes (which have already made the same
+    /// swap) or, without them, found by scanning the locations.
+    fn take(&mut self, location: Location, archetypes: Option<&Archetypes>) -> Option<T> {
+        if location.row == EMPTY {
+            return None;
+        }
+
+        let table = &mut self.tables[location.archetype as usize];
+        let last_row = table.len() as u32 - 1;
+        let component = table.swap_remove(location.row as usize);
+        if location.row < last_row {
+            let moved_entity = match archetypes {
+                Some(archetypes) => archetypes.entities(location.archetype).get(location.row as usize).copied(),
+                None => self.locations.iter().position(|v| *v == Location { archetype: location.archetype, row: last_row }),
+            };
+            if let Some(moved_entity) = moved_entity {
+                self.locations[moved_entity].row = location.row;
+            }
+        }
+        Some(component)
+    }
+
+    fn push_row(&mut self, entity_id: usize, component: T) {
+        let archetype = self.locations[entity_id].archetype as usize;
+        if self.tables.len() <= archetype {
+            self.tables.resize_with(archetype + 1, Vec::new);
+        }
+
+        let table = &mut self.tables[archetype];
+        self.locations[entity_id].row = table.len() as u32;
+        table.push(component);
+    }
+}
+
+impl<T> ComponentStorage<T> for ArchetypeColumn<T> {
+    fn slots(&self) -> usize {
+        self.locations.len()
+    }
+
+    fn count(&self) -> usize {
+        self.tables.iter().map(|v| v.len()).sum()
+    }
+
+    fn contains(&self, entity_id: usize) -> bool {
+        matches!(self.locations.get(entity_id), Some(location) if location.row != EMPTY)
+    }
+
+    fn get(&self, entity_id: usize) -> Option<&T> {
+        let location = self.locations.get(entity_id)?;
+        if location.row == EMPTY {
+            return None;
+        }
+        Some(&self.tables[location.archetype as usize][location.row as usize])
+    }
+
+    fn get_mut(&mut self, entity_id: usize) -> Option<&mut T> {
+        let location = *self.locations.get(entity_id)?;
+        if location.row == EMPTY {
+            return None;
+        }
+        Some(&mut self.tables[location.archetype as usize][location.row as usize])
+    }
+
+    /// Set the entity's component, returning the previous component.
+    ///
+    /// A new component goes in the table of the archetype the entity was last migrated to, use
+    /// [`ArchetypeColumn::insert_into`] to follow the world's archetypes.
+    fn insert(&mut self, entity_id: usize, component: T) -> Option<T> {
+        if entity_id >= self.locations.len() {
+            panic!("Entity {} is out of bounds for {} entities", entity_id, self.locations.len());
+        }
+
+        match self.get_mut(entity_id) {
+            Some(existing) => Some(std::mem::replace(existing, component)),
+            None => {
+                self.push_row(entity_id, component);
+                None
+            },
+        }
+    }
+
+    /// Remove the entity's component, returning it.
+    ///
+    /// Without the world's archetypes this scans for the entity swapped into the removed row,
+    /// the world uses [`ArchetypeColumn::remove_with`] instead.
+    fn remove(&mut self, entity_id: usize) -> Option<T> {
+        let location = *self.locations.get(entity_id)?;
+        let component = self.take(location, None);
+        self.locations[entity_id].row = EMPTY;
+        component
+    }
+
+    fn push_slot(&mut self, component: Option<T>) {
+        self.locations.push(Location { archetype: 0, row: EMPTY });
+        if let Some(component) = component {
+            self.push_row(self.locations.len() - 1, component);
+        }
+    }
+
+    fn reserve_slots(&mut self, additional: usize) {
+        self.locations.reserve(additional);
+    }
+
+    /// Remove the entity's slot, shifting the ids of every later entity down by one.
+    ///
+    /// Without the world's archetypes this scans for the entity swapped into the removed row,
+    /// the world uses [`ArchetypeColumn::remove_slot_with`] instead.
+    fn remove_slot(&mut self, entity_id: usize) -> Option<T> {
+        let location = self.locations.remove(entity_id);
+        self.take(location, None)
+    }
+
+    fn entity_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.locations.iter().enumerate().filter(|v| v.1.row != EMPTY).map(|v| v.0)
+    }
+
+    fn run(&self, run: &Run<'_>) -> Option<&[T]> {
+        match run {
+            Run::Table { archetype, entities } => {
+                let table = self.table(*archetype);
+                (table.len() == entities.len()).then_some(table)
+            },
+            Run::Range(_) => None,
+        }
+    }
+
+    fn run_mut(&mut self, run: &Run<'_>) -> Option<&mut [T]> {
+        match run {
+            Run::Table { archetype, entities } => {
+                let table = self.table_mut(*archetype);
+                (table.len() == entities.len()).then_some(table)
+            },
+            Run::Range(_) => None,
+        }
+    }
+}
+
+impl<T: Send> ParallelStorage<T> for ArchetypeColumn<T> {
+    type Shared<'a> = SharedArchetypeColumn<'a, T> where T: 'a;
+
+    fn shared_mut(&mut self) -> SharedArchetypeColumn<'_, T> {
+        SharedArchetypeColumn {
+            locations: &self.locations,
+            tables: self.tables.iter_mut().map(|v| v.as_mut_ptr()).collect(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Shared view of an [`ArchetypeColumn`] for parallel systems
+pub struct SharedArchetypeColumn<'a, T> {
+    // The table and row of each entity's component
+    locations: &'a [Location],
+    // The start of each table's components
+    tables: Vec<*mut T>,
+    marker: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for SharedArchetypeColumn<'_, T> {}
+unsafe impl<T: Send> Sync for SharedArchetypeColumn<'_, T> {}
+
+unsafe impl<T: Send> SharedComponents<T> for SharedArchetypeColumn<'_, T> {
+    unsafe fn get_mut(&self, entity_id: usize) -> Option<&mut T> {
+        let location = self.locations.get(entity_id)?;
+        if location.row == EMPTY {
+            return None;
+        }
+        Some(&mut *self.tables[location.archetype as usize].add(location.row as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archetypes_move_entities() {
+        let mut archetypes = Archetypes::new();
+        for signature in [0b01, 0b01, 0b11] {
+            archetypes.push_entity(signature);
+        }
+
+        assert_eq!(archetypes.len(), 2);
+        assert_eq!(archetypes.entities(0), &[0, 1]);
+
+        assert_eq!(archetypes.set_signature(0, 0b11), Location { archetype: 1, row: 1 });
+        assert_eq!(archetypes.entities(0), &[1]);
+        assert_eq!(archetypes.location(1), Some(Location { archetype: 0, row: 0 }));
+        assert_eq!(archetypes.matching(0b10, 0).collect::<Vec<u32>>(), vec![1]);
+
+        assert_eq!(archetypes.remove_entity(1), 0b01);
+        assert_eq!(archetypes.entities(1), &[1, 0]);
+        assert_eq!(archetypes.location(0), Some(Location { archetype: 1, row: 1 }));
+    }
+
+    #[test]
+    fn test_column_rows_follow_archetypes() {
+        let mut archetypes = Archetypes::new();
+        let mut column = ArchetypeColumn::new();
+        for i in 0..4 {
+            archetypes.push_entity(0b01);
+            column.push_slot(None);
+            column.insert_into(i, &archetypes, i * 10);
+        }
+
+        // Moving entity 1 to another archetype swaps entity 3 into its row in both
+        archetypes.set_signature(1, 0b11);
+        column.migrate(1, &archetypes);
+        assert_eq!(column.table(0), &[0, 30, 20]);
+        assert_eq!(column.table(1), &[10]);
+        assert_eq!(column.get(1), Some(&10));
+        assert_eq!(column.run(&Run::Table { archetype: 0, entities: archetypes.entities(0) }), Some(&[0, 30, 20][..]));
+
+        archetypes.remove_entity(0);
+        assert_eq!(column.remove_slot_with(0, &archetypes), Some(0));
+        assert_eq!(column.table(0), &[20, 30]);
+        assert_eq!(column.get(0), Some(&10));
+        assert_eq!(column.get(2), Some(&30));
+
+        // Without the archetypes the swapped entity is found by its location
+        assert_eq!(column.remove(1), Some(20));
+        assert_eq!(column.get(2), Some(&30));
+        assert_eq!(column.entity_ids().collect::<Vec<usize>>(), vec![0, 2]);
+    }
+}
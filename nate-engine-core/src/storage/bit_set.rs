@@ -0,0 +1,200 @@
+//!
+//! Bit Set Storage for Zero-Sized Tag Components
+//!
+
+use super::{ComponentStorage, ParallelStorage, SharedComponents};
+
+/// Number of entities tracked by each word of the bit set
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Set of the entities that have a tag component, one bit per entity
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BitSet {
+    // The number of entity slots in the world
+    slots: usize,
+    // The number of set bits
+    count: usize,
+    // The bits for each entity (least significant bit first)
+    words: Vec<u64>,
+    // Storage for the unit tag handed out as a mutable reference
+    unit: (),
+}
+
+impl BitSet {
+    /// Create a new empty bit set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the entity is in the set
+    #[inline]
+    pub fn contains(&self, entity_id: usize) -> bool {
+        match self.words.get(entity_id / WORD_BITS) {
+            Some(word) => word & (1 << (entity_id % WORD_BITS)) != 0,
+            None => false,
+        }
+    }
+
+    /// Add the entity to the set, returning whether it was already present
+    pub fn set(&mut self, entity_id: usize) -> bool {
+        if entity_id >= self.slots {
+            panic!("Entity {} is out of bounds for {} entities", entity_id, self.slots);
+        }
+
+        let word = &mut self.words[entity_id / WORD_BITS];
+        let mask = 1 << (entity_id % WORD_BITS);
+        let present = *word & mask != 0;
+        if !present {
+            *word |= mask;
+            self.count += 1;
+        }
+        present
+    }
+
+    /// Remove the entity from the set, returning whether it was present
+    pub fn unset(&mut self, entity_id: usize) -> bool {
+        let Some(word) = self.words.get_mut(entity_id / WORD_BITS) else {
+            return false;
+        };
+
+        let mask = 1 << (entity_id % WORD_BITS);
+        let present = *word & mask != 0;
+        if present {
+            *word &= !mask;
+            self.count -= 1;
+        }
+        present
+    }
+
+    /// The words backing the set, where bit `i` of word `w` is entity `w * 64 + i`
+    #[inline]
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Iterate over the entities in the set, skipping empty words
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().filter(|v| *v.1 != 0).flat_map(|(index, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(index * WORD_BITS + bit)
+            })
+        })
+    }
+}
+
+impl ComponentStorage<()> for BitSet {
+    fn slots(&self) -> usize {
+        self.slots
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn contains(&self, entity_id: usize) -> bool {
+        BitSet::contains(self, entity_id)
+    }
+
+    fn get(&self, entity_id: usize) -> Option<&()> {
+        BitSet::contains(self, entity_id).then_some(&self.unit)
+    }
+
+    fn get_mut(&mut self, entity_id: usize) -> Option<&mut ()> {
+        BitSet::contains(self, entity_id).then_some(&mut self.unit)
+    }
+
+    fn insert(&mut self, entity_id: usize, _component: ()) -> Option<()> {
+        self.set(entity_id).then_some(())
+    }
+
+    fn remove(&mut self, entity_id: usize) -> Option<()> {
+        self.unset(entity_id).then_some(())
+    }
+
+    fn push_slot(&mut self, component: Option<()>) {
+        self.slots += 1;
+        if self.words.len() * WORD_BITS < self.slots {
+            self.words.push(0);
+        }
+        if component.is_some() {
+            self.set(self.slots - 1);
+        }
+    }
+
+    fn reserve_slots(&mut self, additional: usize) {
+        self.words.reserve(additional.div_ceil(WORD_BITS));
+    }
+
+    fn remove_slot(&mut self, entity_id: usize) -> Option<()> {
+        let present = self.unset(entity_id);
+
+        // Shift every bit above the removed entity down by one
+        let first_word = entity_id / WORD_BITS;
+        let low_mask = (1u64 << (entity_id % WORD_BITS)) - 1;
+        let word = self.words[first_word];
+        self.words[first_word] = (word & low_mask) | ((word >> 1) & !low_mask);
+        for index in first_word..(self.words.len() - 1) {
+            self.words[index] |= (self.words[index + 1] & 1) << (WORD_BITS - 1);
+            self.words[index + 1] >>= 1;
+        }
+
+        self.slots -= 1;
+        self.words.truncate(self.slots.div_ceil(WORD_BITS));
+        present.then_some(())
+    }
+
+    fn entity_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter()
+    }
+}
+
+impl ParallelStorage<()> for BitSet {
+    type Shared<'a> = SharedBitSet<'a>;
+
+    fn shared_mut(&mut self) -> SharedBitSet<'_> {
+        SharedBitSet { bit_set: self }
+    }
+}
+
+/// Shared view of a [`BitSet`] for parallel systems
+pub struct SharedBitSet<'a> {
+    bit_set: &'a BitSet,
+}
+
+unsafe impl SharedComponents<()> for SharedBitSet<'_> {
+    unsafe fn get_mut(&self, entity_id: usize) -> Option<&mut ()> {
+        // Tags have no data, so every entity can be handed its own unit (a dangling pointer is
+        // a valid reference to a zero-sized value)
+        self.bit_set.contains(entity_id).then(|| std::ptr::NonNull::dangling().as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_iterate() {
+        let mut bit_set = BitSet::new();
+        for _ in 0..200 {
+            bit_set.push_slot(None);
+        }
+
+        assert!(!bit_set.set(3));
+        assert!(!bit_set.set(64));
+        assert!(!bit_set.set(199));
+        assert!(bit_set.set(3));
+        assert_eq!(bit_set.count(), 3);
+        assert_eq!(bit_set.iter().collect::<Vec<usize>>(), vec![3, 64, 199]);
+
+        assert!(bit_set.unset(64));
+        assert!(!bit_set.contains(64));
+        assert_eq!(bit_set.iter().collect::<Vec<usize>>(), vec![3, 199]);
+    }
+}
@@ -0,0 +1,319 @@
+//!
+//! Dense Storage with a Separate Presence Bit Set
+//!
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+use super::{BitSet, ComponentStorage, ParallelStorage, Run, SharedComponents};
+
+/// Number of entities tracked by each word of the presence bits
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Components stored one slot per entity without an `Option` around each of them.
+///
+/// Whether an entity has the component is kept in a bit set next to the slots, so each slot is
+/// exactly the size of the component and runs of entities that all have the component can be
+/// handed out as plain slices (see [`DenseColumn::runs_mut`]).
+pub struct DenseColumn<T> {
+    // Which slots hold an initialized component
+    presence: BitSet,
+    // The component of every entity (only initialized where the presence bit is set)
+    values: Vec<MaybeUninit<T>>,
+}
+
+impl<T> Default for DenseColumn<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DenseColumn<T> {
+    /// Create a new empty column
+    pub fn new() -> Self {
+        Self {
+            presence: BitSet::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// The presence bits of the column
+    pub fn presence(&self) -> &BitSet {
+        &self.presence
+    }
+
+    #[inline]
+    fn present(&self, entity_id: usize) -> bool {
+        match self.presence.words().get(entity_id / WORD_BITS) {
+            Some(word) => word & (1 << (entity_id % WORD_BITS)) != 0,
+            None => false,
+        }
+    }
+
+    /// Iterate over the runs of consecutive entities that have the component, as the first
+    /// entity id of the run and the run's components
+    pub fn runs(&self) -> impl Iterator<Item = (usize, &[T])> + '_ {
+        let mut from = 0;
+        std::iter::from_fn(move || {
+            let (start, end) = next_run(self.presence.words(), self.values.len(), from)?;
+            from = end;
+            // SAFETY: every slot in the run has its presence bit set, so it is initialized
+            let run = unsafe { std::slice::from_raw_parts(self.values.as_ptr().add(start) as *const T, end - start) };
+            Some((start, run))
+        })
+    }
+
+    /// Iterate over the runs of consecutive entities that have the component, as the first
+    /// entity id of the run and the run's components (mutably)
+    pub fn runs_mut(&mut self) -> RunsMut<'_, T> {
+        RunsMut {
+            words: self.presence.words(),
+            slots: self.values.len(),
+            values: self.values.as_mut_ptr(),
+            from: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Iterator over the runs of consecutive entities with a component in a [`DenseColumn`]
+pub struct RunsMut<'a, T> {
+    // The presence bits of the column
+    words: &'a [u64],
+    // The number of entity slots in the column
+    slots: usize,
+    // The start of the column's slots
+    values: *mut MaybeUninit<T>,
+    // The entity to start looking for the next run from
+    from: usize,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for RunsMut<'a, T> {
+    type Item = (usize, &'a mut [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, end) = next_run(self.words, self.slots, self.from)?;
+        self.from = end;
+        // SAFETY: every slot in the run is initialized and runs never overlap, so each mutable
+        // slice is only handed out once
+        let run = unsafe { std::slice::from_raw_parts_mut(self.values.add(start) as *mut T, end - start) };
+        Some((start, run))
+    }
+}
+
+/// The first entity at or after `from` whose presence bit is `present`
+fn find_bit(words: &[u64], from: usize, present: bool) -> Option<usize> {
+    let mut index = from / WORD_BITS;
+    if index >= words.len() {
+        return None;
+    }
+
+    let load = |index: usize| if present { words[index] } else { !words[index] };
+    let mut word = load(index) & (u64::MAX << (from % WORD_BITS));
+    loop {
+        if word != 0 {
+            return Some(index * WORD_BITS + word.trailing_zeros() as usize);
+        }
+        index += 1;
+        if index >= words.len() {
+            return None;
+        }
+        word = load(index);
+    }
+}
+
+/// Whether every entity in the range has its presence bit set
+fn all_present(words: &[u64], slots: usize, range: &std::ops::Range<usize>) -> bool {
+    range.end <= slots && !matches!(find_bit(words, range.start, false), Some(v) if v < range.end)
+}
+
+/// The bounds of the next run of present entities at or after `from`
+fn next_run(words: &[u64], slots: usize, from: usize) -> Option<(usize, usize)> {
+    let start = find_bit(words, from, true)?;
+    let end = find_bit(words, start, false).unwrap_or(slots).min(slots);
+    Some((start, end))
+}
+
+impl<T> ComponentStorage<T> for DenseColumn<T> {
+    fn slots(&self) -> usize {
+        self.values.len()
+    }
+
+    fn count(&self) -> usize {
+        ComponentStorage::count(&self.presence)
+    }
+
+    fn contains(&self, entity_id: usize) -> bool {
+        self.presence.contains(entity_id)
+    }
+
+    #[inline]
+    fn get(&self, entity_id: usize) -> Option<&T> {
+        if !self.present(entity_id) {
+            return None;
+        }
+        // SAFETY: presence bits are only set for initialized slots, which are all in bounds
+        Some(unsafe { self.values.get_unchecked(entity_id).assume_init_ref() })
+    }
+
+    #[inline]
+    fn get_mut(&mut self, entity_id: usize) -> Option<&mut T> {
+        if !self.present(entity_id) {
+            return None;
+        }
+        // SAFETY: presence bits are only set for initialized slots, which are all in bounds
+        Some(unsafe { self.values.get_unchecked_mut(entity_id).assume_init_mut() })
+    }
+
+    fn insert(&mut self, entity_id: usize, component: T) -> Option<T> {
+        if self.presence.set(entity_id) {
+            // SAFETY: the slot was already present, so it is initialized
+            Some(std::mem::replace(unsafe { self.values[entity_id].assume_init_mut() }, component))
+        } else {
+            self.values[entity_id].write(component);
+            None
+        }
+    }
+
+    fn remove(&mut self, entity_id: usize) -> Option<T> {
+        if !self.presence.unset(entity_id) {
+            return None;
+        }
+        // SAFETY: the slot was present, and is no longer marked as initialized so it is only read once
+        Some(unsafe { self.values[entity_id].assume_init_read() })
+    }
+
+    fn push_slot(&mut self, component: Option<T>) {
+        self.presence.push_slot(component.as_ref().map(|_v| ()));
+        self.values.push(match component {
+            Some(component) => MaybeUninit::new(component),
+            None => MaybeUninit::uninit(),
+        });
+    }
+
+    fn reserve_slots(&mut self, additional: usize) {
+        self.presence.reserve_slots(additional);
+        self.values.reserve(additional);
+    }
+
+    fn remove_slot(&mut self, entity_id: usize) -> Option<T> {
+        let component = self.remove(entity_id);
+        self.presence.remove_slot(entity_id);
+        self.values.remove(entity_id);
+        component
+    }
+
+    fn entity_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.presence.iter()
+    }
+
+    fn run(&self, run: &Run<'_>) -> Option<&[T]> {
+        match run {
+            Run::Range(range) if all_present(self.presence.words(), self.values.len(), range) => {
+                // SAFETY: every slot in the range has its presence bit set, so it is initialized
+                Some(unsafe { std::slice::from_raw_parts(self.values.as_ptr().add(range.start) as *const T, range.len()) })
+            },
+            _ => None,
+        }
+    }
+
+    fn run_mut(&mut self, run: &Run<'_>) -> Option<&mut [T]> {
+        match run {
+            Run::Range(range) if all_present(self.presence.words(), self.values.len(), range) => {
+                // SAFETY: every slot in the range has its presence bit set, so it is initialized
+                Some(unsafe { std::slice::from_raw_parts_mut(self.values.as_mut_ptr().add(range.start) as *mut T, range.len()) })
+            },
+            _ => None,
+        }
+    }
+}
+
+impl<T: Send> ParallelStorage<T> for DenseColumn<T> {
+    type Shared<'a> = SharedDenseColumn<'a, T> where T: 'a;
+
+    fn shared_mut(&mut self) -> SharedDenseColumn<'_, T> {
+        SharedDenseColumn {
+            words: self.presence.words(),
+            values: self.values.as_mut_ptr(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Shared view of a [`DenseColumn`] for parallel systems
+pub struct SharedDenseColumn<'a, T> {
+    // The presence bits of the column
+    words: &'a [u64],
+    // The start of the column's slots
+    values: *mut MaybeUninit<T>,
+    marker: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for SharedDenseColumn<'_, T> {}
+unsafe impl<T: Send> Sync for SharedDenseColumn<'_, T> {}
+
+unsafe impl<T: Send> SharedComponents<T> for SharedDenseColumn<'_, T> {
+    unsafe fn get_mut(&self, entity_id: usize) -> Option<&mut T> {
+        let word = self.words.get(entity_id / WORD_BITS)?;
+        if word & (1 << (entity_id % WORD_BITS)) == 0 {
+            return None;
+        }
+        Some((*self.values.add(entity_id)).assume_init_mut())
+    }
+}
+
+impl<T> Drop for DenseColumn<T> {
+    fn drop(&mut self) {
+        for entity_id in self.presence.iter() {
+            // SAFETY: every present slot is initialized and dropped exactly once
+            unsafe { self.values[entity_id].assume_init_drop() };
+        }
+    }
+}
+
+impl<T: Clone> Clone for DenseColumn<T> {
+    fn clone(&self) -> Self {
+        let mut column = Self::new();
+        column.reserve_slots(self.values.len());
+        for entity_id in 0..self.values.len() {
+            column.push_slot(self.get(entity_id).cloned());
+        }
+        column
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DenseColumn<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.presence.iter().map(|v| (v, self.get(v)))).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_are_contiguous_slices() {
+        let mut column = DenseColumn::new();
+        for i in 0..200usize {
+            column.push_slot((!(60..70).contains(&i) && i != 130).then_some(i));
+        }
+
+        let runs: Vec<(usize, usize)> = column.runs().map(|v| (v.0, v.1.len())).collect();
+        assert_eq!(runs, vec![(0, 60), (70, 60), (131, 69)]);
+
+        for (start, run) in column.runs_mut() {
+            for (offset, value) in run.iter_mut().enumerate() {
+                assert_eq!(*value, start + offset);
+                *value *= 2;
+            }
+        }
+        assert_eq!(column.get(199), Some(&398));
+
+        assert_eq!(column.run(&Run::Range(70..130)).map(|v| v.len()), Some(60));
+        assert_eq!(column.run(&Run::Range(120..140)), None);
+        assert_eq!(column.run_mut(&Run::Range(190..201)), None);
+    }
+}
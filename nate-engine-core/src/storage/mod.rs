@@ -0,0 +1,233 @@
+//!
+//! Storage Backends for Entity Components
+//!
+//! Every per-entity component of a world is kept in a type implementing [`ComponentStorage`].
+//! By default this is a `Vec<Option<T>>` with one slot per entity, but components that only a
+//! few entities have can be stored in a [`SparseSet`] instead, components that should be stored
+//! without an `Option` around each slot can be stored in a [`DenseColumn`], and zero-sized tag
+//! components are stored as a [`BitSet`]. Worlds declared with `#[world(storage = archetype)]` store their
+//! components in [`ArchetypeColumn`]s instead, which group entities with the same components
+//! into dense tables.
+//!
+//! Alongside the components, each world keeps a [`Signatures`] index of which components every
+//! entity has, which systems use to skip non-matching entities 64 at a time.
+//!
+
+mod archetype;
+pub use archetype::{ArchetypeColumn, Archetypes, Location, SharedArchetypeColumn};
+
+mod bit_set;
+pub use bit_set::{BitSet, SharedBitSet};
+
+mod dense_column;
+pub use dense_column::{DenseColumn, RunsMut, SharedDenseColumn};
+
+mod signatures;
+pub use signatures::{Matching, Run, Runs, Signature, Signatures, MAX_COMPONENTS};
+
+mod sparse_set;
+pub use sparse_set::{SharedSparseSet, SparseSet};
+
+use std::marker::PhantomData;
+
+/// Storage of a single component type for every entity in a world, indexed by entity id
+pub trait ComponentStorage<T> {
+    /// The number of entity slots in the storage
+    fn slots(&self) -> usize;
+
+    /// The number of entities that have the component
+    fn count(&self) -> usize;
+
+    /// Whether the given entity has the component
+    fn contains(&self, entity_id: usize) -> bool;
+
+    /// Get a reference to the entity's component
+    fn get(&self, entity_id: usize) -> Option<&T>;
+
+    /// Get a mutable reference to the entity's component
+    fn get_mut(&mut self, entity_id: usize) -> Option<&mut T>;
+
+    /// Set the entity's component, returning the previous component.
+    ///
+    /// Panics if the entity does not have a slot in the storage
+    fn insert(&mut self, entity_id: usize, component: T) -> Option<T>;
+
+    /// Remove the entity's component, returning it
+    fn remove(&mut self, entity_id: usize) -> Option<T>;
+
+    /// Add a slot for a new entity at the end of the storage
+    fn push_slot(&mut self, component: Option<T>);
+
+    /// Reserve space for at least `additional` more entity slots
+    fn reserve_slots(&mut self, additional: usize);
+
+    /// Remove the entity's slot, shifting the ids of every later entity down by one
+    fn remove_slot(&mut self, entity_id: usize) -> Option<T>;
+
+    /// Iterate over the ids of the entities that have the component (in storage order)
+    fn entity_ids(&self) -> impl Iterator<Item = usize> + '_;
+
+    /// The components of every entity in the run as a single slice, if the storage keeps them
+    /// next to each other in run order (`None` otherwise, in which case systems look the
+    /// components up one entity at a time)
+    fn run(&self, _run: &Run<'_>) -> Option<&[T]> {
+        None
+    }
+
+    /// The components of every entity in the run as a single mutable slice, if the storage keeps
+    /// them next to each other in run order
+    fn run_mut(&mut self, _run: &Run<'_>) -> Option<&mut [T]> {
+        None
+    }
+}
+
+/// Storage that can hand out the components of different entities to several threads at once,
+/// used by parallel systems to write to disjoint chunks of entities
+pub trait ParallelStorage<T>: ComponentStorage<T> {
+    /// View of the storage that is shared between the threads
+    type Shared<'a>: SharedComponents<T> where Self: 'a;
+
+    /// Borrow the storage so its components can be written from several threads
+    fn shared_mut(&mut self) -> Self::Shared<'_>;
+}
+
+/// Mutable access to the components of a storage from several threads.
+///
+/// # Safety
+///
+/// `get_mut` must only access the slot of the requested entity, so calls for different entities
+/// never touch the same memory
+pub unsafe trait SharedComponents<T>: Send + Sync {
+    /// Get a mutable reference to the entity's component.
+    ///
+    /// # Safety
+    ///
+    /// There must be no other live reference to the same entity's component
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get_mut(&self, entity_id: usize) -> Option<&mut T>;
+}
+
+impl<T> ComponentStorage<T> for Vec<Option<T>> {
+    fn slots(&self) -> usize {
+        self.len()
+    }
+
+    fn count(&self) -> usize {
+        self.iter().filter(|v| v.is_some()).count()
+    }
+
+    fn contains(&self, entity_id: usize) -> bool {
+        matches!(self.as_slice().get(entity_id), Some(Some(_)))
+    }
+
+    fn get(&self, entity_id: usize) -> Option<&T> {
+        self.as_slice().get(entity_id)?.as_ref()
+    }
+
+    fn get_mut(&mut self, entity_id: usize) -> Option<&mut T> {
+        self.as_mut_slice().get_mut(entity_id)?.as_mut()
+    }
+
+    fn insert(&mut self, entity_id: usize, component: T) -> Option<T> {
+        self[entity_id].replace(component)
+    }
+
+    fn remove(&mut self, entity_id: usize) -> Option<T> {
+        self.as_mut_slice().get_mut(entity_id)?.take()
+    }
+
+    fn push_slot(&mut self, component: Option<T>) {
+        self.push(component);
+    }
+
+    fn reserve_slots(&mut self, additional: usize) {
+        self.reserve(additional);
+    }
+
+    fn remove_slot(&mut self, entity_id: usize) -> Option<T> {
+        Vec::remove(self, entity_id)
+    }
+
+    fn entity_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter().enumerate().filter(|v| v.1.is_some()).map(|v| v.0)
+    }
+}
+
+impl<T: Send> ParallelStorage<T> for Vec<Option<T>> {
+    type Shared<'a> = SharedSlots<'a, T> where T: 'a;
+
+    fn shared_mut(&mut self) -> SharedSlots<'_, T> {
+        SharedSlots {
+            slots: self.as_mut_ptr(),
+            len: self.len(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Shared view of a `Vec<Option<T>>` for parallel systems
+pub struct SharedSlots<'a, T> {
+    // The start of the slots
+    slots: *mut Option<T>,
+    // The number of slots
+    len: usize,
+    marker: PhantomData<&'a mut [Option<T>]>,
+}
+
+unsafe impl<T: Send> Send for SharedSlots<'_, T> {}
+unsafe impl<T: Send> Sync for SharedSlots<'_, T> {}
+
+unsafe impl<T: Send> SharedComponents<T> for SharedSlots<'_, T> {
+    unsafe fn get_mut(&self, entity_id: usize) -> Option<&mut T> {
+        if entity_id >= self.len {
+            return None;
+        }
+        (*self.slots.add(entity_id)).as_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fmt::Debug;
+
+    /// Run the storage through the same inserts, removals and slot removals as a
+    /// `Vec<Option<T>>`, checking it gives the same results
+    fn check_storage<T: Clone + PartialEq + Debug, S: ComponentStorage<T>>(mut storage: S, value: impl Fn(usize) -> T) {
+        let mut slots: Vec<Option<T>> = Vec::new();
+        // Enough slots to cross the 64 bit words of bitsets
+        for entity_id in 0..150 {
+            let component = (entity_id % 3 == 0).then(|| value(entity_id));
+            storage.push_slot(component.clone());
+            ComponentStorage::push_slot(&mut slots, component);
+        }
+
+        for entity_id in [1, 3, 64, 149] {
+            assert_eq!(storage.insert(entity_id, value(entity_id + 1)), ComponentStorage::insert(&mut slots, entity_id, value(entity_id + 1)));
+        }
+        for entity_id in [6, 6, 2, 63] {
+            assert_eq!(storage.remove(entity_id), ComponentStorage::remove(&mut slots, entity_id));
+        }
+        for entity_id in [0, 64, 1, 145] {
+            assert_eq!(storage.remove_slot(entity_id), ComponentStorage::remove_slot(&mut slots, entity_id));
+        }
+
+        assert_eq!(storage.slots(), ComponentStorage::slots(&slots));
+        assert_eq!(storage.count(), ComponentStorage::count(&slots));
+        let mut entity_ids: Vec<usize> = storage.entity_ids().collect();
+        entity_ids.sort_unstable();
+        assert_eq!(entity_ids, ComponentStorage::entity_ids(&slots).collect::<Vec<usize>>());
+        for entity_id in 0..slots.len() + 1 {
+            assert_eq!(storage.contains(entity_id), ComponentStorage::contains(&slots, entity_id));
+            assert_eq!(storage.get(entity_id), ComponentStorage::get(&slots, entity_id));
+        }
+    }
+
+    #[test]
+    fn test_storages_match_slots() {
+        check_storage(SparseSet::new(), |v| v.to_string());
+        check_storage(DenseColumn::new(), |v| v.to_string());
+        check_storage(BitSet::new(), |_| ());
+    }
+}
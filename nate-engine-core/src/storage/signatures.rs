@@ -0,0 +1,486 @@
+//!
+//! Per-Entity Component Presence Bitmasks (Signatures)
+//!
+
+use std::ops::Range;
+
+use super::{Archetypes, BitSet, ComponentStorage};
+
+/// Bitmask of the components an entity has, where bit `i` is the world's `i`th component
+pub type Signature = u64;
+
+/// The maximum number of components (including tags) a world can have in its signatures
+pub const MAX_COMPONENTS: usize = Signature::BITS as usize;
+
+/// Number of entities covered by each word of a component's presence bits
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Index of which components every entity in a world has.
+///
+/// The presence of each component is kept in its own bit set so queries can test 64
+/// entities at a time and skip words where no entity matches.
+#[derive(Clone, Debug, Default)]
+pub struct Signatures {
+    // The number of entity slots in the world
+    slots: usize,
+    // Presence bits for each component
+    components: Vec<BitSet>,
+    // The archetype of every entity (only kept for worlds with archetype storage)
+    archetypes: Option<Archetypes>,
+}
+
+impl Signatures {
+    /// Create a presence index for a world with the given number of components
+    pub fn new(components: usize) -> Self {
+        if components > MAX_COMPONENTS {
+            panic!("Worlds can have at most {} components, not {}", MAX_COMPONENTS, components);
+        }
+
+        Self {
+            slots: 0,
+            components: (0..components).map(|_v| BitSet::new()).collect(),
+            archetypes: None,
+        }
+    }
+
+    /// Create a presence index that also groups entities into archetypes, so queries visit
+    /// entities table by table
+    pub fn with_archetypes(components: usize) -> Self {
+        Self {
+            archetypes: Some(Archetypes::new()),
+            ..Self::new(components)
+        }
+    }
+
+    /// The archetypes of the world's entities, if they are being kept
+    pub fn archetypes(&self) -> Option<&Archetypes> {
+        self.archetypes.as_ref()
+    }
+
+    /// The archetype of an entity, if archetypes are being kept
+    pub fn archetype(&self, entity_id: usize) -> Option<u32> {
+        Some(self.archetypes.as_ref()?.location(entity_id)?.archetype)
+    }
+
+    /// The number of entity slots in the index
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// The number of entities with the component
+    pub fn count(&self, component: u32) -> usize {
+        ComponentStorage::count(&self.components[component as usize])
+    }
+
+    /// The presence bits of a single component
+    pub fn component(&self, component: u32) -> &BitSet {
+        &self.components[component as usize]
+    }
+
+    /// The signature of an entity
+    pub fn signature(&self, entity_id: usize) -> Signature {
+        if let Some(archetypes) = &self.archetypes {
+            return match archetypes.location(entity_id) {
+                Some(location) => archetypes.signature(location.archetype),
+                None => 0,
+            };
+        }
+
+        self.components.iter().enumerate()
+            .filter(|v| v.1.contains(entity_id))
+            .fold(0, |signature, (component, _)| signature | (1 << component))
+    }
+
+    /// Whether the entity has every required component and none of the excluded components
+    pub fn matches(&self, entity_id: usize, required: Signature, excluded: Signature) -> bool {
+        let signature = self.signature(entity_id);
+        entity_id < self.slots && signature & required == required && signature & excluded == 0
+    }
+
+    /// Mark the entity as having the component
+    pub fn insert(&mut self, entity_id: usize, component: u32) {
+        self.components[component as usize].set(entity_id);
+        let signature = self.signature(entity_id) | (1 << component);
+        if let Some(archetypes) = &mut self.archetypes {
+            archetypes.set_signature(entity_id, signature);
+        }
+    }
+
+    /// Mark the entity as not having the component
+    pub fn remove(&mut self, entity_id: usize, component: u32) {
+        self.components[component as usize].unset(entity_id);
+        let signature = self.signature(entity_id) & !(1 << component);
+        match &mut self.archetypes {
+            Some(archetypes) if entity_id < self.slots => {
+                archetypes.set_signature(entity_id, signature);
+            },
+            _ => (),
+        }
+    }
+
+    /// Add a new entity with the given signature
+    pub fn push_entity(&mut self, signature: Signature) {
+        self.slots += 1;
+        if let Some(archetypes) = &mut self.archetypes {
+            archetypes.push_entity(signature);
+        }
+        for (component, bits) in self.components.iter_mut().enumerate() {
+            bits.push_slot((signature & (1 << component) != 0).then_some(()));
+        }
+    }
+
+    /// Reserve space for at least `additional` more entities
+    pub fn reserve(&mut self, additional: usize) {
+        for bits in self.components.iter_mut() {
+            bits.reserve_slots(additional);
+        }
+        if let Some(archetypes) = &mut self.archetypes {
+            archetypes.reserve(additional);
+        }
+    }
+
+    /// Remove an entity, shifting the ids of every later entity down by one and returning the
+    /// signature of the removed entity
+    pub fn remove_entity(&mut self, entity_id: usize) -> Signature {
+        let signature = self.signature(entity_id);
+        for bits in self.components.iter_mut() {
+            bits.remove_slot(entity_id);
+        }
+        if let Some(archetypes) = &mut self.archetypes {
+            archetypes.remove_entity(entity_id);
+        }
+        self.slots -= 1;
+        signature
+    }
+
+    /// Iterate over the entities that have every required component and none of the excluded
+    /// components.
+    ///
+    /// Without archetypes this checks a word (64 entities) at a time, starting with the required
+    /// component with the fewest entities so words are usually ruled out by the smallest
+    /// participating set. With archetypes the matching archetypes are visited one after another
+    /// in row order.
+    pub fn matching(&self, required: Signature, excluded: Signature) -> Matching<'_> {
+        if let Some(archetypes) = &self.archetypes {
+            return Matching {
+                kind: MatchingKind::Tables {
+                    tables: archetypes.matching(required, excluded).map(|v| archetypes.entities(v)).collect(),
+                    table: 0,
+                    row: 0,
+                },
+            };
+        }
+
+        let mut required_components = Vec::with_capacity(required.count_ones() as usize);
+        let mut components = required;
+        while components != 0 {
+            required_components.push(&self.components[components.trailing_zeros() as usize]);
+            components &= components - 1;
+        }
+        required_components.sort_by_key(|v| ComponentStorage::count(*v));
+
+        let mut excluded_components = Vec::with_capacity(excluded.count_ones() as usize);
+        let mut components = excluded;
+        while components != 0 {
+            excluded_components.push(&self.components[components.trailing_zeros() as usize]);
+            components &= components - 1;
+        }
+
+        Matching {
+            kind: MatchingKind::Words {
+                slots: self.slots,
+                required: required_components,
+                excluded: excluded_components,
+                index: 0,
+                word: 0,
+            },
+        }
+    }
+
+    /// Iterate over the entities matching the query in runs, which storages can hand out as
+    /// slices of their components (see [`ComponentStorage::run`]).
+    ///
+    /// With archetypes each matching archetype is one run. Without them consecutive matching
+    /// entity ids are joined into ranges, so a word where every entity matches extends the run
+    /// instead of ending it.
+    pub fn runs(&self, required: Signature, excluded: Signature) -> Runs<'_> {
+        if let Some(archetypes) = &self.archetypes {
+            let tables: Vec<(u32, &[usize])> = archetypes.matching(required, excluded)
+                .map(|v| (v, archetypes.entities(v)))
+                .filter(|v| !v.1.is_empty())
+                .collect();
+            return Runs {
+                kind: RunsKind::Tables { tables: tables.into_iter() },
+            };
+        }
+
+        let MatchingKind::Words { slots, required, excluded, .. } = self.matching(required, excluded).kind else {
+            unreachable!("Signatures without archetypes match by words");
+        };
+        Runs {
+            kind: RunsKind::Words { slots, required, excluded, index: 0, word: 0, pending: None },
+        }
+    }
+}
+
+/// Entities matching a query whose components storages may keep next to each other
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Run<'a> {
+    /// A range of consecutive entity ids
+    Range(Range<usize>),
+    /// Every entity of an archetype, in row order
+    Table {
+        archetype: u32,
+        entities: &'a [usize],
+    },
+}
+
+impl Run<'_> {
+    /// The number of entities in the run
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            Run::Range(range) => range.len(),
+            Run::Table { entities, .. } => entities.len(),
+        }
+    }
+
+    /// Whether the run has no entities
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The id of the entity at the index of the run (`index` must be less than the run's
+    /// length)
+    #[inline]
+    pub fn entity_id(&self, index: usize) -> usize {
+        match self {
+            Run::Range(range) => range.start + index,
+            // Indices past the end get an id past every entity instead of panicking, so systems
+            // that never read their entity id don't pay for the lookup
+            Run::Table { entities, .. } => entities.get(index).copied().unwrap_or(usize::MAX),
+        }
+    }
+}
+
+/// Iterator over the runs of entities matching a query on the world's signatures
+pub struct Runs<'a> {
+    kind: RunsKind<'a>,
+}
+
+enum RunsKind<'a> {
+    // Scanning the presence bits a word at a time
+    Words {
+        // The number of entity slots in the world
+        slots: usize,
+        // Presence bits of the required components (fewest entities first)
+        required: Vec<&'a BitSet>,
+        // Presence bits of the excluded components
+        excluded: Vec<&'a BitSet>,
+        // Index of the next word to check
+        index: usize,
+        // The remaining matching entities in the current word
+        word: u64,
+        // The run found so far, which the next matching entities may extend
+        pending: Option<Range<usize>>,
+    },
+    // Each matching archetype with entities
+    Tables {
+        tables: std::vec::IntoIter<(u32, &'a [usize])>,
+    },
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = Run<'a>;
+
+    fn next(&mut self) -> Option<Run<'a>> {
+        match &mut self.kind {
+            RunsKind::Words { slots, required, excluded, index, word, pending } => {
+                let words = slots.div_ceil(WORD_BITS);
+                loop {
+                    while *word == 0 {
+                        if *index >= words {
+                            return pending.take().map(Run::Range);
+                        }
+                        *word = word_at(*slots, required, excluded, *index);
+                        *index += 1;
+                    }
+
+                    let bit = word.trailing_zeros();
+                    let ones = (*word >> bit).trailing_ones();
+                    *word = if bit + ones == u64::BITS { 0 } else { *word & (u64::MAX << (bit + ones)) };
+
+                    let start = (*index - 1) * WORD_BITS + bit as usize;
+                    let end = start + ones as usize;
+                    match pending {
+                        Some(run) if run.end == start => run.end = end,
+                        Some(run) => return Some(Run::Range(std::mem::replace(run, start..end))),
+                        None => *pending = Some(start..end),
+                    }
+                }
+            },
+            RunsKind::Tables { tables } => {
+                let (archetype, entities) = tables.next()?;
+                Some(Run::Table { archetype, entities })
+            },
+        }
+    }
+}
+
+/// Iterator over the entities matching a query on the world's signatures
+pub struct Matching<'a> {
+    kind: MatchingKind<'a>,
+}
+
+enum MatchingKind<'a> {
+    // Scanning the presence bits a word at a time
+    Words {
+        // The number of entity slots in the world
+        slots: usize,
+        // Presence bits of the required components (fewest entities first)
+        required: Vec<&'a BitSet>,
+        // Presence bits of the excluded components
+        excluded: Vec<&'a BitSet>,
+        // Index of the next word to check
+        index: usize,
+        // The remaining matching entities in the current word
+        word: u64,
+    },
+    // Walking the entities of the matching archetypes
+    Tables {
+        // The entities of each matching archetype
+        tables: Vec<&'a [usize]>,
+        // Index of the current archetype
+        table: usize,
+        // Index of the next row in the current archetype
+        row: usize,
+    },
+}
+
+/// The matching entities of the word at the given index
+fn word_at(slots: usize, required: &[&BitSet], excluded: &[&BitSet], index: usize) -> u64 {
+    let mut word = u64::MAX;
+    if (index + 1) * WORD_BITS > slots {
+        word = (1 << (slots % WORD_BITS)) - 1;
+    }
+
+    for bits in required.iter() {
+        word &= bits.words()[index];
+        if word == 0 {
+            return 0;
+        }
+    }
+
+    for bits in excluded.iter() {
+        word &= !bits.words()[index];
+    }
+
+    word
+}
+
+impl Iterator for Matching<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match &mut self.kind {
+            MatchingKind::Words { slots, required, excluded, index, word } => {
+                let words = slots.div_ceil(WORD_BITS);
+                while *word == 0 {
+                    if *index >= words {
+                        return None;
+                    }
+                    *word = word_at(*slots, required, excluded, *index);
+                    *index += 1;
+                }
+
+                let bit = word.trailing_zeros() as usize;
+                *word &= *word - 1;
+                Some((*index - 1) * WORD_BITS + bit)
+            },
+            MatchingKind::Tables { tables, table, row } => {
+                while *table < tables.len() {
+                    if let Some(entity_id) = tables[*table].get(*row) {
+                        *row += 1;
+                        return Some(*entity_id);
+                    }
+                    *table += 1;
+                    *row = 0;
+                }
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_skips_words() {
+        let mut signatures = Signatures::new(3);
+        for entity_id in 0..1_000 {
+            let mut signature = 0b001;
+            if entity_id % 100 == 0 {
+                signature |= 0b010;
+            }
+            if entity_id % 200 == 0 {
+                signature |= 0b100;
+            }
+            signatures.push_entity(signature);
+        }
+
+        assert_eq!(signatures.matching(0b011, 0).collect::<Vec<usize>>(), (0..1_000).step_by(100).collect::<Vec<usize>>());
+        assert_eq!(signatures.matching(0b011, 0b100).collect::<Vec<usize>>(), (100..1_000).step_by(200).collect::<Vec<usize>>());
+        assert_eq!(signatures.matching(0, 0).count(), 1_000);
+        assert!(signatures.matches(200, 0b111, 0));
+        assert!(!signatures.matches(200, 0b001, 0b100));
+
+        // Runs join matching entities across words and split around the excluded ones
+        let runs: Vec<Run> = signatures.runs(0b001, 0b100).collect();
+        assert_eq!(runs.len(), 5);
+        assert_eq!(runs[0], Run::Range(1..200));
+        assert_eq!(runs[4], Run::Range(801..1_000));
+        assert_eq!(signatures.runs(0b010, 0).map(|v| v.len()).sum::<usize>(), 10);
+    }
+
+    #[test]
+    fn test_insert_remove_and_remove_entity() {
+        let mut signatures = Signatures::new(2);
+        for _ in 0..3 {
+            signatures.push_entity(0);
+        }
+
+        signatures.insert(1, 0);
+        signatures.insert(2, 1);
+        assert_eq!(signatures.signature(1), 0b01);
+        assert_eq!(signatures.count(1), 1);
+
+        assert_eq!(signatures.remove_entity(0), 0);
+        assert_eq!(signatures.signature(0), 0b01);
+        assert_eq!(signatures.signature(1), 0b10);
+
+        signatures.remove(1, 1);
+        assert_eq!(signatures.matching(0b10, 0).count(), 0);
+    }
+
+    #[test]
+    fn test_matching_visits_archetypes() {
+        let mut signatures = Signatures::with_archetypes(2);
+        for entity_id in 0..6 {
+            signatures.push_entity(if entity_id % 2 == 0 { 0b01 } else { 0b11 });
+        }
+
+        signatures.remove(1, 1);
+        signatures.insert(4, 1);
+        assert_eq!(signatures.signature(1), 0b01);
+        assert_eq!(signatures.archetypes().unwrap().len(), 2);
+        assert_eq!(signatures.matching(0b01, 0b10).collect::<Vec<usize>>(), vec![0, 2, 1]);
+        assert_eq!(signatures.matching(0b11, 0).collect::<Vec<usize>>(), vec![5, 3, 4]);
+        assert_eq!(signatures.runs(0b11, 0).collect::<Vec<Run>>(), vec![Run::Table { archetype: 1, entities: &[5, 3, 4] }]);
+
+        assert_eq!(signatures.remove_entity(0), 0b01);
+        assert_eq!(signatures.archetype(0), Some(0));
+        assert_eq!(signatures.matching(0b01, 0).collect::<Vec<usize>>(), vec![0, 1, 4, 2, 3]);
+    }
+}
@@ -0,0 +1,205 @@
+//!
+//! Sparse Set Storage for Components that Few Entities Have
+//!
+
+use std::marker::PhantomData;
+
+use super::{ComponentStorage, ParallelStorage, SharedComponents};
+
+/// Number of entity slots in each lazily allocated page of the sparse array
+const PAGE_SIZE: usize = 256;
+/// Marker in the sparse array for an entity without the component
+const EMPTY: usize = usize::MAX;
+
+/// Sparse set of components.
+///
+/// Components are packed densely so iteration only touches entities that have the
+/// component, and the entity -> component lookup is split into pages that are only
+/// allocated once an entity in that page has the component.
+#[derive(Clone, Debug)]
+pub struct SparseSet<T> {
+    // The number of entity slots in the world
+    slots: usize,
+    // Paged entity id -> dense index lookup
+    sparse: Vec<Option<Box<[usize; PAGE_SIZE]>>>,
+    // Densely packed components
+    dense: Vec<T>,
+    // Entity id of each densely packed component
+    entities: Vec<usize>,
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SparseSet<T> {
+    /// Create a new empty sparse set
+    pub fn new() -> Self {
+        Self {
+            slots: 0,
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    /// The densely packed components
+    pub fn as_slice(&self) -> &[T] {
+        &self.dense
+    }
+
+    /// The densely packed components (mutably)
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.dense
+    }
+
+    /// The entity id of each densely packed component
+    pub fn entities(&self) -> &[usize] {
+        &self.entities
+    }
+
+    /// Iterate over (entity id, component) pairs
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.entities.iter().copied().zip(self.dense.iter())
+    }
+
+    /// Iterate over (entity id, component) pairs with mutable components
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.entities.iter().copied().zip(self.dense.iter_mut())
+    }
+
+    fn dense_index(&self, entity_id: usize) -> Option<usize> {
+        dense_index(&self.sparse, entity_id)
+    }
+
+    fn set_dense_index(&mut self, entity_id: usize, index: usize) {
+        let page_index = entity_id / PAGE_SIZE;
+        if self.sparse.len() <= page_index {
+            self.sparse.resize_with(page_index + 1, || None);
+        }
+        let page = self.sparse[page_index].get_or_insert_with(|| Box::new([EMPTY; PAGE_SIZE]));
+        page[entity_id % PAGE_SIZE] = index;
+    }
+}
+
+/// Look up the dense index of an entity in the paged sparse array
+fn dense_index(sparse: &[Option<Box<[usize; PAGE_SIZE]>>], entity_id: usize) -> Option<usize> {
+    let page = sparse.get(entity_id / PAGE_SIZE)?.as_deref()?;
+    match page[entity_id % PAGE_SIZE] {
+        EMPTY => None,
+        index => Some(index),
+    }
+}
+
+impl<T> ComponentStorage<T> for SparseSet<T> {
+    fn slots(&self) -> usize {
+        self.slots
+    }
+
+    fn count(&self) -> usize {
+        self.dense.len()
+    }
+
+    fn contains(&self, entity_id: usize) -> bool {
+        self.dense_index(entity_id).is_some()
+    }
+
+    fn get(&self, entity_id: usize) -> Option<&T> {
+        self.dense_index(entity_id).map(|index| &self.dense[index])
+    }
+
+    fn get_mut(&mut self, entity_id: usize) -> Option<&mut T> {
+        self.dense_index(entity_id).map(|index| &mut self.dense[index])
+    }
+
+    fn insert(&mut self, entity_id: usize, component: T) -> Option<T> {
+        if entity_id >= self.slots {
+            panic!("Entity {} is out of bounds for {} entities", entity_id, self.slots);
+        }
+
+        if let Some(index) = self.dense_index(entity_id) {
+            return Some(std::mem::replace(&mut self.dense[index], component));
+        }
+
+        self.set_dense_index(entity_id, self.dense.len());
+        self.dense.push(component);
+        self.entities.push(entity_id);
+        None
+    }
+
+    fn remove(&mut self, entity_id: usize) -> Option<T> {
+        let index = self.dense_index(entity_id)?;
+        self.set_dense_index(entity_id, EMPTY);
+
+        let component = self.dense.swap_remove(index);
+        self.entities.swap_remove(index);
+        if let Some(moved_entity) = self.entities.get(index).copied() {
+            self.set_dense_index(moved_entity, index);
+        }
+        Some(component)
+    }
+
+    fn push_slot(&mut self, component: Option<T>) {
+        self.slots += 1;
+        if let Some(component) = component {
+            self.insert(self.slots - 1, component);
+        }
+    }
+
+    fn reserve_slots(&mut self, _additional: usize) {
+        // Slots are only allocated as entities gain the component
+    }
+
+    fn remove_slot(&mut self, entity_id: usize) -> Option<T> {
+        let component = self.remove(entity_id);
+        self.slots -= 1;
+
+        if self.entities.iter().any(|v| *v > entity_id) {
+            self.sparse.clear();
+            for index in 0..self.entities.len() {
+                if self.entities[index] > entity_id {
+                    self.entities[index] -= 1;
+                }
+                self.set_dense_index(self.entities[index], index);
+            }
+        }
+
+        component
+    }
+
+    fn entity_ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.entities.iter().copied()
+    }
+}
+
+impl<T: Send> ParallelStorage<T> for SparseSet<T> {
+    type Shared<'a> = SharedSparseSet<'a, T> where T: 'a;
+
+    fn shared_mut(&mut self) -> SharedSparseSet<'_, T> {
+        SharedSparseSet {
+            sparse: &self.sparse,
+            dense: self.dense.as_mut_ptr(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Shared view of a [`SparseSet`] for parallel systems
+pub struct SharedSparseSet<'a, T> {
+    // Paged entity id -> dense index lookup
+    sparse: &'a [Option<Box<[usize; PAGE_SIZE]>>],
+    // The start of the densely packed components
+    dense: *mut T,
+    marker: PhantomData<&'a mut [T]>,
+}
+
+unsafe impl<T: Send> Send for SharedSparseSet<'_, T> {}
+unsafe impl<T: Send> Sync for SharedSparseSet<'_, T> {}
+
+unsafe impl<T: Send> SharedComponents<T> for SharedSparseSet<'_, T> {
+    unsafe fn get_mut(&self, entity_id: usize) -> Option<&mut T> {
+        Some(&mut *self.dense.add(dense_index(self.sparse, entity_id)?))
+    }
+}
@@ -6,6 +6,8 @@ use std::sync::{Arc, RwLock};
 
 pub(crate) struct SystemWrapper<WORLD> {
     pub system: fn(Arc<RwLock<WORLD>>),
+    // The index of the system in the engine's metrics
+    pub index: usize,
     pub update_rate: u128,
     pub priority: u128,
 }
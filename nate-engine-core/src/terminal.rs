@@ -0,0 +1,323 @@
+//!
+//! Terminal Renderer Drawing a Glyph Grid Singular and Forwarding Input
+//!
+
+use std::collections::VecDeque;
+use std::io::{stdout, Stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once, RwLock};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute};
+use ratatui::backend::CrosstermBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::widgets::Widget;
+use ratatui::Terminal;
+
+use crate::error::TerminalError;
+use crate::glyph::{Color, GlyphGrid};
+use crate::renderer::Renderer;
+
+/// A key pressed in the terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Esc,
+    Backspace,
+    Tab,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+impl Key {
+    /// The key of a crossterm key code (`None` for keys games don't get)
+    fn from_code(code: KeyCode) -> Option<Self> {
+        Some(match code {
+            KeyCode::Char(c) => Key::Char(c),
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Esc => Key::Esc,
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::Delete => Key::Delete,
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Left => Key::Left,
+            KeyCode::Right => Key::Right,
+            KeyCode::Home => Key::Home,
+            KeyCode::End => Key::End,
+            KeyCode::PageUp => Key::PageUp,
+            KeyCode::PageDown => Key::PageDown,
+            KeyCode::F(n) => Key::F(n),
+            _ => return None,
+        })
+    }
+}
+
+/// The input forwarded by a `TerminalRenderer`, meant to be a singular component of the world
+/// that systems drain
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Input {
+    // Keys pressed since they were last drained
+    keys: VecDeque<Key>,
+    // The size of the terminal in cells
+    size: (u16, u16),
+}
+
+impl Input {
+    /// Take the keys pressed since they were last drained, in the order they were pressed
+    pub fn drain(&mut self) -> impl Iterator<Item = Key> + '_ {
+        self.keys.drain(..)
+    }
+
+    /// Whether the key was pressed since the keys were last drained
+    pub fn pressed(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    /// The size of the terminal in cells (columns, rows)
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Queue a key press (to simulate input in tests or replays)
+    pub fn push(&mut self, key: Key) {
+        self.keys.push_back(key);
+    }
+}
+
+fn color(color: Color) -> ratatui::style::Color {
+    use ratatui::style::Color as Tui;
+    match color {
+        Color::Reset => Tui::Reset,
+        Color::Black => Tui::Black,
+        Color::Red => Tui::Red,
+        Color::Green => Tui::Green,
+        Color::Yellow => Tui::Yellow,
+        Color::Blue => Tui::Blue,
+        Color::Magenta => Tui::Magenta,
+        Color::Cyan => Tui::Cyan,
+        Color::Gray => Tui::Gray,
+        Color::DarkGray => Tui::DarkGray,
+        Color::White => Tui::White,
+        Color::Rgb(r, g, b) => Tui::Rgb(r, g, b),
+    }
+}
+
+/// Draws a glyph grid from the top left of the area, cut off at the edges of the area
+pub struct GlyphGridWidget<'a>(pub &'a GlyphGrid);
+
+impl Widget for GlyphGridWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for (y, row) in self.0.rows().take(area.height as usize).enumerate() {
+            for (x, glyph) in row.iter().take(area.width as usize).enumerate() {
+                buf.get_mut(area.x + x as u16, area.y + y as u16)
+                    .set_char(glyph.symbol)
+                    .set_fg(color(glyph.fg))
+                    .set_bg(color(glyph.bg));
+            }
+        }
+    }
+}
+
+/// Leave raw mode and the alternate screen, showing the cursor again
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), LeaveAlternateScreen, cursor::Show);
+}
+
+/// Take over the terminal, putting it in raw mode on the alternate screen with the cursor hidden
+fn take_terminal() -> std::io::Result<()> {
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, cursor::Hide).inspect_err(|_| restore_terminal())
+}
+
+/// Restores the terminal before panic messages are printed, so they aren't lost in the
+/// alternate screen
+static PANIC_HOOK: Once = Once::new();
+
+/// Set by the panic hook so renderers stop drawing over the panic message
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+/// Accesses a singular component of the world
+type Singular<WORLD, T> = Box<dyn Fn(&WORLD) -> Arc<RwLock<Option<T>>> + Send + Sync>;
+
+/// Renderer drawing a `GlyphGrid` singular of the world to the terminal each frame and
+/// forwarding key presses to an `Input` singular.
+///
+/// The terminal is put in raw mode on the alternate screen while the renderer renders and
+/// restored when the engine stops running, the renderer is dropped or the program panics (which
+/// also stops rendering). Ctrl+C and the quit key (`q` by default) end rendering, and so the
+/// engine, with `TerminalError::Quit`, which the engine treats as a normal shutdown.
+pub struct TerminalRenderer<WORLD> {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    // Whether the renderer holds the terminal (it's taken again by the next render once finished)
+    active: bool,
+    // The glyph grid drawn each frame
+    canvas: Singular<WORLD, GlyphGrid>,
+    // The input key presses are forwarded to
+    input: Option<Singular<WORLD, Input>>,
+    quit_key: Option<Key>,
+}
+
+impl<WORLD> TerminalRenderer<WORLD> {
+    /// Take over the terminal to draw the glyph grid singular the function returns, for
+    /// example `|world: &World| world.canvas.clone()`
+    pub fn new(canvas: impl Fn(&WORLD) -> Arc<RwLock<Option<GlyphGrid>>> + Send + Sync + 'static) -> std::io::Result<Self> {
+        PANIC_HOOK.call_once(|| {
+            let hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                PANICKED.store(true, Ordering::SeqCst);
+                restore_terminal();
+                hook(info);
+            }));
+        });
+        PANICKED.store(false, Ordering::SeqCst);
+
+        take_terminal()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout())).inspect_err(|_| restore_terminal())?;
+        terminal.clear()?;
+        Ok(Self {
+            terminal,
+            active: true,
+            canvas: Box::new(canvas),
+            input: None,
+            quit_key: Some(Key::Char('q')),
+        })
+    }
+
+    /// Forward key presses and the terminal's size to the input singular the function returns,
+    /// for example `|world: &World| world.input.clone()`
+    pub fn with_input(mut self, input: impl Fn(&WORLD) -> Arc<RwLock<Option<Input>>> + Send + Sync + 'static) -> Self {
+        self.input = Some(Box::new(input));
+        self
+    }
+
+    /// The key ending rendering (`None` to only quit with Ctrl+C)
+    pub fn with_quit_key(mut self, quit_key: Option<Key>) -> Self {
+        self.quit_key = quit_key;
+        self
+    }
+
+    /// Forward the pending terminal events to the world's input
+    fn forward_input(&mut self, world: &WORLD) -> Result<(), TerminalError> {
+        let io = |err: std::io::Error| TerminalError::Io(err.to_string());
+        let input = self.input.as_ref().map(|v| v(world));
+        let mut input = input.as_ref().map(|v| v.write().unwrap());
+        if let Some(input) = input.as_mut() {
+            let size = self.terminal.size().map_err(io)?;
+            input.get_or_insert_with(Input::default).size = (size.width, size.height);
+        }
+
+        while event::poll(Duration::ZERO).map_err(io)? {
+            let Event::Key(KeyEvent { code, modifiers, kind, .. }) = event::read().map_err(io)? else {
+                continue;
+            };
+            if kind == KeyEventKind::Release {
+                continue;
+            }
+            // Raw mode turns Ctrl+C into a key press instead of a signal
+            if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+                return Err(TerminalError::Quit);
+            }
+            let Some(key) = Key::from_code(code) else {
+                continue;
+            };
+            if Some(key) == self.quit_key {
+                return Err(TerminalError::Quit);
+            }
+            if let Some(input) = input.as_mut() {
+                input.get_or_insert_with(Input::default).push(key);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<WORLD: Send + Sync + 'static> Renderer<WORLD> for TerminalRenderer<WORLD> {
+    type Error = TerminalError;
+
+    fn render(&mut self, world: Arc<RwLock<WORLD>>) -> Result<(), Self::Error> {
+        let io = |err: std::io::Error| TerminalError::Io(err.to_string());
+        if PANICKED.load(Ordering::SeqCst) {
+            self.active = false;
+            return Err(TerminalError::Panicked);
+        }
+        if !self.active {
+            take_terminal().map_err(io)?;
+            self.active = true;
+            self.terminal.clear().map_err(io)?;
+        }
+
+        let world = world.read().unwrap();
+        let canvas = (self.canvas)(&world);
+        let canvas = canvas.read().unwrap();
+        self.terminal.draw(|frame| {
+            if let Some(canvas) = canvas.as_ref() {
+                frame.render_widget(GlyphGridWidget(canvas), frame.size());
+            }
+        }).map_err(io)?;
+        drop(canvas);
+
+        self.forward_input(&world)
+    }
+
+    fn is_quit(&self, err: &Self::Error) -> bool {
+        err.is_quit()
+    }
+
+    fn finish(&mut self) {
+        if self.active {
+            self.active = false;
+            restore_terminal();
+        }
+    }
+}
+
+impl<WORLD> Drop for TerminalRenderer<WORLD> {
+    fn drop(&mut self) {
+        if self.active {
+            restore_terminal();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glyph::Glyph;
+
+    #[test]
+    fn test_widget_draws_glyphs_inside_the_area() {
+        let mut grid = GlyphGrid::new(3, 2);
+        grid.set((0, 0), Glyph::new('@').with_fg(Color::Rgb(1, 2, 3)));
+        grid.set((2, 1), Glyph::new('#'));
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 2));
+        GlyphGridWidget(&grid).render(Rect::new(1, 0, 2, 2), &mut buffer);
+
+        assert_eq!(buffer.get(1, 0).symbol(), "@");
+        assert_eq!(buffer.get(1, 0).fg, ratatui::style::Color::Rgb(1, 2, 3));
+        // The last column of the grid is outside the area
+        assert_eq!(buffer.get(3, 1).symbol(), " ");
+        assert_eq!(Key::from_code(KeyCode::Char('w')), Some(Key::Char('w')));
+        assert_eq!(Key::from_code(KeyCode::CapsLock), None);
+    }
+
+    #[test]
+    fn test_only_quitting_is_a_normal_shutdown() {
+        assert!(TerminalError::Quit.is_quit());
+        assert!(!TerminalError::Panicked.is_quit());
+        assert!(!TerminalError::Io(String::from("broken pipe")).is_quit());
+    }
+}
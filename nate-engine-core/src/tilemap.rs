@@ -0,0 +1,727 @@
+//!
+//! Tile Maps with Layers, Tile Properties and Collision Flags, Loaded from Tiled
+//!
+
+use std::collections::BTreeMap;
+use std::ops::{BitOr, BitOrAssign};
+
+use crate::glyph::{Color, Glyph, GlyphGrid};
+use crate::hash::{StateHash, StateHasher};
+use crate::navigation::NavGrid;
+
+/// The value of a custom property of a map, layer, tile type or object
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Property {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Property::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The property as a float (ints are converted)
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Property::Int(value) => Some(*value as f64),
+            Property::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Property::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Custom properties by name
+pub type Properties = BTreeMap<String, Property>;
+
+/// What a tile blocks, as bits (`SOLID` is used by `Tilemap::is_solid` and `Tilemap::nav_grid`,
+/// games can give the other bits their own meanings)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionFlags(pub u32);
+
+impl CollisionFlags {
+    pub const NONE: CollisionFlags = CollisionFlags(0);
+    /// Blocks movement
+    pub const SOLID: CollisionFlags = CollisionFlags(1);
+
+    /// Whether every flag of `other` is set
+    pub fn contains(self, other: CollisionFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for CollisionFlags {
+    type Output = CollisionFlags;
+
+    fn bitor(self, rhs: CollisionFlags) -> CollisionFlags {
+        CollisionFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for CollisionFlags {
+    fn bitor_assign(&mut self, rhs: CollisionFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A type of tile (one tile of a tileset) that cells of the layers refer to by its tile id
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileInfo {
+    // The tile's class (its type in Tiled before 1.9)
+    pub class: String,
+    pub properties: Properties,
+    // Set from the `solid` (bool) and `collision` (int of flag bits) properties
+    pub collision: CollisionFlags,
+    // Drawn for the tile by `Tilemap::render`, from the `glyph`, `fg` and `bg` properties
+    pub glyph: Option<Glyph>,
+}
+
+impl TileInfo {
+    /// A tile type reading its collision flags and glyph from its properties
+    pub fn new(class: &str, properties: Properties) -> Self {
+        let mut collision = CollisionFlags(properties.get("collision").and_then(|v| v.as_int()).unwrap_or(0) as u32);
+        if properties.get("solid").and_then(|v| v.as_bool()) == Some(true) {
+            collision |= CollisionFlags::SOLID;
+        }
+        let color = |name: &str| properties.get(name).and_then(|v| v.as_str()).and_then(Color::from_hex).unwrap_or_default();
+        let glyph = properties.get("glyph")
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.chars().next())
+            .map(|v| Glyph::new(v).with_fg(color("fg")).with_bg(color("bg")));
+        Self {
+            class: class.to_string(),
+            properties,
+            collision,
+            glyph,
+        }
+    }
+}
+
+/// A grid of tile ids the size of the map (0 is an empty cell)
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileLayer {
+    pub name: String,
+    // Hidden layers aren't drawn but still collide
+    pub visible: bool,
+    pub properties: Properties,
+    // The tile id of each cell row by row
+    pub tiles: Vec<u32>,
+}
+
+/// An object placed on the map (a spawn point, trigger area, ...)
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapObject {
+    pub id: u32,
+    pub name: String,
+    // The name of the prefab the object spawns (its type in Tiled before 1.9)
+    pub class: String,
+    // Position and size in pixels
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+    pub properties: Properties,
+}
+
+/// A layer of objects
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub properties: Properties,
+    pub objects: Vec<MapObject>,
+}
+
+/// A map of tile layers and object layers, meant to be a singular component of the world
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tilemap {
+    // The size of the map in tiles
+    pub width: usize,
+    pub height: usize,
+    // The size of a tile in pixels (objects are placed in pixels)
+    pub tile_size: (u32, u32),
+    pub properties: Properties,
+    // Tile layers from the bottom up
+    pub layers: Vec<TileLayer>,
+    pub object_layers: Vec<ObjectLayer>,
+    // The tile types by tile id
+    pub tiles: BTreeMap<u32, TileInfo>,
+}
+
+impl Tilemap {
+    /// An empty map without layers
+    pub fn new(width: usize, height: usize, tile_size: (u32, u32)) -> Self {
+        Self {
+            width,
+            height,
+            tile_size,
+            ..Default::default()
+        }
+    }
+
+    /// Add an empty tile layer on top of the others, returning its index
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        self.layers.push(TileLayer {
+            name: name.to_string(),
+            visible: true,
+            properties: Properties::new(),
+            tiles: vec![0; self.width * self.height],
+        });
+        self.layers.len() - 1
+    }
+
+    /// The index of the tile layer with the name
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|v| v.name == name)
+    }
+
+    pub fn contains(&self, cell: (usize, usize)) -> bool {
+        cell.0 < self.width && cell.1 < self.height
+    }
+
+    /// The tile id at a cell of a layer (`None` for empty cells and cells outside the map)
+    pub fn tile(&self, layer: usize, cell: (usize, usize)) -> Option<u32> {
+        if !self.contains(cell) {
+            return None;
+        }
+        self.layers.get(layer)
+            .map(|v| v.tiles[cell.1 * self.width + cell.0])
+            .filter(|v| *v != 0)
+    }
+
+    /// Set the tile id at a cell of a layer (0 empties it).
+    ///
+    /// Panics if the layer or cell is outside the map.
+    pub fn set_tile(&mut self, layer: usize, cell: (usize, usize), tile: u32) {
+        assert!(self.contains(cell), "Cell {:?} is outside the {}x{} tilemap", cell, self.width, self.height);
+        self.layers[layer].tiles[cell.1 * self.width + cell.0] = tile;
+    }
+
+    /// The types of the tiles at a cell from the bottom layer up
+    pub fn tiles_at(&self, cell: (usize, usize)) -> impl Iterator<Item = &TileInfo> + '_ {
+        (0..self.layers.len()).filter_map(move |v| self.tile(v, cell)).filter_map(|v| self.tiles.get(&v))
+    }
+
+    /// A property of the topmost tile at a cell with the property
+    pub fn property(&self, cell: (usize, usize), name: &str) -> Option<&Property> {
+        self.tiles_at(cell).filter_map(|v| v.properties.get(name)).last()
+    }
+
+    /// The collision flags of every tile at a cell (cells outside the map are solid)
+    pub fn collision(&self, cell: (usize, usize)) -> CollisionFlags {
+        if !self.contains(cell) {
+            return CollisionFlags::SOLID;
+        }
+        self.tiles_at(cell).fold(CollisionFlags::NONE, |flags, v| flags | v.collision)
+    }
+
+    pub fn is_solid(&self, cell: (usize, usize)) -> bool {
+        self.collision(cell).contains(CollisionFlags::SOLID)
+    }
+
+    /// A navigation grid of the map, with solid cells blocked and the cost of each cell read
+    /// from the highest `cost` property of its tiles (1 without one)
+    pub fn nav_grid(&self) -> NavGrid {
+        let costs = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|cell| {
+                if self.is_solid(cell) {
+                    None
+                } else {
+                    let cost = self.tiles_at(cell).filter_map(|v| v.properties.get("cost")?.as_int()).max();
+                    Some(cost.map_or(1, |v| v.clamp(1, u32::MAX as i64) as u32))
+                }
+            })
+            .collect();
+        NavGrid::from_costs(self.width, costs)
+    }
+
+    /// Draw the visible layers from the bottom up with the glyphs of their tiles, with the cell
+    /// `camera` of the map at the top left of the canvas. Tiles without a glyph are skipped and
+    /// glyphs without a background keep the background of the tiles below.
+    pub fn render(&self, canvas: &mut GlyphGrid, camera: (isize, isize)) {
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                let (Ok(map_x), Ok(map_y)) = (usize::try_from(camera.0 + x as isize), usize::try_from(camera.1 + y as isize)) else {
+                    continue;
+                };
+                let mut drawn: Option<Glyph> = None;
+                for (index, layer) in self.layers.iter().enumerate() {
+                    let glyph = self.tile(index, (map_x, map_y))
+                        .filter(|_| layer.visible)
+                        .and_then(|v| self.tiles.get(&v)?.glyph);
+                    if let Some(mut glyph) = glyph {
+                        if glyph.bg == Color::Reset {
+                            glyph.bg = drawn.map_or(Color::Reset, |v| v.bg);
+                        }
+                        drawn = Some(glyph);
+                    }
+                }
+                if let Some(glyph) = drawn {
+                    canvas.set((x, y), glyph);
+                }
+            }
+        }
+    }
+}
+
+impl StateHash for Property {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        match self {
+            Property::Bool(value) => (0u8, value).state_hash(hasher),
+            Property::Int(value) => (1u8, value).state_hash(hasher),
+            Property::Float(value) => (2u8, value).state_hash(hasher),
+            Property::String(value) => (3u8, value).state_hash(hasher),
+        }
+    }
+}
+
+crate::state_hash_with_hash!(CollisionFlags);
+
+impl StateHash for TileInfo {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.class.state_hash(hasher);
+        self.properties.state_hash(hasher);
+        self.collision.state_hash(hasher);
+        self.glyph.state_hash(hasher);
+    }
+}
+
+impl StateHash for TileLayer {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.name.state_hash(hasher);
+        self.visible.state_hash(hasher);
+        self.properties.state_hash(hasher);
+        self.tiles.state_hash(hasher);
+    }
+}
+
+impl StateHash for MapObject {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.id.state_hash(hasher);
+        self.name.state_hash(hasher);
+        self.class.state_hash(hasher);
+        self.position.state_hash(hasher);
+        self.size.state_hash(hasher);
+        self.properties.state_hash(hasher);
+    }
+}
+
+impl StateHash for ObjectLayer {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.name.state_hash(hasher);
+        self.visible.state_hash(hasher);
+        self.properties.state_hash(hasher);
+        self.objects.state_hash(hasher);
+    }
+}
+
+impl StateHash for Tilemap {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.width.state_hash(hasher);
+        self.height.state_hash(hasher);
+        self.tile_size.state_hash(hasher);
+        self.properties.state_hash(hasher);
+        self.layers.state_hash(hasher);
+        self.object_layers.state_hash(hasher);
+        self.tiles.state_hash(hasher);
+    }
+}
+
+/// Loading maps from Tiled's JSON format and spawning their objects from prefabs
+#[cfg(feature = "serde")]
+mod tiled {
+    use std::path::Path;
+
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::error::{TilemapError, TilemapErrorKind};
+    use crate::prefab::{Prefab, Scene, SceneEntity};
+
+    // Tiled stores whether a tile is flipped in the top bits of its id
+    const TILE_ID_MASK: u32 = 0x0FFF_FFFF;
+
+    #[derive(Deserialize)]
+    struct TiledMap {
+        width: usize,
+        height: usize,
+        tilewidth: u32,
+        tileheight: u32,
+        #[serde(default)]
+        infinite: bool,
+        #[serde(default)]
+        properties: Vec<TiledProperty>,
+        #[serde(default)]
+        layers: Vec<TiledLayer>,
+        #[serde(default)]
+        tilesets: Vec<TiledTilesetRef>,
+    }
+
+    #[derive(Deserialize)]
+    struct TiledProperty {
+        name: String,
+        #[serde(default, rename = "type")]
+        kind: String,
+        value: Value,
+    }
+
+    #[derive(Deserialize)]
+    struct TiledLayer {
+        #[serde(rename = "type")]
+        kind: String,
+        #[serde(default)]
+        name: String,
+        #[serde(default = "visible")]
+        visible: bool,
+        #[serde(default)]
+        properties: Vec<TiledProperty>,
+        #[serde(default)]
+        data: Option<Value>,
+        #[serde(default)]
+        objects: Vec<TiledObject>,
+        // The layers of group layers
+        #[serde(default)]
+        layers: Vec<TiledLayer>,
+    }
+
+    fn visible() -> bool {
+        true
+    }
+
+    #[derive(Deserialize)]
+    struct TiledObject {
+        id: u32,
+        #[serde(default)]
+        name: String,
+        #[serde(default, rename = "type")]
+        kind: String,
+        #[serde(default)]
+        class: String,
+        x: f32,
+        y: f32,
+        #[serde(default)]
+        width: f32,
+        #[serde(default)]
+        height: f32,
+        #[serde(default)]
+        properties: Vec<TiledProperty>,
+    }
+
+    #[derive(Deserialize)]
+    struct TiledTilesetRef {
+        firstgid: u32,
+        // The file of an external tileset relative to the map
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(flatten)]
+        tileset: TiledTileset,
+    }
+
+    #[derive(Deserialize)]
+    struct TiledTileset {
+        #[serde(default)]
+        tiles: Vec<TiledTile>,
+    }
+
+    #[derive(Deserialize)]
+    struct TiledTile {
+        id: u32,
+        #[serde(default, rename = "type")]
+        kind: String,
+        #[serde(default)]
+        class: String,
+        #[serde(default)]
+        properties: Vec<TiledProperty>,
+    }
+
+    fn properties(properties: Vec<TiledProperty>) -> Properties {
+        properties.into_iter().map(|v| {
+            let property = match (v.kind.as_str(), v.value) {
+                (_, Value::Bool(value)) => Property::Bool(value),
+                ("float", Value::Number(value)) => Property::Float(value.as_f64().unwrap_or_default()),
+                (_, Value::Number(value)) => match value.as_i64() {
+                    Some(value) => Property::Int(value),
+                    None => Property::Float(value.as_f64().unwrap_or_default()),
+                },
+                (_, Value::String(value)) => Property::String(value),
+                (_, value) => Property::String(value.to_string()),
+            };
+            (v.name, property)
+        }).collect()
+    }
+
+    /// The class of a tile or object, from its type in maps saved before Tiled 1.9
+    fn class(class: String, kind: String) -> String {
+        if class.is_empty() { kind } else { class }
+    }
+
+    fn read_file(path: &Path) -> Result<String, TilemapError> {
+        std::fs::read_to_string(path).map_err(|err| TilemapError::new(TilemapErrorKind::Io(err.to_string())).with_file(path))
+    }
+
+    fn parse<'a, T: Deserialize<'a>>(json: &'a str) -> Result<T, TilemapError> {
+        serde_json::from_str(json).map_err(|err| TilemapError::new(TilemapErrorKind::Parse(err.to_string())))
+    }
+
+    impl Tilemap {
+        /// Load a map saved by Tiled as JSON, with its external tilesets (relative to the map).
+        ///
+        /// Tile layers must be saved in the CSV format, group layers are flattened into their
+        /// layers and the properties of tiles set their collision flags and glyphs (see
+        /// `TileInfo::new`).
+        pub fn load_tiled(path: impl AsRef<Path>) -> Result<Self, TilemapError> {
+            let path = path.as_ref();
+            Self::from_tiled(&read_file(path)?, path.parent())
+                .map_err(|err| if err.file.is_none() { err.with_file(path) } else { err })
+        }
+
+        /// Read a map in Tiled's JSON format that only uses embedded tilesets
+        pub fn from_tiled_json(json: &str) -> Result<Self, TilemapError> {
+            Self::from_tiled(json, None)
+        }
+
+        fn from_tiled(json: &str, directory: Option<&Path>) -> Result<Self, TilemapError> {
+            let map: TiledMap = parse(json)?;
+            if map.infinite {
+                return Err(TilemapError::new(TilemapErrorKind::InfiniteMap));
+            }
+
+            let mut tilemap = Tilemap::new(map.width, map.height, (map.tilewidth, map.tileheight));
+            tilemap.properties = properties(map.properties);
+            for tileset in map.tilesets {
+                let tiles = match (tileset.source, directory) {
+                    (None, _) => tileset.tileset.tiles,
+                    (Some(source), Some(directory)) => {
+                        let path = directory.join(source);
+                        parse::<TiledTileset>(&read_file(&path)?).map_err(|err| err.with_file(path))?.tiles
+                    },
+                    (Some(source), None) => return Err(TilemapError::new(TilemapErrorKind::ExternalTileset(source))),
+                };
+                for tile in tiles {
+                    let id = tileset.firstgid.checked_add(tile.id)
+                        .ok_or_else(|| TilemapError::new(TilemapErrorKind::InvalidTileId { firstgid: tileset.firstgid, id: tile.id }))?;
+                    tilemap.tiles.insert(id, TileInfo::new(&class(tile.class, tile.kind), properties(tile.properties)));
+                }
+            }
+            tilemap.add_layers(map.layers, true)?;
+            Ok(tilemap)
+        }
+
+        fn add_layers(&mut self, layers: Vec<TiledLayer>, visible: bool) -> Result<(), TilemapError> {
+            for layer in layers {
+                let visible = visible && layer.visible;
+                match layer.kind.as_str() {
+                    "tilelayer" => {
+                        let tiles: Vec<u32> = match layer.data {
+                            Some(Value::Array(tiles)) => tiles.iter().map(|v| v.as_u64().unwrap_or(0) as u32 & TILE_ID_MASK).collect(),
+                            _ => return Err(TilemapError::new(TilemapErrorKind::EncodedLayer(layer.name))),
+                        };
+                        if tiles.len() != self.width * self.height {
+                            return Err(TilemapError::new(TilemapErrorKind::WrongLayerSize(layer.name)));
+                        }
+                        self.layers.push(TileLayer {
+                            name: layer.name,
+                            visible,
+                            properties: properties(layer.properties),
+                            tiles,
+                        });
+                    },
+                    "objectgroup" => self.object_layers.push(ObjectLayer {
+                        name: layer.name,
+                        visible,
+                        properties: properties(layer.properties),
+                        objects: layer.objects.into_iter().map(|v| MapObject {
+                            id: v.id,
+                            name: v.name,
+                            class: class(v.class, v.kind),
+                            position: (v.x, v.y),
+                            size: (v.width, v.height),
+                            properties: properties(v.properties),
+                        }).collect(),
+                    }),
+                    "group" => self.add_layers(layer.layers, visible)?,
+                    // Image layers have nothing to load
+                    _ => (),
+                }
+            }
+            Ok(())
+        }
+
+        /// A scene of the prefabs named by the classes of the map's objects (objects without a
+        /// class are skipped), for `spawn_scene` to spawn.
+        ///
+        /// The objects' properties override the prefab's components, with string properties
+        /// holding a JSON array or object passed as JSON so tuple and struct components can be
+        /// set. With a `position` component name the objects' cells are set as `[x, y]`.
+        pub fn object_scene(&self, prefabs: impl IntoIterator<Item = Prefab>, position: Option<&str>) -> Scene {
+            let mut scene = Scene::default();
+            for prefab in prefabs {
+                scene = scene.with_prefab(prefab);
+            }
+            for layer in self.object_layers.iter() {
+                for object in layer.objects.iter().filter(|v| !v.class.is_empty()) {
+                    let mut overrides: BTreeMap<String, Value> = object.properties.iter()
+                        .map(|(name, property)| (name.clone(), property_value(property)))
+                        .collect();
+                    if let Some(position) = position {
+                        let cell = |pixels: f32, size: u32| (pixels / size.max(1) as f32).floor().max(0.0) as usize;
+                        let cell = (cell(object.position.0, self.tile_size.0), cell(object.position.1, self.tile_size.1));
+                        overrides.insert(position.to_string(), serde_json::json!([cell.0, cell.1]));
+                    }
+                    let name = if object.name.is_empty() { format!("{}#{}", layer.name, object.id) } else { object.name.clone() };
+                    scene = scene.with_entity(SceneEntity {
+                        prefab: object.class.clone(),
+                        name: Some(name),
+                        overrides,
+                    });
+                }
+            }
+            scene
+        }
+    }
+
+    fn property_value(property: &Property) -> Value {
+        match property {
+            Property::Bool(value) => Value::Bool(*value),
+            Property::Int(value) => Value::from(*value),
+            Property::Float(value) => Value::from(*value),
+            Property::String(value) if value.starts_with('[') || value.starts_with('{') => {
+                serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.clone()))
+            },
+            Property::String(value) => Value::String(value.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tilemap() -> Tilemap {
+        let mut tilemap = Tilemap::new(3, 2, (8, 8));
+        let mut wall = Properties::new();
+        wall.insert(String::from("solid"), Property::Bool(true));
+        wall.insert(String::from("glyph"), Property::String(String::from("#")));
+        let mut grass = Properties::new();
+        grass.insert(String::from("glyph"), Property::String(String::from(".")));
+        grass.insert(String::from("bg"), Property::String(String::from("#00ff00")));
+        let mut mud = Properties::new();
+        mud.insert(String::from("cost"), Property::Int(3));
+        mud.insert(String::from("collision"), Property::Int(4));
+        tilemap.tiles.insert(1, TileInfo::new("wall", wall));
+        tilemap.tiles.insert(2, TileInfo::new("grass", grass));
+        tilemap.tiles.insert(3, TileInfo::new("mud", mud));
+
+        let ground = tilemap.add_layer("ground");
+        let walls = tilemap.add_layer("walls");
+        for x in 0..3 {
+            tilemap.set_tile(ground, (x, 0), 2);
+        }
+        tilemap.set_tile(ground, (0, 1), 3);
+        tilemap.set_tile(walls, (1, 0), 1);
+        tilemap
+    }
+
+    #[test]
+    fn test_collision_and_navigation() {
+        let tilemap = tilemap();
+        assert!(tilemap.is_solid((1, 0)));
+        assert!(tilemap.is_solid((3, 0)));
+        assert_eq!(tilemap.collision((0, 1)), CollisionFlags(4));
+        assert_eq!(tilemap.property((0, 1), "cost"), Some(&Property::Int(3)));
+
+        let nav = tilemap.nav_grid();
+        assert_eq!(nav.cost((1, 0)), None);
+        assert_eq!(nav.cost((0, 1)), Some(3));
+        assert_eq!(nav.cost((2, 1)), Some(1));
+    }
+
+    #[test]
+    fn test_render_layers_onto_a_canvas() {
+        let tilemap = tilemap();
+        let mut canvas = GlyphGrid::new(3, 2);
+        tilemap.render(&mut canvas, (-1, 0));
+
+        let rows: Vec<String> = canvas.rows().map(|v| v.iter().map(|v| v.symbol).collect()).collect();
+        assert_eq!(rows, vec![" .#", "   "]);
+        // The wall keeps the grass's background
+        assert_eq!(canvas.get((2, 0)).unwrap().bg, Color::Rgb(0, 255, 0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_load_tiled_json_and_spawn_objects() {
+        use crate::error::TilemapErrorKind;
+        use crate::prefab::Prefab;
+
+        let json = r##"{
+            "width": 2, "height": 2, "tilewidth": 16, "tileheight": 16, "infinite": false,
+            "properties": [{ "name": "music", "type": "string", "value": "cave" }],
+            "tilesets": [{ "firstgid": 1, "name": "tiles", "tiles": [
+                { "id": 0, "type": "wall", "properties": [{ "name": "solid", "type": "bool", "value": true }] }
+            ] }],
+            "layers": [
+                { "type": "group", "name": "level", "visible": true, "layers": [
+                    { "type": "tilelayer", "name": "walls", "visible": false, "data": [0, 2147483649, 0, 0] }
+                ] },
+                { "type": "objectgroup", "name": "spawns", "objects": [
+                    { "id": 1, "name": "boss", "class": "goblin", "x": 20, "y": 17, "properties": [
+                        { "name": "health", "type": "int", "value": 50 },
+                        { "name": "velocity", "type": "string", "value": "[1, 0]" }
+                    ] },
+                    { "id": 2, "name": "note", "x": 0, "y": 0 }
+                ] }
+            ]
+        }"##;
+        let tilemap = Tilemap::from_tiled_json(json).unwrap();
+        assert_eq!(tilemap.properties.get("music"), Some(&Property::String(String::from("cave"))));
+        // The flip flag is stripped and hidden layers still collide
+        assert_eq!(tilemap.tile(0, (1, 0)), Some(1));
+        assert!(!tilemap.layers[0].visible);
+        assert!(tilemap.is_solid((1, 0)));
+
+        let scene = tilemap.object_scene([Prefab::new("goblin").with("health", serde_json::json!(10))], Some("position"));
+        assert_eq!(scene.entities.len(), 1);
+        let components = scene.components(0).unwrap();
+        let component = |name: &str| components.iter().find(|v| v.name == name).unwrap().value.clone();
+        assert_eq!(component("health"), serde_json::json!(50));
+        assert_eq!(component("velocity"), serde_json::json!([1, 0]));
+        assert_eq!(component("position"), serde_json::json!([1, 1]));
+
+        let encoded = json.replace("[0, 2147483649, 0, 0]", "\"AAAA\"");
+        assert_eq!(Tilemap::from_tiled_json(&encoded).unwrap_err().kind, TilemapErrorKind::EncodedLayer(String::from("walls")));
+        let overflowing = json.replace(r#""firstgid": 1"#, r#""firstgid": 4294967295"#).replace(r#""id": 0, "type""#, r#""id": 1, "type""#);
+        assert_eq!(Tilemap::from_tiled_json(&overflowing).unwrap_err().kind, TilemapErrorKind::InvalidTileId { firstgid: u32::MAX, id: 1 });
+    }
+}
@@ -0,0 +1,333 @@
+//!
+//! Local and Global Transforms of Entities and their Propagation Down the Hierarchy
+//!
+
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+
+use crate::hash::{StateHash, StateHasher};
+use crate::hierarchy::Hierarchy;
+use crate::storage::ComponentStorage;
+
+/// A position, rotation and scale that can be combined with the transform of a parent
+pub trait Transform: Copy + PartialEq {
+    /// The transform that changes nothing
+    const IDENTITY: Self;
+
+    /// The world-space transform of a child with this world-space transform as its parent and
+    /// `local` relative to it
+    fn then(&self, local: &Self) -> Self;
+}
+
+/// A transform in continuous 2D space
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform2D {
+    pub position: (f32, f32),
+    // Counterclockwise rotation (in radians)
+    pub rotation: f32,
+    pub scale: (f32, f32),
+}
+
+impl Transform2D {
+    pub fn from_position(position: (f32, f32)) -> Self {
+        Self {
+            position,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Scale, rotate then move a point by the transform
+    pub fn transform_point(&self, point: (f32, f32)) -> (f32, f32) {
+        let (x, y) = (point.0 * self.scale.0, point.1 * self.scale.1);
+        let (sin, cos) = self.rotation.sin_cos();
+        (self.position.0 + x * cos - y * sin, self.position.1 + x * sin + y * cos)
+    }
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform for Transform2D {
+    const IDENTITY: Self = Self {
+        position: (0.0, 0.0),
+        rotation: 0.0,
+        scale: (1.0, 1.0),
+    };
+
+    /// Scales are multiplied per axis, so a rotated child of a parent with a non-uniform scale
+    /// isn't skewed
+    fn then(&self, local: &Self) -> Self {
+        Self {
+            position: self.transform_point(local.position),
+            rotation: self.rotation + local.rotation,
+            scale: (self.scale.0 * local.scale.0, self.scale.1 * local.scale.1),
+        }
+    }
+}
+
+impl StateHash for Transform2D {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.position.state_hash(hasher);
+        self.rotation.state_hash(hasher);
+        self.scale.state_hash(hasher);
+    }
+}
+
+/// A transform on an integer grid, rotated by quarter turns
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridTransform {
+    pub position: (isize, isize),
+    // Quarter turns (0 to 3), one quarter turn moves (1, 0) to (0, 1)
+    pub rotation: u8,
+    pub scale: (isize, isize),
+}
+
+impl GridTransform {
+    pub fn from_position(position: (isize, isize)) -> Self {
+        Self {
+            position,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Scale, rotate then move a cell by the transform
+    pub fn transform_point(&self, point: (isize, isize)) -> (isize, isize) {
+        let (x, y) = (point.0 * self.scale.0, point.1 * self.scale.1);
+        let (x, y) = match self.rotation % 4 {
+            0 => (x, y),
+            1 => (-y, x),
+            2 => (-x, -y),
+            _ => (y, -x),
+        };
+        (self.position.0 + x, self.position.1 + y)
+    }
+}
+
+impl Default for GridTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform for GridTransform {
+    const IDENTITY: Self = Self {
+        position: (0, 0),
+        rotation: 0,
+        scale: (1, 1),
+    };
+
+    fn then(&self, local: &Self) -> Self {
+        Self {
+            position: self.transform_point(local.position),
+            rotation: (self.rotation % 4 + local.rotation % 4) % 4,
+            scale: (self.scale.0 * local.scale.0, self.scale.1 * local.scale.1),
+        }
+    }
+}
+
+crate::state_hash_with_hash!(GridTransform);
+
+/// The transform of an entity relative to its parent (or the world if it has no parent).
+///
+/// Changing the transform (through `DerefMut`) marks it so the next propagation recomputes the
+/// global transforms of the entity and its descendants.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct LocalTransform<T> {
+    value: T,
+    // Whether the transform changed since it was last propagated
+    #[cfg_attr(feature = "serde", serde(skip, default = "changed"))]
+    changed: bool,
+}
+
+#[cfg(feature = "serde")]
+fn changed() -> bool {
+    true
+}
+
+impl<T> LocalTransform<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            changed: true,
+        }
+    }
+
+    /// Whether the transform changed since it was last propagated
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Recompute the global transforms of the entity and its descendants in the next
+    /// propagation even though the transform hasn't changed
+    pub fn mark_changed(&mut self) {
+        self.changed = true;
+    }
+}
+
+impl<T: Transform> Default for LocalTransform<T> {
+    fn default() -> Self {
+        Self::new(T::IDENTITY)
+    }
+}
+
+impl<T> Deref for LocalTransform<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for LocalTransform<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.changed = true;
+        &mut self.value
+    }
+}
+
+impl<T: PartialEq> PartialEq for LocalTransform<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Debug> Debug for LocalTransform<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LocalTransform").field(&self.value).finish()
+    }
+}
+
+impl<T: StateHash> StateHash for LocalTransform<T> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.value.state_hash(hasher);
+    }
+}
+
+/// The world-space transform of an entity, computed from its local transform and the global
+/// transform of its parent by `propagate`
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct GlobalTransform<T>(pub T);
+
+impl<T: Transform> Default for GlobalTransform<T> {
+    fn default() -> Self {
+        Self(T::IDENTITY)
+    }
+}
+
+impl<T> Deref for GlobalTransform<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: StateHash> StateHash for GlobalTransform<T> {
+    fn state_hash(&self, hasher: &mut StateHasher) {
+        self.0.state_hash(hasher);
+    }
+}
+
+/// Compute the global transforms of every entity with a local and a global transform from the
+/// roots of the hierarchy down, returning the number of global transforms recomputed.
+///
+/// Only entities whose local transform changed, that were reparented or whose parent's global
+/// transform was recomputed are recomputed, so static subtrees keep their global transforms.
+/// Entities without a local transform pass their parent's global transform on to their children.
+pub fn propagate<T, L, G>(hierarchy: &mut Hierarchy, local: &mut L, global: &mut G) -> usize
+where
+    T: Transform,
+    L: ComponentStorage<LocalTransform<T>>,
+    G: ComponentStorage<GlobalTransform<T>>,
+{
+    let mut recomputed = 0;
+    // (Entity id, global transform of its parent, whether that transform was recomputed)
+    let mut stack: Vec<(usize, T, bool)> = hierarchy.roots().map(|v| (v, T::IDENTITY, false)).collect();
+    stack.reverse();
+    while let Some((entity_id, parent, parent_changed)) = stack.pop() {
+        let changed = parent_changed || hierarchy.reparented(entity_id);
+        let (transform, changed) = match (local.get_mut(entity_id), global.get_mut(entity_id)) {
+            (Some(local), Some(global)) if changed || local.changed => {
+                local.changed = false;
+                global.0 = parent.then(&local.value);
+                recomputed += 1;
+                (global.0, true)
+            },
+            (Some(_), Some(global)) => (global.0, false),
+            _ => (parent, changed),
+        };
+        stack.extend(hierarchy.children(entity_id).iter().rev().map(|v| (*v, transform, changed)));
+    }
+    hierarchy.clear_reparented();
+    recomputed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transform_2d_then() {
+        let parent = Transform2D {
+            position: (10.0, 0.0),
+            rotation: std::f32::consts::FRAC_PI_2,
+            scale: (2.0, 2.0),
+        };
+        let global = parent.then(&Transform2D::from_position((1.0, 0.0)));
+        assert!((global.position.0 - 10.0).abs() < 1e-5 && (global.position.1 - 2.0).abs() < 1e-5);
+        assert_eq!(global.scale, (2.0, 2.0));
+        assert_eq!(Transform2D::IDENTITY.then(&parent), parent);
+    }
+
+    #[test]
+    fn test_grid_transform_then() {
+        let parent = GridTransform {
+            position: (5, 5),
+            rotation: 3,
+            scale: (1, 1),
+        };
+        let local = GridTransform {
+            position: (2, 1),
+            rotation: 2,
+            scale: (3, 1),
+        };
+        assert_eq!(parent.then(&local), GridTransform {
+            position: (6, 3),
+            rotation: 1,
+            scale: (3, 1),
+        });
+    }
+
+    #[test]
+    fn test_propagate_skips_static_subtrees() {
+        let mut hierarchy = Hierarchy::with_slots(4);
+        hierarchy.set_parent(1, 0).unwrap();
+        hierarchy.set_parent(2, 1).unwrap();
+        let mut local: Vec<Option<LocalTransform<GridTransform>>> = (0..4).map(|v| Some(LocalTransform::new(GridTransform::from_position((v, 0))))).collect();
+        let mut global: Vec<Option<GlobalTransform<GridTransform>>> = vec![Some(GlobalTransform::default()); 4];
+        // Entity 1 has no local transform, so entity 2 is placed relative to entity 0
+        local[1] = None;
+
+        assert_eq!(propagate(&mut hierarchy, &mut local, &mut global), 3);
+        assert_eq!(global[2].unwrap().position, (2, 0));
+        assert_eq!(propagate(&mut hierarchy, &mut local, &mut global), 0);
+
+        local[0].as_mut().unwrap().position = (10, 10);
+        assert_eq!(propagate(&mut hierarchy, &mut local, &mut global), 2);
+        assert_eq!(global[2].unwrap().position, (12, 10));
+        assert_eq!(global[3].unwrap().position, (3, 0));
+
+        hierarchy.set_parent(3, 2).unwrap();
+        assert_eq!(propagate(&mut hierarchy, &mut local, &mut global), 1);
+        assert_eq!(global[3].unwrap().position, (15, 10));
+    }
+}
@@ -0,0 +1,182 @@
+//!
+//! Benchmarks of Dense (Presence Bit Set) Columns Against `Vec<Option<T>>` Columns, Both for
+//! Memory Used and for Iteration by Generated Systems and Plain Slices
+//!
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use nate_engine_core::storage::{ComponentStorage, DenseColumn};
+use nate_engine_macros::{system, world};
+
+const ENTITIES: usize = 100_000;
+
+/// Allocator that keeps track of the bytes currently allocated, so the memory used by each
+/// layout can be measured
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[world]
+pub struct VecWorld {
+    position: (isize, isize),
+    velocity: (isize, isize),
+    health: usize,
+}
+
+#[world]
+pub struct DenseWorld {
+    #[storage(dense)]
+    position: (isize, isize),
+    #[storage(dense)]
+    velocity: (isize, isize),
+    #[storage(dense)]
+    health: usize,
+}
+
+#[system(world=VecWorld, read=[velocity], write=[position])]
+fn vec_position_update() {
+    *position = (position.0 + velocity.0, position.1 + velocity.1);
+}
+
+#[system(world=DenseWorld, read=[velocity], write=[position])]
+fn dense_position_update() {
+    *position = (position.0 + velocity.0, position.1 + velocity.1);
+}
+
+#[system(world=VecWorld, write=[health])]
+fn vec_health_update() {
+    *health += 1;
+}
+
+#[system(world=DenseWorld, write=[health])]
+fn dense_health_update() {
+    *health += 1;
+}
+
+/// The same update as `dense_health_update` over the column's runs by hand, which the generated
+/// system should keep up with now that it walks the same runs as slices
+fn dense_health_slices(world: Arc<RwLock<DenseWorld>>) {
+    let world = world.read().unwrap();
+    let mut health = world.health.write().unwrap();
+    for (_start, run) in health.runs_mut() {
+        for health in run.iter_mut() {
+            *health += 1;
+        }
+    }
+}
+
+/// Every entity has a position and every `stride`th entity has a velocity and health
+fn vec_world_with_stride(stride: usize) -> Arc<RwLock<VecWorld>> {
+    let world = VecWorld::new();
+    world.write().unwrap().spawn_batch((0..ENTITIES).map(|v| {
+        let bundle = VecWorldBundle::default().with_position((0, 0));
+        if v % stride == 0 {
+            bundle.with_velocity((1, 1)).with_health(1)
+        } else {
+            bundle
+        }
+    }));
+    world
+}
+
+/// Every entity has a position and every `stride`th entity has a velocity and health
+fn dense_world_with_stride(stride: usize) -> Arc<RwLock<DenseWorld>> {
+    let world = DenseWorld::new();
+    world.write().unwrap().spawn_batch((0..ENTITIES).map(|v| {
+        let bundle = DenseWorldBundle::default().with_position((0, 0));
+        if v % stride == 0 {
+            bundle.with_velocity((1, 1)).with_health(1)
+        } else {
+            bundle
+        }
+    }));
+    world
+}
+
+/// The bytes allocated while building a column of `(isize, isize)` where every `stride`th
+/// entity has the component
+fn column_bytes<S: ComponentStorage<(isize, isize)> + Default>(stride: usize) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let mut column = S::default();
+    column.reserve_slots(ENTITIES);
+    for entity_id in 0..ENTITIES {
+        column.push_slot((entity_id % stride == 0).then_some((1, 1)));
+    }
+    let bytes = ALLOCATED.load(Ordering::Relaxed) - before;
+    drop(column);
+    bytes
+}
+
+fn bench_memory(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory_100k");
+    for stride in [1, 2, 100] {
+        println!(
+            "memory_100k/{}: Vec<Option<T>> {} bytes, DenseColumn<T> {} bytes",
+            stride,
+            column_bytes::<Vec<Option<(isize, isize)>>>(stride),
+            column_bytes::<DenseColumn<(isize, isize)>>(stride),
+        );
+
+        group.bench_with_input(BenchmarkId::new("vec_option", stride), &stride, |b, stride| {
+            b.iter(|| column_bytes::<Vec<Option<(isize, isize)>>>(*stride))
+        });
+        group.bench_with_input(BenchmarkId::new("dense", stride), &stride, |b, stride| {
+            b.iter(|| column_bytes::<DenseColumn<(isize, isize)>>(*stride))
+        });
+    }
+    group.finish();
+}
+
+fn bench_two_components(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dense_two_components_100k");
+    for stride in [1, 2, 100] {
+        let vec_world = vec_world_with_stride(stride);
+        let dense_world = dense_world_with_stride(stride);
+        group.bench_with_input(BenchmarkId::new("vec_option", stride), &vec_world, |b, world| {
+            b.iter(|| vec_position_update(world.clone()))
+        });
+        group.bench_with_input(BenchmarkId::new("dense", stride), &dense_world, |b, world| {
+            b.iter(|| dense_position_update(world.clone()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_single_component(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dense_single_component_100k");
+    for stride in [1, 2, 100] {
+        let vec_world = vec_world_with_stride(stride);
+        let dense_world = dense_world_with_stride(stride);
+        group.bench_with_input(BenchmarkId::new("vec_option", stride), &vec_world, |b, world| {
+            b.iter(|| vec_health_update(world.clone()))
+        });
+        group.bench_with_input(BenchmarkId::new("dense", stride), &dense_world, |b, world| {
+            b.iter(|| dense_health_update(world.clone()))
+        });
+        group.bench_with_input(BenchmarkId::new("dense_slices", stride), &dense_world, |b, world| {
+            b.iter(|| dense_health_slices(world.clone()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_memory, bench_two_components, bench_single_component);
+criterion_main!(benches);
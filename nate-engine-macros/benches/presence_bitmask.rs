@@ -0,0 +1,99 @@
+//!
+//! Benchmarks of the Signature (Presence Bitmask) Iteration of Generated Systems
+//! Against the Previous Zip / Filter Iteration Over Every Slot of Every Column
+//!
+
+use std::sync::{Arc, RwLock};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use nate_engine_macros::{system, world};
+
+const ENTITIES: usize = 100_000;
+
+#[world]
+pub struct BenchWorld {
+    position: (isize, isize),
+    velocity: (isize, isize),
+    health: usize,
+}
+
+#[system(world=BenchWorld, read=[velocity], write=[position])]
+fn signature_position_update() {
+    *position = (position.0 + velocity.0, position.1 + velocity.1);
+}
+
+#[system(world=BenchWorld, read=[velocity, health], write=[position])]
+fn signature_three_component_update() {
+    *position = (position.0 + velocity.0, position.1 + velocity.1 + *health as isize);
+}
+
+/// The iteration the system macro generated before signatures were kept
+fn zip_filter_position_update(world: Arc<RwLock<BenchWorld>>) {
+    let world = world.read().unwrap();
+    let velocity = world.velocity.read().unwrap();
+    let mut position = world.position.write().unwrap();
+    for ((_entity_id, velocity), position) in velocity.iter().enumerate().zip(position.iter_mut()).filter(|v| v.0.1.is_some() && v.1.is_some()) {
+        let velocity = velocity.as_ref().unwrap();
+        let position = position.as_mut().unwrap();
+        *position = (position.0 + velocity.0, position.1 + velocity.1);
+    }
+}
+
+/// The iteration the system macro generated before signatures were kept
+fn zip_filter_three_component_update(world: Arc<RwLock<BenchWorld>>) {
+    let world = world.read().unwrap();
+    let velocity = world.velocity.read().unwrap();
+    let health = world.health.read().unwrap();
+    let mut position = world.position.write().unwrap();
+    for (((_entity_id, velocity), health), position) in velocity.iter().enumerate().zip(health.iter()).zip(position.iter_mut()).filter(|v| v.0.0.1.is_some() && v.0.1.is_some() && v.1.is_some()) {
+        let velocity = velocity.as_ref().unwrap();
+        let health = health.as_ref().unwrap();
+        let position = position.as_mut().unwrap();
+        *position = (position.0 + velocity.0, position.1 + velocity.1 + *health as isize);
+    }
+}
+
+/// World where every entity has a position and every `stride`th entity has a velocity and health
+fn world_with_stride(stride: usize) -> Arc<RwLock<BenchWorld>> {
+    let world = BenchWorld::new();
+    world.write().unwrap().spawn_batch((0..ENTITIES).map(|v| {
+        let bundle = BenchWorldBundle::default().with_position((0, 0));
+        if v % stride == 0 {
+            bundle.with_velocity((1, 1)).with_health(1)
+        } else {
+            bundle
+        }
+    }));
+    world
+}
+
+fn bench_two_components(c: &mut Criterion) {
+    let mut group = c.benchmark_group("two_components_100k");
+    for stride in [1, 2, 10, 100, 1_000] {
+        let world = world_with_stride(stride);
+        group.bench_with_input(BenchmarkId::new("signatures", stride), &world, |b, world| {
+            b.iter(|| signature_position_update(world.clone()))
+        });
+        group.bench_with_input(BenchmarkId::new("zip_filter", stride), &world, |b, world| {
+            b.iter(|| zip_filter_position_update(world.clone()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_three_components(c: &mut Criterion) {
+    let mut group = c.benchmark_group("three_components_100k");
+    for stride in [1, 10, 1_000] {
+        let world = world_with_stride(stride);
+        group.bench_with_input(BenchmarkId::new("signatures", stride), &world, |b, world| {
+            b.iter(|| signature_three_component_update(world.clone()))
+        });
+        group.bench_with_input(BenchmarkId::new("zip_filter", stride), &world, |b, world| {
+            b.iter(|| zip_filter_three_component_update(world.clone()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_two_components, bench_three_components);
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+//!
+//! Archetype World to Demonstrate Grouping Entities with the Same Components into Tables
+//!
+
+use nate_engine_macros::{system, world};
+
+use rand::random;
+
+#[world(storage = archetype, tags=[frozen])]
+pub struct ArchetypeWorld {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    health: u32,
+    // Explicit storage still overrides the world's layout
+    #[storage(sparse)]
+    name: String,
+}
+
+// The same system as in a `Vec<Option<T>>` world, but entities are visited archetype by
+// archetype so each table is read front to back
+#[system(world=ArchetypeWorld, read=[velocity], write=[position], without=[frozen])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+#[system(world=ArchetypeWorld, read=[position, name])]
+fn log_named() {
+    println!("{} ({}) is at {:?}", name, entity_id, position);
+}
+
+fn main() {
+    let world = ArchetypeWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn_batch((0..10_000).map(|i| {
+            let bundle = ArchetypeWorldBundle::default()
+                .with_position((random::<f32>() * 100.0, random::<f32>() * 100.0))
+                .with_health(100);
+            if i % 2 == 0 {
+                bundle.with_velocity((1.0, 0.0))
+            } else {
+                bundle
+            }
+        }));
+
+        // Each of these moves the entity's components to the tables of its new archetype
+        world.set_velocity(1, (0.0, 1.0));
+        world.clear_health(2);
+        world.set_frozen(4);
+        world.set_name(4, String::from("Statue"));
+        world.remove_entity(0);
+
+        let signatures = world.signatures.read().unwrap();
+        println!("{} archetypes", signatures.archetypes().unwrap().len());
+    }
+
+    movement(world.clone());
+    log_named(world.clone());
+}
@@ -49,25 +49,14 @@ fn main() {
     let world = BigWorld::new();
     {
         let mut world = world.write().unwrap();
-        let entity_ids = world.add_entities(100_000);
-
-        let positions = entity_ids.iter().map(|_v| (0.0 ,0.0)).collect();
-        world.set_positions(&entity_ids, positions);
-
-        let velocities = entity_ids.iter().map(|_v| (0.0, 0.0)).collect();
-        world.set_velocitys(&entity_ids, velocities);
-
-        let accelerations = entity_ids.iter().map(|_v| (0.0, 0.0)).collect();
-        world.set_accelerations(&entity_ids, accelerations);
-
-        let jerks = entity_ids.iter().map(|_v| (random::<f32>(), random::<f32>())).collect();
-        world.set_jerks(&entity_ids, jerks);
-
-        let healths = entity_ids.iter().map(|_v| random::<u32>() % 20).collect();
-        world.set_healths(&entity_ids, healths);
-
-        let alives = entity_ids.iter().map(|_v| true).collect();
-        world.set_alives(&entity_ids, alives);
+        world.spawn_batch((0..100_000).map(|_v| BigWorldBundle {
+            position: Some((0.0, 0.0)),
+            velocity: Some((0.0, 0.0)),
+            acceleration: Some((0.0, 0.0)),
+            jerk: Some((random::<f32>(), random::<f32>())),
+            health: Some(random::<u32>() % 20),
+            alive: Some(true),
+        }));
     }
 
     log_world(world.clone());
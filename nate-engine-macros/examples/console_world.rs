@@ -0,0 +1,66 @@
+//!
+//! Console World to Demonstrate Running Developer Console Commands Against a World
+//!
+
+use nate_engine_core::console::{Console, ConsoleOutput};
+use nate_engine_core::error::ConsoleError;
+use nate_engine_core::metrics::EngineMetrics;
+use nate_engine_macros::world;
+
+#[world(singular=[wave], tags=[enemy, tower, dead])]
+pub struct ConsoleWorld {
+    position: (isize, isize),
+    health: u32,
+
+    wave: u32,
+}
+
+/// `spawn enemy at 3 4`, which replaces the built-in `spawn` since positions can't be parsed
+/// from a single string
+fn spawn(world: &mut ConsoleWorld, args: &[&str], output: &mut ConsoleOutput) -> Result<(), ConsoleError> {
+    let usage = || ConsoleError::Usage(String::from("spawn enemy|tower at X Y"));
+    let (kind, x, y) = match args {
+        [kind, "at", x, y] => (*kind, x.parse().map_err(|_| usage())?, y.parse().map_err(|_| usage())?),
+        _ => return Err(usage()),
+    };
+
+    let builder = world.spawn().with_position((x, y));
+    let entity_id = match kind {
+        "enemy" => builder.with_health(10).with_enemy().build(),
+        "tower" => builder.with_tower().build(),
+        _ => return Err(usage()),
+    };
+    output.push(format!("spawned {} {} at ({}, {})", kind, entity_id, x, y));
+    Ok(())
+}
+
+fn main() {
+    let console = Console::new().with_command("spawn", "spawn enemy|tower at X Y", "Spawn an enemy or a tower", spawn);
+    let world = ConsoleWorld::new();
+    let metrics = EngineMetrics::new(["movement", "towers"]);
+
+    // Commands typed into the console are queued and run by the engine between ticks
+    for line in [
+        "spawn enemy at 3 4",
+        "spawn enemy at 5 -2",
+        "spawn tower at 0 0",
+        "set 1 dead true",
+        "set 0 health 4",
+        "set wave 2",
+        "list with health without dead",
+        "get 0",
+        "get wave",
+        "pause",
+        "step 5",
+        "disable towers",
+        "spawn dragon at 1 1",
+        "help",
+    ] {
+        console.submit(line);
+    }
+    console.run_pending(&world, &metrics);
+
+    for line in console.output().lines() {
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,49 @@
+//!
+//! Debug World to Demonstrate Printing Entities and Worlds
+//!
+
+use nate_engine_macros::world;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Inventory {
+    items: Vec<String>,
+}
+
+// Doesn't implement `Debug`, so it's printed as its type
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brain {
+    state: u8,
+}
+
+#[world(singular=[tick, seed], tags=[enemy, dead])]
+pub struct DebugWorld {
+    position: (isize, isize),
+    health: u32,
+    inventory: Inventory,
+    brain: Brain,
+
+    tick: u64,
+    seed: u64,
+}
+
+fn main() {
+    let world = DebugWorld::new();
+    let mut world = world.write().unwrap();
+    let goblin = world.spawn()
+        .with_position((3, 4))
+        .with_health(5)
+        .with_inventory(Inventory { items: vec![String::from("dagger")] })
+        .with_brain(Brain { state: 0 })
+        .with_enemy()
+        .build();
+    world.spawn().with_position((0, 0)).with_health(10).build();
+    world.set_tick(42);
+
+    println!("{}", world.describe_entity(goblin).unwrap());
+    assert!(world.describe_entity(2).is_none());
+
+    println!("{:?}", world);
+    println!("{:#?}", world);
+}
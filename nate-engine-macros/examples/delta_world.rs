@@ -0,0 +1,63 @@
+//!
+//! Delta World to Demonstrate Keeping a Replica of a World in Sync with Deltas
+//!
+
+use nate_engine_macros::{system, world};
+
+#[world(diff, singular=[turn], tags=[enemy])]
+pub struct DeltaWorld {
+    position: (isize, isize),
+    velocity: (isize, isize),
+    #[storage(sparse)]
+    name: String,
+
+    turn: u32,
+}
+
+#[system(world=DeltaWorld, read=[velocity], write=[position])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+fn main() {
+    let world = DeltaWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_position((0, 0)).with_name(String::from("player")).build();
+        for v in 0..5 {
+            world.spawn().with_position((v, v)).with_velocity((1, 0)).with_enemy().build();
+        }
+        world.set_turn(0);
+    }
+
+    // The replica starts from a full copy of the world and is then kept in sync with deltas
+    let mut replica = world.read().unwrap().snapshot();
+
+    for turn in 1..=3 {
+        let before = world.read().unwrap().snapshot();
+
+        movement(world.clone());
+        {
+            let mut world = world.write().unwrap();
+            world.remove_entity(1);
+            world.spawn().with_position((-turn, 0)).with_enemy().build();
+            world.set_turn(turn as u32);
+        }
+
+        let delta = world.read().unwrap().delta_since(&before);
+        println!(
+            "Turn {}: {} despawned, {} spawned, {} positions changed",
+            turn,
+            delta.despawned.len(),
+            delta.spawned.len(),
+            delta.position.len(),
+        );
+        replica.apply_delta(&delta).unwrap();
+    }
+
+    // Nothing is left to send once the replica has every delta
+    assert!(replica.diff(&world.read().unwrap()).is_empty());
+    println!("Replica positions: {:?}", *replica.position.read().unwrap());
+    println!("Replica turn: {:?}", *replica.turn.read().unwrap());
+}
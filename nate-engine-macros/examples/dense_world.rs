@@ -0,0 +1,58 @@
+//!
+//! Dense World to Demonstrate Storing Components Without an Option Around Each Slot
+//!
+
+use std::sync::{Arc, RwLock};
+
+use nate_engine_macros::{system, world};
+
+#[world]
+pub struct DenseWorld {
+    // (isize, isize) takes 16 bytes per entity here instead of the 24 of Option<(isize, isize)>
+    #[storage(dense)]
+    position: (isize, isize),
+    #[storage(dense)]
+    velocity: (isize, isize),
+}
+
+// Both columns are dense, so runs of entities with a position and a velocity are read
+// straight out of the columns as slices
+#[system(world=DenseWorld, read=[velocity], write=[position])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+/// Consecutive entities with a position are handed out as plain slices
+fn wrap_positions(world: Arc<RwLock<DenseWorld>>) {
+    let world = world.read().unwrap();
+    let mut position = world.position.write().unwrap();
+    for (_start, positions) in position.runs_mut() {
+        for position in positions.iter_mut() {
+            position.0 %= 100;
+            position.1 %= 100;
+        }
+    }
+}
+
+fn main() {
+    let world = DenseWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn_batch((0..1_000).map(|v| {
+            DenseWorldBundle::default()
+                .with_position((v, v))
+                .with_velocity((1, 2))
+        }));
+        world.clear_position(500);
+    }
+
+    movement(world.clone());
+    wrap_positions(world.clone());
+
+    let world = world.read().unwrap();
+    let position = world.position.read().unwrap();
+    for (start, positions) in position.runs() {
+        println!("Entities {}..{} start at {:?}", start, start + positions.len(), positions[0]);
+    }
+}
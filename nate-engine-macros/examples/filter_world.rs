@@ -30,8 +30,7 @@ fn main() {
 
         let entity_ids = world.add_entities(20);
 
-        let healths = entity_ids.iter().map(|_v| random::<u32>() % 10).collect();
-        world.set_healths(&entity_ids, healths);
+        world.insert_health_batch(entity_ids.iter().map(|v| (*v, random::<u32>() % 10))).unwrap();
     }
 
     log_dying_entities(world.clone());
@@ -0,0 +1,72 @@
+//!
+//! Hash World to Demonstrate Comparing Runs of the Same Seed by their State Hashes
+//!
+
+use std::sync::{Arc, RwLock};
+
+use nate_engine_core::state_hash_with_hash;
+use nate_engine_macros::{system, world};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+// Components implementing `Hash` can be hashed through their `Hash` implementation
+state_hash_with_hash!(Team);
+
+#[world(hash, singular=[tick, canvas])]
+pub struct HashWorld {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    team: Team,
+
+    tick: u64,
+    // Redrawn from the positions, so it doesn't need to be compared
+    #[skip_hash]
+    canvas: Vec<String>,
+}
+
+#[system(world=HashWorld, read=[velocity], write=[position], parallel)]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+/// Run the world for some ticks from the given seed, returning the state hash after every tick
+fn run(seed: u64) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let world = HashWorld::new();
+    world.write().unwrap().spawn_batch((0..10_000).map(|v| {
+        HashWorldBundle::default()
+            .with_position((rng.gen(), rng.gen()))
+            .with_velocity((rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5))
+            .with_team(if v % 2 == 0 { Team::Red } else { Team::Blue })
+    }));
+
+    (0..10).map(|tick| step(&world, tick)).collect()
+}
+
+fn step(world: &Arc<RwLock<HashWorld>>, tick: u64) -> u64 {
+    movement(world.clone());
+    let mut world = world.write().unwrap();
+    world.set_tick(tick);
+    world.set_canvas(vec![format!("tick {}", tick)]);
+    world.state_hash()
+}
+
+fn main() {
+    let first = run(7);
+    let second = run(7);
+    for (tick, (first, second)) in first.iter().zip(second.iter()).enumerate() {
+        println!("Tick {}: {:016x} {:016x}", tick, first, second);
+        assert_eq!(first, second, "Runs diverged at tick {}", tick);
+    }
+
+    let other_seed = run(8);
+    assert_ne!(first, other_seed);
+}
@@ -0,0 +1,47 @@
+//!
+//! Hierarchy World to Demonstrate Parent/Child Relations and Despawning Whole Subtrees
+//!
+
+use nate_engine_macros::{system, world};
+
+#[world(hierarchy, tags=[turret, barrel])]
+pub struct HierarchyWorld {
+    position: (i32, i32),
+    name: String,
+}
+
+#[system(world=HierarchyWorld, read=[name, position], with=[turret])]
+fn log_turrets() {
+    // The hierarchy has its own lock, so systems can walk it next to the components they read
+    let hierarchy = world.hierarchy.read().unwrap();
+    println!("{} at {:?} has barrels {:?}", name, position, hierarchy.children(entity_id));
+}
+
+fn main() {
+    let world = HierarchyWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        let cannon = world.spawn().with_name(String::from("cannon")).with_position((0, 0)).with_turret().build();
+        let laser = world.spawn().with_name(String::from("laser")).with_position((5, 2)).with_turret().build();
+        for (turret, barrel) in [(cannon, "cannon barrel"), (laser, "left lens"), (laser, "right lens")] {
+            let barrel = world.spawn().with_name(String::from(barrel)).with_barrel().build();
+            world.set_parent(barrel, turret).unwrap();
+        }
+
+        // A turret can't be mounted on its own barrel
+        let barrel = world.children(cannon)[0];
+        println!("{}", world.set_parent(cannon, barrel).unwrap_err());
+    }
+
+    log_turrets(world.clone());
+
+    {
+        let mut world = world.write().unwrap();
+        // Removing the cannon removes its barrel too, the laser and its lenses move down
+        world.remove_entity(0);
+        println!("The laser is now entity 0 with lenses {:?}", world.children(0));
+        assert_eq!(world.parent(2), Some(0));
+    }
+
+    log_turrets(world.clone());
+}
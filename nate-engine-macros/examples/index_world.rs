@@ -0,0 +1,60 @@
+//!
+//! Index World to Demonstrate Looking Up Entities by Component Value
+//!
+
+use nate_engine_macros::{system, world};
+
+#[world(tags=[enemy])]
+pub struct IndexWorld {
+    #[index]
+    cell: (isize, isize),
+    #[unique_index]
+    name: String,
+    velocity: (isize, isize),
+}
+
+#[system(world=IndexWorld, read=[velocity], write=[cell])]
+fn movement() {
+    cell.0 += velocity.0;
+    cell.1 += velocity.1;
+}
+
+fn main() {
+    let world = IndexWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_name(String::from("player")).with_cell((0, 0)).build().unwrap();
+        for (name, x) in [("goblin", 3), ("orc", 5), ("troll", 7)] {
+            world.spawn()
+                .with_name(String::from(name))
+                .with_cell((x, 4))
+                .with_velocity((-1, 0))
+                .with_enemy()
+                .build()
+                .unwrap();
+        }
+    }
+
+    for _ in 0..2 {
+        movement(world.clone());
+        let world = world.read().unwrap();
+        // The index is updated after the system writes the cells
+        println!("At (3, 4): {:?}", world.entities_with_cell(&(3, 4)));
+    }
+
+    let mut world = world.write().unwrap();
+    let player = world.entity_with_name("player").unwrap();
+    world.set_cell(player, (3, 4));
+    println!("At (3, 4): {:?}", world.entities_with_cell(&(3, 4)));
+
+    // A name another entity has is rejected without changing anything
+    if let Err(err) = world.set_name(player, String::from("orc")) {
+        println!("{}", err);
+    }
+
+    // Removing an entity removes it from the indexes and moves the later entities down
+    let goblin = world.entity_with_name("goblin").unwrap();
+    world.remove_entity(goblin);
+    println!("The troll is now entity {:?}", world.entity_with_name("troll"));
+    println!("At (3, 4): {:?}", world.entities_with_cell(&(3, 4)));
+}
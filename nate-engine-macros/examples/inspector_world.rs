@@ -0,0 +1,70 @@
+//!
+//! Inspector World to Demonstrate Inspecting a Running Engine
+//!
+//! Run it, then look at it with the client from another terminal:
+//! `cargo run -p nate-engine-core --features inspector --bin nengine-inspect -- entities 0 10`
+//!
+
+use std::sync::{Arc, RwLock};
+
+use nate_engine_core::{Engine, Renderer};
+use nate_engine_core::inspector::DEFAULT_PORT;
+use nate_engine_macros::{system, world};
+
+#[world(singular=[tick], tags=[frozen])]
+pub struct InspectorWorld {
+    position: (isize, isize),
+    velocity: (isize, isize),
+    health: u32,
+
+    tick: u64,
+}
+
+#[system(world=InspectorWorld, read=[velocity], write=[position], without=[frozen])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+fn tick_system(world: Arc<RwLock<InspectorWorld>>) {
+    let mut world = world.write().unwrap();
+    let tick = world.tick.read().unwrap().unwrap_or(0);
+    world.set_tick(tick + 1);
+}
+
+/// Renderer that only prints the tick now and then
+struct TickRenderer;
+
+impl Renderer<InspectorWorld> for TickRenderer {
+    type Error = String;
+
+    fn render(&mut self, world: Arc<RwLock<InspectorWorld>>) -> Result<(), Self::Error> {
+        let tick = world.read().unwrap().tick.read().unwrap().unwrap_or(0);
+        if tick % 100 == 0 {
+            println!("tick {}", tick);
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let world = InspectorWorld::new();
+    world.write().unwrap().spawn_batch((0..10).map(|v| {
+        InspectorWorldBundle::default()
+            .with_position((v, v))
+            .with_velocity((1, -1))
+            .with_health(100)
+    }));
+
+    let mut engine = Engine::new(
+        30,
+        3,
+        world,
+        vec![(movement, 100_000), (tick_system, 10_000)],
+        Box::new(TickRenderer),
+    ).with_system_names(["movement", "tick"]);
+
+    let inspector = engine.start_inspector(DEFAULT_PORT).expect("Unable to Start the Inspector");
+    println!("Inspector listening on {} (Ctrl-C to stop)", inspector.address());
+    engine.run();
+}
@@ -0,0 +1,68 @@
+//!
+//! Navigation World to Demonstrate Agents Following a Shared Flow Field
+//!
+
+use nate_engine_core::navigation::NavGrid;
+use nate_engine_macros::{system, world};
+
+#[world(singular=[nav, goal])]
+pub struct NavigationWorld {
+    nav: NavGrid,
+    goal: (usize, usize),
+    cell: (usize, usize),
+}
+
+#[system(world=NavigationWorld, write=[cell], _read=[nav, goal])]
+fn walk_to_goal() {
+    // Every agent shares the cached field until the grid changes
+    if let Some(next) = nav.flow_field(*goal).next(*cell) {
+        *cell = next;
+    }
+}
+
+#[system(world=NavigationWorld, _read=[nav])]
+fn print_grid() {
+    let world_cells = world.cell.read().unwrap();
+    for y in 0..nav.height() {
+        let row: String = (0..nav.width()).map(|x| match nav.cost((x, y)) {
+            _ if world_cells.contains(&Some((x, y))) => 'e',
+            None => '#',
+            Some(1) => '.',
+            Some(_) => '~',
+        }).collect();
+        println!("{}", row);
+    }
+    println!("({} cached flow fields)", nav.cached_fields());
+}
+
+fn main() {
+    let world = NavigationWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        let mut nav = NavGrid::new(8, 5);
+        for y in 0..4 {
+            nav.set_cost((3, y), None);
+        }
+        // Swamp that agents walk around unless it saves enough steps
+        nav.set_cost((5, 3), Some(4));
+        nav.set_cost((5, 4), Some(4));
+        world.set_nav(nav);
+        world.set_goal((7, 0));
+        for start in [(0, 0), (1, 2), (0, 4)] {
+            world.spawn().with_cell(start).build();
+        }
+    }
+
+    println!("Path of the first agent: {:?}", world.read().unwrap().nav.read().unwrap().as_ref().unwrap().find_path((0, 0), (7, 0)));
+    for _ in 0..4 {
+        walk_to_goal(world.clone());
+    }
+    print_grid(world.clone());
+
+    // Building a tower clears the cached fields, so the agents take the new shortest path
+    world.read().unwrap().nav.write().unwrap().as_mut().unwrap().set_cost((4, 3), None);
+    for _ in 0..8 {
+        walk_to_goal(world.clone());
+    }
+    print_grid(world.clone());
+}
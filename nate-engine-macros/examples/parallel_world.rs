@@ -0,0 +1,63 @@
+//!
+//! Parallel World to Demonstrate Splitting a System's Entities Across Worker Threads
+//!
+
+use nate_engine_core::parallel::{self, ThreadPool};
+use nate_engine_macros::{system, world};
+
+use rand::random;
+
+#[world(singular=[total_health, lowest_health])]
+pub struct ParallelWorld {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    health: u32,
+
+    total_health: u64,
+    lowest_health: u32,
+}
+
+// Chunks of the entities are moved on different threads, each only writing the positions of
+// its own entities
+#[system(world=ParallelWorld, read=[velocity], write=[position], parallel)]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+// Each chunk sums into its own total, and the chunk totals are added into the singular
+// component afterwards (custom reductions can be given as `reduce=[singular = combiner]`)
+#[system(world=ParallelWorld, read=[health], par_chunk=4_096, reduce=[total_health, lowest_health = |lowest: &mut u32, chunk: u32| *lowest = (*lowest).min(chunk)])]
+fn health_statistics() {
+    *total_health += *health as u64;
+    if *lowest_health == 0 || *health < *lowest_health {
+        *lowest_health = *health;
+    }
+}
+
+fn main() {
+    let world = ParallelWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn_batch((0..100_000).map(|_v| {
+            ParallelWorldBundle::default()
+                .with_position((random::<f32>() * 100.0, random::<f32>() * 100.0))
+                .with_velocity((random::<f32>() - 0.5, random::<f32>() - 0.5))
+                .with_health(random::<u32>() % 100 + 1)
+        }));
+        world.set_total_health(0);
+        world.set_lowest_health(u32::MAX);
+    }
+
+    // The engine runs systems inside its own pool, outside of an engine the chunks are spread
+    // across whichever pool the systems are called with
+    let pool = ThreadPool::new(3);
+    parallel::with_pool(&pool, || {
+        movement(world.clone());
+        health_statistics(world.clone());
+    });
+
+    let world = world.read().unwrap();
+    println!("Total health: {:?}", *world.total_health.read().unwrap());
+    println!("Lowest health: {:?}", *world.lowest_health.read().unwrap());
+}
@@ -0,0 +1,55 @@
+//!
+//! Physics World to Demonstrate Fixed Step Motion and Collision Events
+//!
+
+use nate_engine_core::physics::{Collider, CollisionEvent, Physics, RigidBody, Velocity};
+use nate_engine_macros::world;
+
+#[world(physics = position, diff, hash)]
+pub struct PhysicsWorld {
+    #[spatial]
+    position: (f32, f32),
+    name: String,
+}
+
+fn main() {
+    let world = PhysicsWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        // Ten steps a second with gravity pulling down
+        *world.physics.write().unwrap() = Physics::new(0.1).with_gravity((0.0, -10.0));
+        world.spawn()
+            .with_name(String::from("ground"))
+            .with_position((0.0, 0.0))
+            .with_collider(Collider::new(20.0, 1.0))
+            .with_rigid_body(RigidBody::Static)
+            .build();
+        world.spawn()
+            .with_name(String::from("crate"))
+            .with_position((0.0, 3.0))
+            .with_velocity(Velocity(2.0, 0.0))
+            .with_collider(Collider::new(1.0, 1.0))
+            .with_rigid_body(RigidBody::Dynamic)
+            .build();
+        world.spawn()
+            .with_name(String::from("coin"))
+            .with_position((1.5, 1.0))
+            .with_collider(Collider::new(0.5, 0.5).sensor())
+            .build();
+    }
+
+    for step in 0..12 {
+        PhysicsWorld::step_physics(world.clone()).unwrap();
+        let world = world.read().unwrap();
+        for event in world.physics.write().unwrap().drain_events() {
+            let (CollisionEvent::Started(a, b) | CollisionEvent::Ended(a, b)) = event;
+            let names = world.name.read().unwrap();
+            println!("Step {}: {:?} ({} and {})", step, event, names[a].as_ref().unwrap(), names[b].as_ref().unwrap());
+        }
+    }
+
+    let world = world.read().unwrap();
+    println!("The crate is sliding along the ground at {:?}", world.position.read().unwrap()[1]);
+    // The spatial grid follows the positions physics moved
+    println!("Near the coin: {:?}", world.position_grid.read().unwrap().within_radius(&(1.5, 1.0), 1.5));
+}
@@ -0,0 +1,58 @@
+//!
+//! Prefab World to Demonstrate Spawning Entities from Prefab and Scene Files
+//!
+
+use std::path::Path;
+
+use nate_engine_core::prefab::{Prefab, Scene};
+use nate_engine_core::storage::ComponentStorage;
+use nate_engine_macros::world;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+#[world(tags=[enemy])]
+pub struct PrefabWorld {
+    position: (isize, isize),
+    health: u32,
+    team: Team,
+    #[storage(sparse)]
+    range: u32,
+}
+
+fn main() {
+    let prefabs = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/prefabs");
+    let world = PrefabWorld::new();
+    let mut world = world.write().unwrap();
+
+    // A single prefab
+    let goblin = Prefab::load(prefabs.join("goblin.json")).unwrap();
+    let entity_id = world.spawn_prefab(&goblin).unwrap();
+    println!("Spawned {} as entity {}", goblin.name, entity_id);
+
+    // A scene of prefabs with overrides
+    let scene = Scene::load(prefabs.join("level.json")).unwrap();
+    let entity_ids = world.spawn_scene(&scene).unwrap();
+    for (scene_entity, entity_id) in entity_ids.iter().enumerate() {
+        println!(
+            "{}: position {:?}, health {:?}, team {:?}, range {:?}, enemy {}",
+            scene.entity_name(scene_entity),
+            world.position.read().unwrap().get(*entity_id),
+            world.health.read().unwrap().get(*entity_id),
+            world.team.read().unwrap().get(*entity_id),
+            world.range.read().unwrap().get(*entity_id),
+            world.has_enemy(*entity_id),
+        );
+    }
+
+    // Errors name the file, entity and field
+    let broken = Scene::load(prefabs.join("broken_level.json")).unwrap();
+    println!("{}", world.spawn_scene(&broken).unwrap_err());
+    let unknown = Prefab::new("wizard").with("mana", serde_json::json!(10));
+    println!("{}", world.spawn_prefab(&unknown).unwrap_err());
+}
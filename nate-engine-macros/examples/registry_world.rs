@@ -0,0 +1,58 @@
+//!
+//! Registry World to Demonstrate Listing Components and Accessing them by Name
+//!
+
+use nate_engine_macros::world;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Inventory {
+    items: Vec<String>,
+}
+
+#[world(singular=[difficulty], tags=[enemy])]
+pub struct RegistryWorld {
+    position: (isize, isize),
+    health: u32,
+    name: String,
+    // Doesn't implement FromStr, so it can only be set from code
+    inventory: Inventory,
+
+    difficulty: f32,
+}
+
+fn main() {
+    for component in RegistryWorld::REGISTRY {
+        println!("{:<10} {:<12} {:?}", component.name, component.type_name, component.kind);
+    }
+
+    let world = RegistryWorld::new();
+    let mut world = world.write().unwrap();
+    let entity_id = world.spawn()
+        .with_position((1, 2))
+        .with_inventory(Inventory { items: vec![String::from("sword")] })
+        .build();
+
+    // Set components from strings, like a console or inspector would
+    world.set_from_str(entity_id, "health", "25").unwrap();
+    world.set_from_str(entity_id, "name", "goblin").unwrap();
+    world.set_from_str(entity_id, "enemy", "true").unwrap();
+    world.set_singular_from_str("difficulty", "1.5").unwrap();
+    println!("{}", world.set_from_str(entity_id, "health", "lots").unwrap_err());
+    println!("{}", world.set_from_str(entity_id, "inventory", "shield").unwrap_err());
+    println!("{}", world.set_from_str(entity_id, "mana", "10").unwrap_err());
+
+    // Read them back as `dyn Any` or as their `Debug` output
+    let health = world.get_dyn(entity_id, "health").unwrap();
+    let info = RegistryWorld::component_info("health").unwrap();
+    println!("health is u32: {}, value {:?}", info.is::<u32>(), health.downcast_ref::<u32>());
+    drop(health);
+
+    for component in RegistryWorld::REGISTRY {
+        let value = match component.kind {
+            nate_engine_core::registry::ComponentKind::Singular => world.get_singular_string(component.name),
+            _ => world.get_string(entity_id, component.name),
+        };
+        println!("{} = {:?}", component.name, value.unwrap());
+    }
+}
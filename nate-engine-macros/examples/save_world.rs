@@ -0,0 +1,60 @@
+//!
+//! Save World to Demonstrate Saving a World to JSON and Loading it Back
+//!
+
+use nate_engine_core::storage::ComponentStorage;
+use nate_engine_macros::world;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameState {
+    Playing,
+    Paused,
+}
+
+#[world(singular=[game_state, canvas], tags=[enemy])]
+pub struct SaveWorld {
+    position: (isize, isize),
+    #[storage(sparse)]
+    name: String,
+    // Rebuilt every frame, so there is no need to save it
+    #[skip_save]
+    last_position: (isize, isize),
+
+    game_state: GameState,
+    #[skip_save]
+    canvas: Vec<String>,
+}
+
+fn main() {
+    let world = SaveWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_position((0, 0)).with_name(String::from("player")).build();
+        world.spawn().with_position((5, 3)).with_enemy().build();
+        world.spawn().with_position((-2, 7)).with_last_position((-2, 6)).with_enemy().build();
+        world.set_game_state(GameState::Paused);
+        world.set_canvas(vec![String::from("..@..")]);
+    }
+
+    let mut saved = Vec::new();
+    world.read().unwrap().serialize_world(&mut serde_json::Serializer::new(&mut saved)).unwrap();
+    println!("{}", String::from_utf8(saved.clone()).unwrap());
+
+    let loaded = SaveWorld::new();
+    {
+        let mut loaded = loaded.write().unwrap();
+        loaded.set_canvas(vec![String::from(".....")]);
+        loaded.deserialize_world(&mut serde_json::Deserializer::from_slice(&saved)).unwrap();
+    }
+
+    let loaded = loaded.read().unwrap();
+    println!("Positions: {:?}", *loaded.position.read().unwrap());
+    let name = loaded.name.read().unwrap();
+    println!("Names: {:?}", name.entity_ids().map(|v| (v, name.get(v).unwrap())).collect::<Vec<_>>());
+    println!("Enemies: {:?}", loaded.enemy.read().unwrap().iter().collect::<Vec<usize>>());
+    println!("Last positions (not saved): {:?}", *loaded.last_position.read().unwrap());
+    println!("Game state: {:?}", *loaded.game_state.read().unwrap());
+    println!("Canvas (not saved): {:?}", *loaded.canvas.read().unwrap());
+}
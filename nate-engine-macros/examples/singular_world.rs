@@ -41,8 +41,8 @@ fn main() {
 
         let entity_ids = world.add_entities(10);
 
-        let positions = entity_ids.iter().map(|_v| (random::<f32>() * 10.0, random::<f32>() * 10.0)).collect();
-        world.set_positions(&entity_ids, positions);
+        let positions = entity_ids.iter().map(|v| (*v, (random::<f32>() * 10.0, random::<f32>() * 10.0)));
+        world.insert_position_batch(positions).unwrap();
 
         world.set_canvas([[false; 10]; 10]);
     }
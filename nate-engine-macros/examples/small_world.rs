@@ -52,8 +52,12 @@ fn main() {
     {
         let mut world = world.write().unwrap();
         world.add_entities(4);
-        world.set_positions(&vec![0, 1, 2, 3], vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0)]);
-        world.set_player_velocitys(&vec![0, 1, 2, 3], vec![(3.0, 3.0), (2.0, 2.0), (1.0, 1.0), (0.0, 0.0)]);
+        world.insert_position_batch([(0, (0.0, 0.0)), (1, (1.0, 1.0)), (2, (2.0, 2.0)), (3, (3.0, 3.0))]).unwrap();
+        world.insert_player_velocity_batch([(0, (3.0, 3.0)), (1, (2.0, 2.0)), (2, (1.0, 1.0)), (3, (0.0, 0.0))]).unwrap();
+
+        // Entity 4 does not exist, so inserting for it is reported back
+        let result = world.insert_position_batch([(3, (3.0, 3.0)), (4, (4.0, 4.0))]);
+        println!("Inserting Invalid Entity: {:?}", result);
     }
 
     read_positions(world.clone());
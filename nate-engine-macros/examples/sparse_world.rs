@@ -0,0 +1,49 @@
+//!
+//! Sparse World to Demonstrate Storing Rarely Present Components in a Sparse Set
+//!
+
+use nate_engine_macros::{system, world};
+
+use rand::random;
+
+#[world]
+pub struct SparseWorld {
+    position: (f32, f32),
+    health: u32,
+    // Only a handful of entities are ever the boss, so there is no reason to keep a
+    // slot for every entity
+    #[storage(sparse)]
+    boss: String,
+}
+
+// Only visits the entities in the boss sparse set instead of every entity with a position
+#[system(world=SparseWorld, read=[position, boss])]
+fn log_bosses() {
+    println!("Boss {} ({}) is at {:?}", boss, entity_id, position);
+}
+
+#[system(world=SparseWorld, read=[boss], write=[health])]
+fn heal_bosses() {
+    *health += 10;
+    println!("Healed {} to {}", boss, health);
+}
+
+fn main() {
+    let world = SparseWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn_batch((0..100_000).map(|_v| {
+            SparseWorldBundle::default()
+                .with_position((random::<f32>() * 100.0, random::<f32>() * 100.0))
+                .with_health(random::<u32>() % 100)
+        }));
+
+        world.set_boss(10, String::from("Dragon"));
+        world.set_boss(50_000, String::from("Lich"));
+        world.set_boss(99_999, String::from("Hydra"));
+        world.clear_boss(50_000);
+    }
+
+    log_bosses(world.clone());
+    heal_bosses(world.clone());
+}
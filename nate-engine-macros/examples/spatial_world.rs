@@ -0,0 +1,46 @@
+//!
+//! Spatial World to Demonstrate Neighbour Queries Over Positions
+//!
+
+use nate_engine_macros::{system, world};
+
+#[world(tags=[sheep, wolf])]
+pub struct SpatialWorld {
+    #[spatial(cell_size = 4.0)]
+    position: (f32, f32),
+    velocity: (f32, f32),
+}
+
+#[system(world=SpatialWorld, read=[velocity], write=[position])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+#[system(world=SpatialWorld, read=[position], with=[wolf])]
+fn hunt() {
+    // The grid is updated after the movement system writes the positions
+    let grid = world.position_grid.read().unwrap();
+    let nearby: Vec<usize> = grid.within_radius(position, 3.0).into_iter().filter(|v| *v != entity_id).collect();
+    println!("Wolf {} can reach {:?}", entity_id, nearby);
+    println!("The two entities nearest to wolf {} are {:?}", entity_id, &grid.nearest_k(position, 3)[1..]);
+}
+
+fn main() {
+    let world = SpatialWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_position((0.0, 0.0)).with_velocity((1.0, 0.0)).with_wolf().build();
+        for position in [(5.0, 0.0), (6.0, 1.0), (-8.0, 3.0), (20.0, 20.0)] {
+            world.spawn().with_position(position).with_sheep().build();
+        }
+    }
+
+    for _ in 0..3 {
+        movement(world.clone());
+        hunt(world.clone());
+    }
+
+    let world = world.read().unwrap();
+    println!("In the pen: {:?}", world.position_grid.read().unwrap().in_rect(&(-10.0, -10.0), &(10.0, 10.0)));
+}
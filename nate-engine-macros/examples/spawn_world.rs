@@ -0,0 +1,52 @@
+//!
+//! Spawn World to Demonstrate Spawning Fully Formed Entities with the
+//! Entity Builder and Bundles
+//!
+
+use nate_engine_macros::{system, world};
+
+#[world]
+pub struct SpawnWorld {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    health: u32,
+}
+
+#[system(world=SpawnWorld, read=[position, velocity, health])]
+fn log_entities() {
+    println!("Entity {}: position: {:?}, velocity: {:?}, health: {}", entity_id, position, velocity, health);
+}
+
+#[system(world=SpawnWorld, read=[position])]
+fn log_positions() {
+    println!("Entity {}: position: {:?}", entity_id, position);
+}
+
+fn main() {
+    let world = SpawnWorld::new();
+    {
+        let mut world = world.write().unwrap();
+
+        // Build an entity one component at a time
+        world.spawn()
+            .with_position((0.0, 0.0))
+            .with_velocity((1.0, 1.0))
+            .with_health(10)
+            .build();
+
+        // Or spawn an entity from a bundle of components
+        let bundle = SpawnWorldBundle::default()
+            .with_position((5.0, 5.0))
+            .with_health(3);
+        world.spawn_bundle(bundle);
+
+        world.spawn_bundle(SpawnWorldBundle {
+            position: Some((2.0, 2.0)),
+            velocity: Some((-1.0, 0.0)),
+            health: Some(7),
+        });
+    }
+
+    log_entities(world.clone());
+    log_positions(world.clone());
+}
@@ -0,0 +1,52 @@
+//!
+//! Tag World to Demonstrate Zero-Sized Tag Components and Filtering Systems on Them
+//!
+
+use nate_engine_macros::{system, world};
+
+#[world(tags=[enemy, dead])]
+pub struct TagWorld {
+    position: (i32, i32),
+    health: u32,
+    // Unit fields are also tags
+    player: (),
+}
+
+#[system(world=TagWorld, read=[position], with=[player])]
+fn log_player() {
+    println!("Player {} is at {:?}", entity_id, position);
+}
+
+#[system(world=TagWorld, write=[health], with=[enemy], without=[dead])]
+fn damage_enemies() {
+    *health = health.saturating_sub(5);
+    println!("Enemy {} has {} health", entity_id, health);
+}
+
+#[system(world=TagWorld, without=[dead])]
+fn log_alive() {
+    println!("Entity {} is alive", entity_id);
+}
+
+fn main() {
+    let world = TagWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_position((0, 0)).with_health(100).with_player().build();
+
+        let enemies = world.spawn_batch((1..5).map(|v| {
+            TagWorldBundle::default()
+                .with_position((v, v))
+                .with_health(10)
+                .with_enemy()
+        }));
+        world.set_dead(enemies[0]);
+        world.insert_dead_batch([enemies[1]]).unwrap();
+        world.clear_dead(enemies[1]);
+        println!("Enemy {} is dead: {}", enemies[0], world.has_dead(enemies[0]));
+    }
+
+    log_player(world.clone());
+    damage_enemies(world.clone());
+    log_alive(world.clone());
+}
@@ -0,0 +1,71 @@
+//!
+//! Tilemap World to Demonstrate Loading a Tiled Map and Spawning its Objects
+//!
+
+use std::path::Path;
+
+use nate_engine_core::glyph::{Color, Glyph, GlyphGrid};
+use nate_engine_core::prefab::Prefab;
+use nate_engine_core::tilemap::Tilemap;
+use nate_engine_macros::{system, world};
+
+#[world(singular=[map, canvas])]
+pub struct TilemapWorld {
+    map: Tilemap,
+    #[skip_save]
+    canvas: GlyphGrid,
+    position: (usize, usize),
+    glyph: char,
+    health: u32,
+}
+
+#[system(world=TilemapWorld, _read=[map], _write=[canvas])]
+fn draw_map() {
+    canvas.clear();
+    map.render(canvas, (0, 0));
+}
+
+#[system(world=TilemapWorld, read=[position, glyph], _write=[canvas])]
+fn draw_entities() {
+    let background = canvas.get(*position).map_or(Color::Reset, |v| v.bg);
+    canvas.set(*position, Glyph::new(*glyph).with_fg(Color::Yellow).with_bg(background));
+}
+
+#[system(world=TilemapWorld, _read=[canvas])]
+fn print_canvas() {
+    for row in canvas.rows() {
+        println!("{}", row.iter().map(|v| v.symbol).collect::<String>());
+    }
+}
+
+fn main() {
+    let maps = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/maps");
+    let map = Tilemap::load_tiled(maps.join("dungeon.json")).unwrap();
+    println!("{:?}", map.properties.get("title").and_then(|v| v.as_str()));
+
+    let world = TilemapWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        // Objects name the prefab they spawn with their class and override its components
+        let prefabs = [
+            Prefab::new("player").with("glyph", serde_json::json!("@")).with("health", serde_json::json!(20)),
+            Prefab::new("rat").with("glyph", serde_json::json!("r")).with("health", serde_json::json!(3)),
+        ];
+        let scene = map.object_scene(prefabs, Some("position"));
+        let entity_ids = world.spawn_scene(&scene).unwrap();
+        for (scene_entity, entity_id) in entity_ids.iter().enumerate() {
+            println!("{} is entity {} with {:?} health", scene.entity_name(scene_entity), entity_id, world.health.read().unwrap()[*entity_id]);
+        }
+
+        world.set_canvas(GlyphGrid::new(map.width, map.height));
+        world.set_map(map);
+    }
+
+    draw_map(world.clone());
+    draw_entities(world.clone());
+    print_canvas(world.clone());
+
+    // Paths go around walls and avoid the water unless it saves enough steps
+    let nav = world.read().unwrap().map.read().unwrap().as_ref().unwrap().nav_grid();
+    println!("Path to the rat: {:?}", nav.find_path((1, 1), (8, 4)));
+}
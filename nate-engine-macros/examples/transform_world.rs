@@ -0,0 +1,49 @@
+//!
+//! Transform World to Demonstrate Propagating Transforms Down the Hierarchy
+//!
+
+use nate_engine_core::transform::{GridTransform, LocalTransform};
+use nate_engine_macros::{system, world};
+
+#[world(transform = GridTransform, tags=[turret, barrel])]
+pub struct TransformWorld {
+    name: String,
+}
+
+#[system(world=TransformWorld, write=[local_transform], with=[turret])]
+fn rotate_turrets() {
+    // Writing through the local transform marks it, so only the turrets' subtrees are recomputed
+    local_transform.rotation = (local_transform.rotation + 1) % 4;
+}
+
+#[system(world=TransformWorld, read=[name, global_transform], with=[barrel])]
+fn log_barrels() {
+    println!("{} is at {:?} facing {}", name, global_transform.position, global_transform.rotation);
+}
+
+fn main() {
+    let world = TransformWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        let turret = world.spawn()
+            .with_name(String::from("turret"))
+            .with_local_transform(LocalTransform::new(GridTransform::from_position((10, 10))))
+            .with_turret()
+            .build();
+        // Entities spawned with only a local transform get a global transform when propagated
+        for (name, offset) in [("left barrel", (1, -1)), ("right barrel", (1, 1))] {
+            let barrel = world.spawn()
+                .with_name(String::from(name))
+                .with_local_transform(LocalTransform::new(GridTransform::from_position(offset)))
+                .with_barrel()
+                .build();
+            world.set_parent(barrel, turret).unwrap();
+        }
+    }
+
+    for _ in 0..2 {
+        TransformWorld::propagate_transforms(world.clone());
+        log_barrels(world.clone());
+        rotate_turrets(world.clone());
+    }
+}
@@ -0,0 +1,68 @@
+//!
+//! Versioned Save World to Demonstrate Upgrading Saves of Older Versions of a World
+//!
+
+use nate_engine_core::error::SaveError;
+use nate_engine_core::save::{ComponentKind, ComponentSchema, Migrations, SaveFile};
+use nate_engine_macros::world;
+
+// Version 0 of the world was saved with a `pos` component and no health
+const VERSION_0_SAVE: &str = r#"{
+    "world": "VersionedWorld",
+    "version": 0,
+    "components": [
+        { "name": "pos", "type_name": "(isize,isize)", "kind": "Entity" },
+        { "name": "enemy", "type_name": "()", "kind": "Tag" }
+    ],
+    "data": {
+        "entities": [0, 1, 2],
+        "pos": [[0, [0, 0]], [1, [5, 3]], [2, [-2, 7]]],
+        "enemy": [1, 2]
+    }
+}"#;
+
+// Version 1 renamed `pos` to `position` and version 2 added health to every enemy
+#[world(version = 2, tags=[enemy])]
+pub struct VersionedWorld {
+    position: (isize, isize),
+    health: u32,
+}
+
+fn migrations() -> Migrations {
+    let mut migrations = Migrations::new();
+    migrations.register(0, |save| save.rename_component("pos", "position"));
+    migrations.register(1, |save| {
+        let enemies = save.component_mut("enemy")
+            .and_then(|v| v.as_array().cloned())
+            .ok_or_else(|| SaveError::MissingComponent(String::from("enemy")))?;
+        let health = enemies.into_iter().map(|entity_id| serde_json::json!([entity_id, 10])).collect();
+        save.add_component(ComponentSchema::new("health", "u32", ComponentKind::Entity), serde_json::Value::Array(health));
+        Ok(())
+    });
+    migrations
+}
+
+fn main() {
+    let save: SaveFile = serde_json::from_str(VERSION_0_SAVE).unwrap();
+
+    // Without the migrations the old save can't be loaded
+    let world = VersionedWorld::new();
+    let err = world.write().unwrap().load(save.clone(), &Migrations::new()).unwrap_err();
+    println!("Loading without migrations: {}", err);
+
+    world.write().unwrap().load(save, &migrations()).unwrap();
+    {
+        let world = world.read().unwrap();
+        println!("Positions: {:?}", *world.position.read().unwrap());
+        println!("Health: {:?}", *world.health.read().unwrap());
+    }
+
+    // Saves of the current version record the world's schema
+    let save = world.read().unwrap().save().unwrap();
+    println!("{}", serde_json::to_string(&save).unwrap());
+
+    // Saves with components the world doesn't know about are rejected
+    let mut save = save;
+    save.add_component(ComponentSchema::new("mana", "u32", ComponentKind::Entity), serde_json::json!([]));
+    println!("Loading an unknown component: {}", world.write().unwrap().load(save, &migrations()).unwrap_err());
+}
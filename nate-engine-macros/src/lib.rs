@@ -1,18 +1,49 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
+use proc_macro_crate::{crate_name, FoundCrate};
 use quote::{format_ident, quote};
-use syn::{parse::{Parse, ParseStream, Result}, parse_macro_input, Block, Error, Expr, ExprBinary, FnArg, Ident, ItemFn, ItemStruct, Token};
+use syn::{parse::{Parse, ParseStream, Result}, parse_macro_input, Block, Error, Expr, ExprBinary, FnArg, Ident, ItemFn, ItemStruct, LitStr, Token};
 
 use std::collections::{HashMap, HashSet};
 
+/// Path to the engine core from the crate using the macros (either nate-engine-core
+/// directly or its re-export through nate-engine)
+fn core_path() -> proc_macro2::TokenStream {
+    let found = crate_name("nate-engine-core").or_else(|_| crate_name("nate-engine"));
+    match found {
+        Ok(FoundCrate::Itself) => quote!{ crate },
+        Ok(FoundCrate::Name(name)) => {
+            let name = format_ident!("{}", name);
+            quote!{ ::#name }
+        },
+        Err(_) => quote!{ ::nate_engine },
+    }
+}
+
 struct IgnoreArgs {
     ignore_identifiers: HashSet<String>,
+    tag_identifiers: Vec<Ident>,
+    archetype_storage: bool,
+    schema_version: u32,
+    diff: bool,
+    hash: bool,
+    hierarchy: bool,
+    transform: Option<syn::Path>,
+    physics: Option<Ident>,
 }
 
 impl Parse for IgnoreArgs {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut ignore_identifiers = HashSet::new();
+        let mut tag_identifiers = Vec::new();
+        let mut archetype_storage = false;
+        let mut schema_version = 0;
+        let mut diff = false;
+        let mut hash = false;
+        let mut hierarchy = false;
+        let mut transform = None;
+        let mut physics = None;
 
         let parts = input.parse_terminated(Expr::parse, Token![,])?;
         for part in parts.iter() {
@@ -37,18 +68,179 @@ impl Parse for IgnoreArgs {
                                 },
                                 _ => (),
                             }
+                        } else if segment.ident.to_string().as_str() == "tags" {
+                            match assignment.right.as_ref() {
+                                Expr::Path(path) => {
+                                    if let Some(segment) = path.path.segments.first() {
+                                        tag_identifiers.push(segment.ident.clone());
+                                    }
+                                },
+                                Expr::Array(array) => {
+                                    for element in array.elems.iter() {
+                                        if let Expr::Path(path) = element {
+                                            if let Some(segment) = path.path.segments.first() {
+                                                tag_identifiers.push(segment.ident.clone());
+                                            }
+                                        }
+                                    }
+                                },
+                                _ => (),
+                            }
+                        } else if segment.ident.to_string().as_str() == "storage" {
+                            let layout = match assignment.right.as_ref() {
+                                Expr::Path(path) => path.path.get_ident(),
+                                _ => None,
+                            };
+                            match layout.map(|v| v.to_string()) {
+                                Some(layout) if layout == "archetype" => archetype_storage = true,
+                                Some(layout) if layout == "vec" => archetype_storage = false,
+                                _ => return Err(Error::new_spanned(&assignment.right, "Expected World Storage to be one of `vec` or `archetype`")),
+                            }
+                        } else if segment.ident.to_string().as_str() == "version" {
+                            schema_version = match assignment.right.as_ref() {
+                                Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(version), .. }) => version.base10_parse()?,
+                                _ => return Err(Error::new_spanned(&assignment.right, "Expected World Version to be an integer")),
+                            };
+                        } else if segment.ident.to_string().as_str() == "transform" {
+                            transform = match assignment.right.as_ref() {
+                                Expr::Path(path) => Some(path.path.clone()),
+                                _ => return Err(Error::new_spanned(&assignment.right, "Expected World Transform to be a type implementing `Transform`")),
+                            };
+                        } else if segment.ident.to_string().as_str() == "physics" {
+                            physics = match assignment.right.as_ref() {
+                                Expr::Path(path) if path.path.get_ident().is_some() => path.path.get_ident().cloned(),
+                                _ => return Err(Error::new_spanned(&assignment.right, "Expected World Physics to be the position component moved by physics")),
+                            };
                         }
                     }
                 }
+            } else if matches!(part, Expr::Path(path) if path.path.is_ident("diff")) {
+                diff = true;
+            } else if matches!(part, Expr::Path(path) if path.path.is_ident("hash")) {
+                hash = true;
+            } else if matches!(part, Expr::Path(path) if path.path.is_ident("hierarchy")) {
+                hierarchy = true;
             }
         }
 
         Ok(IgnoreArgs {
             ignore_identifiers,
+            tag_identifiers,
+            archetype_storage,
+            schema_version,
+            diff,
+            hash,
+            // Transforms are propagated down the hierarchy
+            hierarchy: hierarchy || transform.is_some(),
+            transform,
+            physics,
         })
     }
 }
 
+/// How a per-entity component is stored in the world
+enum StorageKind {
+    // One `Option` slot per entity
+    Vec,
+    // Sparse set of only the entities with the component
+    Sparse,
+    // One slot per entity with a separate presence bit set
+    Dense,
+    // Dense tables grouped by the entities' signatures
+    Archetype,
+}
+
+/// Read the storage kind from a field's `#[storage(...)]` attribute, falling back to the world's
+/// default storage kind
+fn field_storage_kind(field: &syn::Field, default: StorageKind) -> Result<StorageKind> {
+    for attribute in field.attrs.iter() {
+        if attribute.path().is_ident("storage") {
+            let kind: Ident = attribute.parse_args()?;
+            return match kind.to_string().as_str() {
+                "vec" => Ok(StorageKind::Vec),
+                "sparse" => Ok(StorageKind::Sparse),
+                "dense" => Ok(StorageKind::Dense),
+                _ => Err(Error::new(kind.span(), "Expected Storage to be one of `vec`, `sparse` or `dense`")),
+            };
+        }
+    }
+    Ok(default)
+}
+
+/// The type of a field as written, without the spaces quote puts between tokens
+fn type_name(ty: &syn::Type) -> String {
+    let tokens = quote!{ #ty }.to_string();
+    let mut name = String::with_capacity(tokens.len());
+    let mut previous = ' ';
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ' ' {
+            // Spaces are only kept between words (e.g. `dyn Trait`)
+            let next = chars.peek().copied().unwrap_or(' ');
+            if (previous.is_alphanumeric() || previous == '_') && (next.is_alphanumeric() || next == '_') {
+                name.push(c);
+            }
+        } else {
+            name.push(c);
+            previous = c;
+        }
+    }
+    name
+}
+
+/// Whether a field is left out of saved worlds with `#[skip_save]`
+fn field_skips_save(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attribute| attribute.path().is_ident("skip_save"))
+}
+
+/// Whether a field is left out of the world's state hash with `#[skip_hash]`
+fn field_skips_hash(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attribute| attribute.path().is_ident("skip_hash"))
+}
+
+/// Whether a field is indexed with `#[index]` (`Some(false)`) or `#[unique_index]` (`Some(true)`)
+fn field_index(field: &syn::Field) -> Option<bool> {
+    field.attrs.iter().find_map(|attribute| {
+        if attribute.path().is_ident("index") {
+            Some(false)
+        } else if attribute.path().is_ident("unique_index") {
+            Some(true)
+        } else {
+            None
+        }
+    })
+}
+
+/// The cell size of a field's spatial grid with `#[spatial]` (cells of size 1) or
+/// `#[spatial(cell_size = N)]`
+fn field_spatial(field: &syn::Field) -> Result<Option<Expr>> {
+    for attribute in field.attrs.iter() {
+        if attribute.path().is_ident("spatial") {
+            let mut cell_size = syn::parse_quote!{ 1.0 };
+            if !matches!(attribute.meta, syn::Meta::Path(_)) {
+                attribute.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("cell_size") {
+                        cell_size = meta.value()?.parse()?;
+                        Ok(())
+                    } else {
+                        Err(meta.error("Expected `cell_size = ...`"))
+                    }
+                })?;
+            }
+            return Ok(Some(cell_size));
+        }
+    }
+    Ok(None)
+}
+
+/// Check that the world doesn't already have a field the option adds to it
+fn check_added_fields(fields: &syn::FieldsNamed, added: &[&str], option: &str) -> Result<()> {
+    match fields.named.iter().filter_map(|v| v.ident.as_ref()).find(|v| added.iter().any(|added| v == added)) {
+        Some(ident) => Err(Error::new(ident.span(), format!("Worlds with `{}` add the `{}` component themselves, rename this field", option, ident))),
+        None => Ok(()),
+    }
+}
+
 #[proc_macro_attribute]
 pub fn world(attr: TokenStream, item: TokenStream) -> TokenStream {
     let item = parse_macro_input!(item as ItemStruct);
@@ -56,34 +248,271 @@ pub fn world(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let ignore_args = parse_macro_input!(attr as IgnoreArgs);
 
-    let fields = item.fields;
+    let core = core_path();
+    let storage = quote!{ #core::storage::ComponentStorage };
+
+    // Worlds with a transform get the standard pair of transform components
+    let mut fields = item.fields;
+    if let (Some(transform), syn::Fields::Named(named)) = (&ignore_args.transform, &mut fields) {
+        if let Err(err) = check_added_fields(named, &["local_transform", "global_transform"], "transform") {
+            return err.to_compile_error().into();
+        }
+        named.named.push(syn::parse_quote!{ local_transform: #core::transform::LocalTransform<#transform> });
+        named.named.push(syn::parse_quote!{ global_transform: #core::transform::GlobalTransform<#transform> });
+    }
+    // Worlds with physics get the components physics moves and collides entities with
+    if let (Some(_), syn::Fields::Named(named)) = (&ignore_args.physics, &mut fields) {
+        if let Err(err) = check_added_fields(named, &["velocity", "acceleration", "collider", "rigid_body"], "physics") {
+            return err.to_compile_error().into();
+        }
+        named.named.push(syn::parse_quote!{ velocity: #core::physics::Velocity });
+        named.named.push(syn::parse_quote!{ acceleration: #core::physics::Acceleration });
+        named.named.push(syn::parse_quote!{ collider: #core::physics::Collider });
+        named.named.push(syn::parse_quote!{ rigid_body: #core::physics::RigidBody });
+    }
     let mut field_identifiers = Vec::new();
     let mut field_types = Vec::new();
+    let mut field_storages = Vec::new();
+    let mut field_archetypes = Vec::new();
     let mut ignore_identifiers = Vec::new();
     let mut ignore_types = Vec::new();
+    let mut tag_identifiers: Vec<&Ident> = ignore_args.tag_identifiers.iter().collect();
+    let mut field_saved = Vec::new();
+    let mut tag_saved = vec![true; tag_identifiers.len()];
+    let mut ignore_saved = Vec::new();
+    let mut hashed_identifiers = Vec::new();
+    let mut hashed_tag_identifiers: Vec<&Ident> = tag_identifiers.clone();
+    let mut hashed_ignore_identifiers = Vec::new();
+    let mut field_indexes = Vec::new();
+    let mut field_spatials = Vec::new();
     for field in fields.iter() {
         if let Some(ident) = &field.ident {
+            let field_spatial = match field_spatial(field) {
+                Ok(field_spatial) => field_spatial,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            if (field_index(field).is_some() || field_spatial.is_some()) && (ignore_args.ignore_identifiers.contains(&ident.to_string()) || matches!(&field.ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty())) {
+                return Error::new(ident.span(), "Only per-entity components can be indexed").to_compile_error().into();
+            }
             if ignore_args.ignore_identifiers.contains(&ident.to_string()) {
                 ignore_identifiers.push(ident);
                 ignore_types.push(&field.ty);
+                ignore_saved.push(!field_skips_save(field));
+                if !field_skips_hash(field) {
+                    hashed_ignore_identifiers.push(ident);
+                }
+            } else if matches!(&field.ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty()) {
+                tag_identifiers.push(ident);
+                tag_saved.push(!field_skips_save(field));
+                if !field_skips_hash(field) {
+                    hashed_tag_identifiers.push(ident);
+                }
             } else {
+                let field_type = &field.ty;
+                let default_kind = if ignore_args.archetype_storage { StorageKind::Archetype } else { StorageKind::Vec };
+                let field_kind = match field_storage_kind(field, default_kind) {
+                    Ok(field_kind) => field_kind,
+                    Err(err) => return err.to_compile_error().into(),
+                };
+                let field_storage = match field_kind {
+                    StorageKind::Vec => quote!{ std::vec::Vec<std::option::Option<#field_type>> },
+                    StorageKind::Sparse => quote!{ #core::storage::SparseSet<#field_type> },
+                    StorageKind::Dense => quote!{ #core::storage::DenseColumn<#field_type> },
+                    StorageKind::Archetype => quote!{ #core::storage::ArchetypeColumn<#field_type> },
+                };
                 field_identifiers.push(ident);
-                field_types.push(&field.ty);
+                field_types.push(field_type);
+                field_storages.push(field_storage);
+                field_archetypes.push(matches!(field_kind, StorageKind::Archetype));
+                field_saved.push(!field_skips_save(field));
+                if !field_skips_hash(field) {
+                    hashed_identifiers.push(ident);
+                }
+                field_indexes.push(field_index(field));
+                field_spatials.push(field_spatial);
             }
         }
     }
 
+    // Indexed components keep a map from their values to the entities with them (and spatial
+    // components a grid of the entities' positions), updated wherever the world changes the
+    // component and by systems after they write it
+    let index = Ident::new("index", Span::mixed_site());
+    let grid = Ident::new("grid", Span::mixed_site());
+    let mut index_identifiers = Vec::new();
+    let mut indexed_identifiers = Vec::new();
+    let mut indexed_types = Vec::new();
+    let mut index_types = Vec::new();
+    let mut index_docs = Vec::new();
+    let mut index_constructors = Vec::new();
+    let mut index_locks = Vec::new();
+    let mut index_inserts = Vec::new();
+    let mut index_removes = Vec::new();
+    let mut index_checks = Vec::new();
+    let mut reindex_arms = Vec::new();
+    let mut indexed_names = Vec::new();
+    let mut lookup_functions = Vec::new();
+    // Fields with a unique index have fallible setters, and worlds with any of them fallible spawns
+    let unique_fields: Vec<bool> = field_indexes.iter().map(|v| *v == Some(true)).collect();
+    let unique_identifiers: Vec<&Ident> = field_identifiers.iter().zip(unique_fields.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+    let unique_index_identifiers: Vec<Ident> = unique_identifiers.iter().map(|v| format_ident!("{}_index", v)).collect();
+    let entity_ids = Ident::new("entity_ids", Span::mixed_site());
+    let column = Ident::new("column", Span::mixed_site());
+    for (((ident, ty), unique), cell_size) in field_identifiers.iter().zip(field_types.iter()).zip(field_indexes.iter()).zip(field_spatials.iter()) {
+        let name = LitStr::new(&ident.to_string(), Span::call_site());
+        let mut locks = Vec::new();
+        let mut inserts = Vec::new();
+        let mut removes = Vec::new();
+        let mut grid_sync = quote!{ };
+        let mut index_sync = quote!{ Ok(()) };
+        if unique == &Some(true) {
+            index_checks.push(quote!{ #index.check(entity_id, &#ident)?; });
+        } else {
+            index_checks.push(quote!{ });
+        }
+        if let Some(unique) = unique {
+            let index_identifier = format_ident!("{}_index", ident);
+            locks.push(quote!{ let mut #index = self.#index_identifier.write().unwrap(); });
+            inserts.push(quote!{ #index.insert(entity_id, &#ident); });
+            removes.push(quote!{ #index.remove(entity_id); });
+            index_sync = quote!{ self.#index_identifier.write().unwrap().sync(&*#column, #entity_ids) };
+            lookup_functions.push(if *unique {
+                let entity_with = format_ident!("entity_with_{}", ident);
+                quote!{
+                    /// The entity with the component value
+                    pub fn #entity_with<Q: std::hash::Hash + Eq + ?Sized>(&self, value: &Q) -> Option<usize>
+                    where
+                        #ty: std::borrow::Borrow<Q>,
+                    {
+                        self.#index_identifier.read().unwrap().get(value).first().copied()
+                    }
+                }
+            } else {
+                let entities_with = format_ident!("entities_with_{}", ident);
+                quote!{
+                    /// The entities with the component value in the order they were given it
+                    pub fn #entities_with<Q: std::hash::Hash + Eq + ?Sized>(&self, value: &Q) -> Vec<usize>
+                    where
+                        #ty: std::borrow::Borrow<Q>,
+                    {
+                        self.#index_identifier.read().unwrap().get(value).to_vec()
+                    }
+                }
+            });
+            index_identifiers.push(index_identifier);
+            indexed_identifiers.push(*ident);
+            indexed_types.push(*ty);
+            index_types.push(quote!{ #core::index::ComponentIndex<#ty> });
+            index_docs.push(LitStr::new("The entities with each value of the component", Span::call_site()));
+            index_constructors.push(quote!{ #core::index::ComponentIndex::<#ty>::new(#name, #unique) });
+        }
+        if let Some(cell_size) = cell_size {
+            let grid_identifier = format_ident!("{}_grid", ident);
+            locks.push(quote!{ let mut #grid = self.#grid_identifier.write().unwrap(); });
+            inserts.push(quote!{ #grid.insert(entity_id, &#ident); });
+            removes.push(quote!{ #grid.remove(entity_id); });
+            grid_sync = quote!{ self.#grid_identifier.write().unwrap().sync(&*#column, #entity_ids.clone()); };
+            index_identifiers.push(grid_identifier);
+            indexed_identifiers.push(*ident);
+            indexed_types.push(*ty);
+            index_types.push(quote!{ #core::spatial::SpatialGrid });
+            index_docs.push(LitStr::new("The entities with the component bucketed by position for neighbour queries", Span::call_site()));
+            index_constructors.push(quote!{ #core::spatial::SpatialGrid::new((#cell_size) as f64) });
+        }
+        if unique.is_some() || cell_size.is_some() {
+            // The grid goes first so it is updated even if the index reports a duplicate. Like
+            // everywhere else the column is locked before its index and grid, as systems lock them
+            reindex_arms.push(quote!{ #name => {
+                let #column = self.#ident.read().unwrap();
+                #grid_sync
+                #index_sync
+            } });
+            indexed_names.push(name);
+        }
+        index_locks.push(quote!{ #(#locks)* });
+        index_inserts.push(quote!{ #(#inserts)* });
+        index_removes.push(quote!{ #(#removes)* });
+    }
+
     let plural_identifiers: Vec<Ident> = field_identifiers.iter().map(|v| format_ident!("{}s", v)).collect();
+    // Setting a component with a unique index returns an error (without changing anything) if
+    // another entity has the value, and batches check every value before changing any
+    let world_error = quote!{ #core::error::WorldError };
+    let setter_results: Vec<proc_macro2::TokenStream> = unique_fields.iter().map(|v| if *v { quote!{ -> std::result::Result<(), #world_error> } } else { quote!{ } }).collect();
+    let setter_oks: Vec<proc_macro2::TokenStream> = unique_fields.iter().map(|v| if *v { quote!{ Ok(()) } } else { quote!{ } }).collect();
+    let setter_tries: Vec<proc_macro2::TokenStream> = unique_fields.iter().map(|v| if *v { quote!{ ? } } else { quote!{ } }).collect();
+    let batch_checks: Vec<proc_macro2::TokenStream> = field_identifiers.iter().zip(plural_identifiers.iter()).zip(unique_fields.iter()).map(|((ident, plural), unique)| if *unique {
+        let index_identifier = format_ident!("{}_index", ident);
+        quote!{
+            let #plural: std::vec::Vec<_> = #plural.into_iter().collect();
+            let slots = #storage::slots(&*#column);
+            self.#index_identifier.read().unwrap().check_changes(&[], #plural.iter().filter(|v| v.0 < slots).map(|v| (v.0, Some(&v.1))))?;
+        }
+    } else {
+        quote!{ }
+    }).collect();
+    let many_checks: Vec<proc_macro2::TokenStream> = field_identifiers.iter().zip(plural_identifiers.iter()).zip(unique_fields.iter()).map(|((ident, plural), unique)| if *unique {
+        let index_identifier = format_ident!("{}_index", ident);
+        quote!{ self.#index_identifier.read().unwrap().check_changes(&[], entity_ids.iter().copied().zip(#plural.iter().map(Some)))?; }
+    } else {
+        quote!{ }
+    }).collect();
+    let has_unique = !unique_identifiers.is_empty();
+    let bundle_name = format_ident!("{}Bundle", item_name);
+    let (spawn_result, spawn_ok, batch_result, batch_ok) = if has_unique {
+        (
+            quote!{ std::result::Result<usize, #world_error> },
+            quote!{ Ok(entity_id) },
+            quote!{ std::result::Result<Vec<usize>, #world_error> },
+            quote!{ Ok(new_entity_ids) },
+        )
+    } else {
+        (quote!{ usize }, quote!{ entity_id }, quote!{ Vec<usize> }, quote!{ new_entity_ids })
+    };
+    let (batch_bundles, batch_unique_checks) = if has_unique {
+        (
+            quote!{
+                let bundles: std::vec::Vec<#bundle_name> = bundles.into_iter().collect();
+                let additional = bundles.len();
+            },
+            quote!{
+                let start = entities_list.len();
+                #(#unique_index_identifiers.check_changes(&[], bundles.iter().enumerate().map(|v| (start + v.0, v.1.#unique_identifiers.as_ref())))?;)*
+            },
+        )
+    } else {
+        (
+            quote!{
+                let bundles = bundles.into_iter();
+                let (additional, _) = bundles.size_hint();
+            },
+            quote!{ },
+        )
+    };
+    // Wraps a spawn in the error of the method calling it
+    let spawned = |call: proc_macro2::TokenStream, map_err: proc_macro2::TokenStream| if has_unique {
+        quote!{ #call.map_err(#map_err) }
+    } else {
+        quote!{ Ok(#call) }
+    };
     let setter_identifiers: Vec<Ident> = field_identifiers.iter().map(|v| format_ident!("set_{}", v)).collect();
     let set_many_identifiers: Vec<Ident> = field_identifiers.iter().map(|v| format_ident!("set_{}s", v)).collect();
     let clear_identifiers: Vec<Ident> = field_identifiers.iter().map(|v| format_ident!("clear_{}", v)).collect();
     let clear_many_identifiers: Vec<Ident> = field_identifiers.iter().map(|v| format_ident!("clear{}s", v)).collect();
+    let with_identifiers: Vec<Ident> = field_identifiers.iter().map(|v| format_ident!("with_{}", v)).collect();
+    let insert_batch_identifiers: Vec<Ident> = field_identifiers.iter().map(|v| format_ident!("insert_{}_batch", v)).collect();
+    let set_many_deprecations: Vec<LitStr> = insert_batch_identifiers.iter().map(|v| LitStr::new(&format!("use `{}` instead", v), Span::call_site())).collect();
     let set_ignore_identifiers: Vec<Ident> = ignore_identifiers.iter().map(|v| format_ident!("set_{}", v)).collect();
     let clear_ignore_identifiers: Vec<Ident> = ignore_identifiers.iter().map(|v| format_ident!("clear_{}", v)).collect();
+    let set_tag_identifiers: Vec<Ident> = tag_identifiers.iter().map(|v| format_ident!("set_{}", v)).collect();
+    let clear_tag_identifiers: Vec<Ident> = tag_identifiers.iter().map(|v| format_ident!("clear_{}", v)).collect();
+    let has_tag_identifiers: Vec<Ident> = tag_identifiers.iter().map(|v| format_ident!("has_{}", v)).collect();
+    let with_tag_identifiers: Vec<Ident> = tag_identifiers.iter().map(|v| format_ident!("with_{}", v)).collect();
+    let insert_tag_batch_identifiers: Vec<Ident> = tag_identifiers.iter().map(|v| format_ident!("insert_{}_batch", v)).collect();
 
     let entity_fields = if field_identifiers.len() > 0 {
         quote!{
-            #(pub #field_identifiers: std::sync::Arc<std::sync::RwLock<std::vec::Vec<std::option::Option<#field_types>>>>),*,
+            #(pub #field_identifiers: std::sync::Arc<std::sync::RwLock<#field_storages>>),*,
         }
     } else {
         quote!{ }
@@ -91,7 +520,23 @@ pub fn world(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let entity_initializers = if field_identifiers.len() > 0 {
         quote!{
-            #(#field_identifiers: std::sync::Arc::new(std::sync::RwLock::new(std::vec::Vec::new()))),*,
+            #(#field_identifiers: std::sync::Arc::new(std::sync::RwLock::new(std::default::Default::default()))),*,
+        }
+    } else {
+        quote!{ }
+    };
+
+    let tag_fields = if !tag_identifiers.is_empty() {
+        quote!{
+            #(pub #tag_identifiers: std::sync::Arc<std::sync::RwLock<#core::storage::BitSet>>),*,
+        }
+    } else {
+        quote!{ }
+    };
+
+    let tag_initializers = if !tag_identifiers.is_empty() {
+        quote!{
+            #(#tag_identifiers: std::sync::Arc::new(std::sync::RwLock::new(#core::storage::BitSet::new()))),*,
         }
     } else {
         quote!{ }
@@ -113,18 +558,1083 @@ pub fn world(attr: TokenStream, item: TokenStream) -> TokenStream {
         quote!{ }
     };
 
+    // Every per-entity component and tag gets a bit in the entity signatures
+    let component_count = field_identifiers.len() + tag_identifiers.len();
+    if component_count > 64 {
+        return Error::new(item_name.span(), "Worlds can have at most 64 per-entity components and tags").to_compile_error().into();
+    }
+    let component_bits: Vec<u32> = (0..field_identifiers.len() as u32).collect();
+    let tag_bits: Vec<u32> = (field_identifiers.len() as u32..component_count as u32).collect();
+    let component_names: Vec<String> = field_identifiers.iter().chain(tag_identifiers.iter()).map(|v| v.to_string()).collect();
+    let component_bit_error = LitStr::new(&format!("Unknown component for world {}", item_name), Span::call_site());
+
+    // In archetype worlds every signature change moves the entity to a new archetype, so adding
+    // or removing a component relocates the entity's other components to the new archetype's tables
+    let archetype_storage = ignore_args.archetype_storage;
+    let archetypes = Ident::new("archetypes", Span::mixed_site());
+    let previous = Ident::new("previous", Span::mixed_site());
+    let mut insert_component_bodies = Vec::new();
+    let mut remove_component_bodies = Vec::new();
+    let mut push_component_slots = Vec::new();
+    for ((ident, bit), is_archetype) in field_identifiers.iter().zip(component_bits.iter()).zip(field_archetypes.iter()) {
+        let place = if *is_archetype {
+            quote!{ #column.insert_into(entity_id, #archetypes, #ident); }
+        } else {
+            quote!{ #storage::insert(&mut *#column, entity_id, #ident); }
+        };
+
+        if archetype_storage {
+            insert_component_bodies.push(quote!{
+                let #previous = signatures.signature(entity_id);
+                signatures.insert(entity_id, #bit);
+                let #archetypes = signatures.archetypes().unwrap();
+                self.relocate_entity(entity_id, #archetypes, #previous & !(1 << #bit));
+                #place
+            });
+            // Archetype columns take their row out after the archetypes have moved the entity,
+            // so they can read which entity was swapped into it
+            let take = if *is_archetype {
+                quote!{ #column.remove_with(entity_id, #archetypes); }
+            } else {
+                quote!{ #storage::remove(&mut *#column, entity_id); }
+            };
+            remove_component_bodies.push(quote!{
+                let #previous = signatures.signature(entity_id);
+                signatures.remove(entity_id, #bit);
+                let #archetypes = signatures.archetypes().unwrap();
+                #take
+                self.relocate_entity(entity_id, #archetypes, #previous & !(1 << #bit));
+            });
+        } else {
+            insert_component_bodies.push(quote!{
+                #storage::insert(&mut *#column, entity_id, #ident);
+                signatures.insert(entity_id, #bit);
+            });
+            remove_component_bodies.push(quote!{
+                #storage::remove(&mut *#column, entity_id);
+                signatures.remove(entity_id, #bit);
+            });
+        }
+
+        if *is_archetype {
+            let component = Ident::new("component", Span::mixed_site());
+            push_component_slots.push(quote!{
+                #storage::push_slot(&mut *#ident, None);
+                if let Some(#component) = bundle.#ident {
+                    #ident.insert_into(entity_id, #archetypes, #component);
+                }
+            });
+        } else {
+            push_component_slots.push(quote!{
+                #storage::push_slot(&mut *#ident, bundle.#ident);
+            });
+        }
+    }
+
+    let mut insert_tag_bodies = Vec::new();
+    let mut remove_tag_bodies = Vec::new();
+    for bit in tag_bits.iter() {
+        if archetype_storage {
+            insert_tag_bodies.push(quote!{
+                let #previous = signatures.signature(entity_id);
+                #column.set(entity_id);
+                signatures.insert(entity_id, #bit);
+                self.relocate_entity(entity_id, signatures.archetypes().unwrap(), #previous);
+            });
+            remove_tag_bodies.push(quote!{
+                #column.unset(entity_id);
+                let #previous = signatures.signature(entity_id);
+                signatures.remove(entity_id, #bit);
+                self.relocate_entity(entity_id, signatures.archetypes().unwrap(), #previous);
+            });
+        } else {
+            insert_tag_bodies.push(quote!{
+                #column.set(entity_id);
+                signatures.insert(entity_id, #bit);
+            });
+            remove_tag_bodies.push(quote!{
+                #column.unset(entity_id);
+                signatures.remove(entity_id, #bit);
+            });
+        }
+    }
+
+    let (signatures_constructor, spawn_archetype, relocate_entity, remove_slots) = if archetype_storage {
+        let archetype_identifiers: Vec<&Ident> = field_identifiers.iter().zip(field_archetypes.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+        let archetype_bits: Vec<u32> = component_bits.iter().zip(field_archetypes.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+        (
+            quote!{ with_archetypes },
+            quote!{ let #archetypes = signatures.archetypes().unwrap(); },
+            quote!{
+                /// Move the given components of an entity to the tables of the archetype the
+                /// archetypes have moved it to
+                fn relocate_entity(&self, entity_id: usize, archetypes: &#core::storage::Archetypes, components: #core::storage::Signature) {
+                    #(if components & (1 << #archetype_bits) != 0 {
+                        self.#archetype_identifiers.write().unwrap().migrate(entity_id, archetypes);
+                    })*
+                }
+            },
+            field_identifiers.iter().zip(field_archetypes.iter()).map(|(ident, is_archetype)| if *is_archetype {
+                quote!{ self.#ident.write().unwrap().remove_slot_with(entity_id, signatures.archetypes().unwrap()); }
+            } else {
+                quote!{ #storage::remove_slot(&mut *self.#ident.write().unwrap(), entity_id); }
+            }).collect::<Vec<_>>(),
+        )
+    } else {
+        (
+            quote!{ new },
+            quote!{ },
+            quote!{ },
+            field_identifiers.iter().map(|ident| quote!{ #storage::remove_slot(&mut *self.#ident.write().unwrap(), entity_id); }).collect(),
+        )
+    };
+
+    // Parent/child relations are opt in with `hierarchy`, which also makes removing an entity
+    // remove its descendants
+    let hierarchy = Ident::new("hierarchy", Span::mixed_site());
+    let (hierarchy_field, hierarchy_initializer, hierarchy_lock, push_hierarchy_slot, remove_hierarchy_slot, remove_descendants, hierarchy_world) = if ignore_args.hierarchy {
+        (
+            quote!{
+                /// The parent and children of every entity, kept in sync by the world's methods
+                pub hierarchy: std::sync::Arc<std::sync::RwLock<#core::hierarchy::Hierarchy>>,
+            },
+            quote!{ hierarchy: std::sync::Arc::new(std::sync::RwLock::new(#core::hierarchy::Hierarchy::new())), },
+            quote!{ let mut #hierarchy = self.hierarchy.write().unwrap(); },
+            quote!{ #hierarchy.push_entity(); },
+            quote!{ self.hierarchy.write().unwrap().remove_entity(entity_id); },
+            quote!{
+                let mut removed = self.hierarchy.read().unwrap().descendants(entity_id);
+                removed.push(entity_id);
+                removed.sort_unstable();
+                // Removing from the highest entity id down keeps the lower entity ids valid
+                for entity_id in removed.into_iter().rev() {
+                    self.remove_entity_slot(entity_id);
+                }
+            },
+            quote!{
+                /// Make an entity a child of another, returning its previous parent.
+                ///
+                /// Nothing is changed if either entity doesn't exist or the parent is the child
+                /// or one of its descendants.
+                pub fn set_parent(&mut self, child: usize, parent: usize) -> std::result::Result<Option<usize>, #core::error::WorldError> {
+                    self.hierarchy.write().unwrap().set_parent(child, parent)
+                }
+
+                /// Detach an entity from its parent, returning the parent
+                pub fn remove_parent(&mut self, child: usize) -> Option<usize> {
+                    self.hierarchy.write().unwrap().remove_parent(child)
+                }
+
+                /// The parent of an entity
+                pub fn parent(&self, entity_id: usize) -> Option<usize> {
+                    self.hierarchy.read().unwrap().parent(entity_id)
+                }
+
+                /// The children of an entity in the order they were added (systems going through
+                /// the children of many entities can read `hierarchy` once instead)
+                pub fn children(&self, entity_id: usize) -> Vec<usize> {
+                    self.hierarchy.read().unwrap().children(entity_id).to_vec()
+                }
+
+                /// The children, grandchildren, ... of an entity, every entity before its children
+                pub fn descendants(&self, entity_id: usize) -> Vec<usize> {
+                    self.hierarchy.read().unwrap().descendants(entity_id)
+                }
+            },
+        )
+    } else {
+        (quote!{ }, quote!{ }, quote!{ }, quote!{ }, quote!{ }, quote!{ self.remove_entity_slot(entity_id); }, quote!{ })
+    };
+
+    let transform_world = if ignore_args.transform.is_some() {
+        let missing = Ident::new("missing", Span::mixed_site());
+        quote!{
+            /// System computing the global transforms of the entities from their local transforms
+            /// down the hierarchy, recomputing only the subtrees that changed. Entities with only
+            /// a local transform are given a global transform first.
+            pub fn propagate_transforms(world: std::sync::Arc<std::sync::RwLock<Self>>) {
+                let #missing: Vec<usize> = world.read().unwrap().signatures.read().unwrap()
+                    .matching(1 << Self::component_bit("local_transform"), 1 << Self::component_bit("global_transform"))
+                    .collect();
+                if !#missing.is_empty() {
+                    let mut world = world.write().unwrap();
+                    for entity_id in #missing {
+                        world.set_global_transform(entity_id, std::default::Default::default());
+                        if let Some(local_transform) = #storage::get_mut(&mut *world.local_transform.write().unwrap(), entity_id) {
+                            local_transform.mark_changed();
+                        }
+                    }
+                }
+
+                let world = world.read().unwrap();
+                #core::transform::propagate(
+                    &mut world.hierarchy.write().unwrap(),
+                    &mut *world.local_transform.write().unwrap(),
+                    &mut *world.global_transform.write().unwrap(),
+                );
+            }
+        }
+    } else {
+        quote!{ }
+    };
+
+    // Physics is opt in with `physics = position`, naming the position component it moves
+    let (physics_field, physics_initializer, snapshot_physics, remove_physics_slot, clear_physics, physics_world) = if let Some(position) = &ignore_args.physics {
+        if !field_identifiers.contains(&position) {
+            return Error::new(position.span(), "Physics can only move a per-entity component of the world").to_compile_error().into();
+        }
+        let position_name = LitStr::new(&position.to_string(), Span::call_site());
+        (
+            quote!{
+                /// The physics settings, current contacts and undrained collision events
+                pub physics: std::sync::Arc<std::sync::RwLock<#core::physics::Physics>>,
+            },
+            quote!{ physics: std::sync::Arc::new(std::sync::RwLock::new(#core::physics::Physics::default())), },
+            quote!{ physics: std::sync::Arc::new(std::sync::RwLock::new(self.physics.read().unwrap().clone())), },
+            quote!{ self.physics.write().unwrap().remove_entity(entity_id); },
+            quote!{ self.physics.write().unwrap().clear(); },
+            quote!{
+                /// Simulate one fixed step of `physics.step` seconds: moving the entities by their
+                /// velocities, pushing dynamic bodies out of the colliders they hit and queueing
+                /// the collisions that started or ended in `physics`.
+                ///
+                /// The entities physics moved to the same value of a unique index are still moved
+                /// (and indexed), they are returned in the error.
+                pub fn step_physics(world: std::sync::Arc<std::sync::RwLock<Self>>) -> std::result::Result<(), #core::error::WorldError> {
+                    let world = world.read().unwrap();
+                    world.physics.write().unwrap().step(
+                        &mut *world.#position.write().unwrap(),
+                        &mut *world.velocity.write().unwrap(),
+                        &*world.acceleration.read().unwrap(),
+                        &*world.collider.read().unwrap(),
+                        &*world.rigid_body.read().unwrap(),
+                    );
+                    world.reindex(#position_name)
+                }
+
+                /// System running `step_physics`, keeping its error (if any) in `index_error`
+                /// like other systems writing indexed components
+                pub fn physics_system(world: std::sync::Arc<std::sync::RwLock<Self>>) {
+                    if let Err(err) = Self::step_physics(world.clone()) {
+                        *world.read().unwrap().index_error.write().unwrap() = Some(err);
+                    }
+                }
+            },
+        )
+    } else {
+        (quote!{ }, quote!{ }, quote!{ }, quote!{ }, quote!{ }, quote!{ })
+    };
+
+    // Saving and loading is only generated with the `serde` feature, which also brings in the
+    // serde re-export of the core crate the generated code uses
+    let save_world = if cfg!(feature = "serde") {
+        let saved_identifiers: Vec<&Ident> = field_identifiers.iter().zip(field_saved.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+        let saved_types: Vec<&syn::Type> = field_types.iter().zip(field_saved.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+        let saved_insert_batch_identifiers: Vec<&Ident> = insert_batch_identifiers.iter().zip(field_saved.iter()).filter(|v| *v.1).map(|v| v.0).collect();
+        let saved_tag_identifiers: Vec<&Ident> = tag_identifiers.iter().zip(tag_saved.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+        let saved_insert_tag_batch_identifiers: Vec<&Ident> = insert_tag_batch_identifiers.iter().zip(tag_saved.iter()).filter(|v| *v.1).map(|v| v.0).collect();
+        let saved_ignore_identifiers: Vec<&Ident> = ignore_identifiers.iter().zip(ignore_saved.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+        let saved_ignore_types: Vec<&syn::Type> = ignore_types.iter().zip(ignore_saved.iter()).filter(|v| *v.1).map(|v| *v.0).collect();
+        let saved_field_count = 1 + usize::from(ignore_args.hierarchy) + saved_identifiers.len() + saved_tag_identifiers.len() + saved_ignore_identifiers.len();
+        let saved_type_names: Vec<LitStr> = saved_types.iter().map(|v| LitStr::new(&type_name(v), Span::call_site())).collect();
+        let saved_ignore_type_names: Vec<LitStr> = saved_ignore_types.iter().map(|v| LitStr::new(&type_name(v), Span::call_site())).collect();
+        let schema_version = ignore_args.schema_version;
+        let saved_names: Vec<LitStr> = saved_identifiers.iter().map(|v| LitStr::new(&v.to_string(), Span::call_site())).collect();
+        let saved_unique_identifiers: Vec<&Ident> = saved_identifiers.iter().copied().filter(|v| unique_identifiers.contains(v)).collect();
+        let saved_unique_types: Vec<&syn::Type> = saved_identifiers.iter().zip(saved_types.iter()).filter(|v| unique_identifiers.contains(v.0)).map(|v| *v.1).collect();
+        let saved_tag_names: Vec<LitStr> = saved_tag_identifiers.iter().map(|v| LitStr::new(&v.to_string(), Span::call_site())).collect();
+        let bundle = Ident::new("bundle", Span::mixed_site());
+        let component = Ident::new("component", Span::mixed_site());
+        let entity = Ident::new("entity", Span::mixed_site());
+        let spawn_prefab = spawned(
+            quote!{ self.spawn_bundle(#bundle) },
+            quote!{ |err| #core::error::PrefabError::new(#core::error::PrefabErrorKind::World(err)).with_entity(&prefab.name) },
+        );
+        let spawn_scene = spawned(quote!{ self.spawn_batch(bundles) }, quote!{ |err| #core::error::PrefabError::new(#core::error::PrefabErrorKind::World(err)) });
+        let world_name = LitStr::new(&item_name.to_string(), Span::call_site());
+        let serde_crate = LitStr::new(&quote!{ #core::serde }.to_string(), Span::call_site());
+        let snapshot_name = format_ident!("{}Snapshot", item_name);
+        let snapshot = Ident::new("snapshot", Span::mixed_site());
+        let slots = Ident::new("slots", Span::mixed_site());
+        let state = Ident::new("state", Span::mixed_site());
+        // Relations are saved as (child, parent) pairs, which saves from before the world had
+        // a hierarchy are loaded without
+        let (serialize_relations, relations_field, load_relations, set_relations) = if ignore_args.hierarchy {
+            (
+                quote!{ #state.serialize_field("parents", &self.hierarchy.read().unwrap().relations())?; },
+                quote!{
+                    #[serde(default)]
+                    parents: std::vec::Vec<(usize, usize)>,
+                },
+                quote!{
+                    invalid_entity_ids.extend(#snapshot.parents.iter().flat_map(|v| [v.0, v.1]).filter(|v| *v >= #slots));
+                    let mut #hierarchy = #core::hierarchy::Hierarchy::with_slots(#slots);
+                    if invalid_entity_ids.is_empty() {
+                        for (child, parent) in #snapshot.parents.iter() {
+                            #hierarchy.set_parent(*child, *parent).map_err(<D::Error as #core::serde::de::Error>::custom)?;
+                        }
+                    }
+                },
+                quote!{ *self.hierarchy.write().unwrap() = #hierarchy; },
+            )
+        } else {
+            (quote!{ }, quote!{ }, quote!{ }, quote!{ })
+        };
+
+        quote!{
+            /// The version of the world's components, set with `#[world(version = N)]` and
+            /// increased whenever a change to the components needs saves to be migrated
+            pub const SCHEMA_VERSION: u32 = #schema_version;
+
+            /// The names and types of the components saved with the world
+            pub fn schema() -> Vec<#core::save::ComponentSchema> {
+                vec![
+                    #(#core::save::ComponentSchema::new(stringify!(#saved_identifiers), #saved_type_names, #core::save::ComponentKind::Entity),)*
+                    #(#core::save::ComponentSchema::new(stringify!(#saved_tag_identifiers), "()", #core::save::ComponentKind::Tag),)*
+                    #(#core::save::ComponentSchema::new(stringify!(#saved_ignore_identifiers), #saved_ignore_type_names, #core::save::ComponentKind::Singular),)*
+                ]
+            }
+
+            /// Save the world with its name, schema version and components
+            pub fn save(&self) -> std::result::Result<#core::save::SaveFile, #core::error::SaveError> {
+                let data = self.serialize_world(#core::serde_json::value::Serializer)
+                    .map_err(|err| #core::error::SaveError::Format(err.to_string()))?;
+                Ok(#core::save::SaveFile {
+                    world: std::string::String::from(#world_name),
+                    version: Self::SCHEMA_VERSION,
+                    components: Self::schema(),
+                    data,
+                })
+            }
+
+            /// Replace the contents of the world with a save, upgrading older saves with the
+            /// registered migrations
+            pub fn load(&mut self, mut save: #core::save::SaveFile, migrations: &#core::save::Migrations) -> std::result::Result<(), #core::error::SaveError> {
+                save.migrate(#world_name, Self::SCHEMA_VERSION, migrations)?;
+                save.check_components(&Self::schema())?;
+                self.deserialize_world(save.data)
+                    .map_err(|err| #core::error::SaveError::Format(err.to_string()))
+            }
+
+            /// Spawn an entity from a prefab, with errors naming the prefab's file and the field
+            /// of the component.
+            ///
+            /// Fields marked `#[skip_save]` and singular components can't be set by prefabs.
+            pub fn spawn_prefab(&mut self, prefab: &#core::prefab::Prefab) -> std::result::Result<usize, #core::error::PrefabError> {
+                let #bundle = Self::prefab_bundle(&prefab.name, prefab.components())?;
+                #spawn_prefab
+            }
+
+            /// Spawn every entity of a scene, returning the new entity ids.
+            ///
+            /// Nothing is spawned if any entity of the scene is invalid.
+            pub fn spawn_scene(&mut self, scene: &#core::prefab::Scene) -> std::result::Result<Vec<usize>, #core::error::PrefabError> {
+                let mut bundles = Vec::with_capacity(scene.entities.len());
+                for #entity in 0..scene.entities.len() {
+                    bundles.push(Self::prefab_bundle(&scene.entity_name(#entity), scene.components(#entity)?)?);
+                }
+                #spawn_scene
+            }
+
+            /// The bundle of an entity's prefab components
+            fn prefab_bundle<'a>(#entity: &str, components: impl std::iter::IntoIterator<Item = #core::prefab::PrefabComponent<'a>>) -> std::result::Result<#bundle_name, #core::error::PrefabError> {
+                let mut #bundle = #bundle_name::default();
+                for #component in components {
+                    match #component.name {
+                        #(#saved_names => #bundle.#saved_identifiers = Some(#core::prefab::component(#entity, &#component)?),)*
+                        #(#saved_tag_names => #bundle.#saved_tag_identifiers = #core::prefab::tag(#entity, &#component)?,)*
+                        _ => return Err(#core::prefab::unknown_component(#entity, &#component)),
+                    }
+                }
+                Ok(#bundle)
+            }
+
+            /// Serialize the entities, their components and tags, and the singular components of
+            /// the world (except for fields marked `#[skip_save]`)
+            pub fn serialize_world<S: #core::serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                use #core::serde::ser::SerializeStruct;
+
+                let entities_list = self.entities.read().unwrap();
+                #(let #saved_identifiers = self.#saved_identifiers.read().unwrap();)*
+                #(let #saved_tag_identifiers = self.#saved_tag_identifiers.read().unwrap();)*
+                #(let #saved_ignore_identifiers = self.#saved_ignore_identifiers.read().unwrap();)*
+
+                let mut #state = serializer.serialize_struct(#world_name, #saved_field_count)?;
+                #state.serialize_field("entities", &*entities_list)?;
+                #serialize_relations
+                #(#state.serialize_field(stringify!(#saved_identifiers), &#core::save::SerializeComponents::new(&*#saved_identifiers))?;)*
+                #(#state.serialize_field(stringify!(#saved_tag_identifiers), &#core::save::SerializeTags(&*#saved_tag_identifiers))?;)*
+                #(#state.serialize_field(stringify!(#saved_ignore_identifiers), &*#saved_ignore_identifiers)?;)*
+                #state.end()
+            }
+
+            /// Replace the contents of the world with a world saved by `serialize_world`.
+            ///
+            /// Per-entity fields marked `#[skip_save]` are left empty and singular components
+            /// marked `#[skip_save]` keep their current values. Nothing is changed if the saved
+            /// world is invalid.
+            pub fn deserialize_world<'de, D: #core::serde::Deserializer<'de>>(&mut self, deserializer: D) -> std::result::Result<(), D::Error> {
+                #[derive(#core::serde::Deserialize)]
+                #[serde(crate = #serde_crate)]
+                struct #snapshot_name {
+                    entities: std::vec::Vec<usize>,
+                    #relations_field
+                    #(#saved_identifiers: std::vec::Vec<(usize, #saved_types)>,)*
+                    #(#saved_tag_identifiers: std::vec::Vec<usize>,)*
+                    #(#saved_ignore_identifiers: std::option::Option<#saved_ignore_types>,)*
+                }
+
+                let #snapshot = <#snapshot_name as #core::serde::Deserialize>::deserialize(deserializer)?;
+                let #slots = #snapshot.entities.len();
+                let mut invalid_entity_ids = Vec::new();
+                #(invalid_entity_ids.extend(#snapshot.#saved_identifiers.iter().map(|v| v.0).filter(|v| *v >= #slots));)*
+                #(invalid_entity_ids.extend(#snapshot.#saved_tag_identifiers.iter().copied().filter(|v| *v >= #slots));)*
+                #load_relations
+                if !invalid_entity_ids.is_empty() {
+                    return Err(<D::Error as #core::serde::de::Error>::custom(#core::error::WorldError::InvalidEntities(invalid_entity_ids)));
+                }
+                #(#core::index::ComponentIndex::<#saved_unique_types>::new(stringify!(#saved_unique_identifiers), true)
+                    .check_changes(&[], #snapshot.#saved_unique_identifiers.iter().map(|v| (v.0, Some(&v.1))))
+                    .map_err(<D::Error as #core::serde::de::Error>::custom)?;)*
+
+                self.entities.write().unwrap().clear();
+                #(*self.#field_identifiers.write().unwrap() = std::default::Default::default();)*
+                #(*self.#tag_identifiers.write().unwrap() = #core::storage::BitSet::new();)*
+                *self.signatures.write().unwrap() = #core::storage::Signatures::#signatures_constructor(#component_count);
+                #(*self.#index_identifiers.write().unwrap() = #index_constructors;)*
+                #clear_physics
+                self.add_entities(#slots);
+                *self.entities.write().unwrap() = #snapshot.entities;
+                #set_relations
+
+                #(self.#saved_insert_batch_identifiers(#snapshot.#saved_identifiers).map_err(<D::Error as #core::serde::de::Error>::custom)?;)*
+                #(self.#saved_insert_tag_batch_identifiers(#snapshot.#saved_tag_identifiers).map_err(<D::Error as #core::serde::de::Error>::custom)?;)*
+                #(*self.#saved_ignore_identifiers.write().unwrap() = #snapshot.#saved_ignore_identifiers;)*
+                Ok(())
+            }
+        }
+    } else {
+        quote!{ }
+    };
+
+    // Diffing needs every component to be `Clone + PartialEq`, so worlds opt in with `diff`
+    let delta_name = format_ident!("{}Delta", item_name);
+    let (delta_struct, diff_world) = if ignore_args.diff {
+        let alignment = Ident::new("alignment", Span::mixed_site());
+        let delta = Ident::new("delta", Span::mixed_site());
+        let other = Ident::new("other", Span::mixed_site());
+        let component = Ident::new("component", Span::mixed_site());
+        // Fields left out of saves are left out of serialized deltas too
+        let (delta_derives, field_serde_skips, tag_serde_skips, ignore_serde_skips) = if cfg!(feature = "serde") {
+            let serde_crate = LitStr::new(&quote!{ #core::serde }.to_string(), Span::call_site());
+            let skip = |saved: &bool| if *saved { quote!{ } } else { quote!{ #[serde(skip)] } };
+            (
+                quote!{
+                    #[derive(#core::serde::Serialize, #core::serde::Deserialize)]
+                    #[serde(crate = #serde_crate)]
+                },
+                field_saved.iter().map(skip).collect(),
+                tag_saved.iter().map(skip).collect(),
+                ignore_saved.iter().map(skip).collect(),
+            )
+        } else {
+            (quote!{ }, vec![quote!{ }; field_identifiers.len()], vec![quote!{ }; tag_identifiers.len()], vec![quote!{ }; ignore_identifiers.len()])
+        };
+        // Parent changes are applied after every other change so their entities exist, detaching
+        // every changed child first so no step of the way has a cycle
+        let (parents_field, parents_empty, snapshot_hierarchy, diff_parents, check_parents, apply_parents) = if ignore_args.hierarchy {
+            (
+                quote!{
+                    // (Entity id, parent or `None` if detached) of the entities with a new parent
+                    pub parents: std::vec::Vec<(usize, std::option::Option<usize>)>,
+                },
+                quote!{ && self.parents.is_empty() },
+                quote!{ hierarchy: std::sync::Arc::new(std::sync::RwLock::new(self.hierarchy.read().unwrap().clone())), },
+                quote!{ #delta.parents = #core::delta::parent_changes(&self.hierarchy.read().unwrap(), &#other.hierarchy.read().unwrap(), &#alignment.previous); },
+                quote!{
+                    invalid_entity_ids.extend(#delta.parents.iter().flat_map(|v| [Some(v.0), v.1]).flatten().filter(|v| *v >= new_slots));
+                    if invalid_entity_ids.is_empty() {
+                        #core::delta::check_parent_changes(&self.hierarchy.read().unwrap(), &despawned, #delta.spawned.len(), &#delta.parents)?;
+                    }
+                },
+                quote!{
+                    for (child, _) in #delta.parents.iter() {
+                        self.remove_parent(*child);
+                    }
+                    for (child, parent) in #delta.parents.iter() {
+                        if let Some(parent) = parent {
+                            self.set_parent(*child, *parent)?;
+                        }
+                    }
+                },
+            )
+        } else {
+            (quote!{ }, quote!{ }, quote!{ }, quote!{ }, quote!{ }, quote!{ })
+        };
+
+        (
+            quote!{
+                /// The changes turning one state of a world into another
+                #[derive(Clone, Debug, Default, PartialEq)]
+                #delta_derives
+                pub struct #delta_name {
+                    // Entity ids of the removed entities (in the earlier state)
+                    pub despawned: std::vec::Vec<usize>,
+                    // Entity list values of the entities added after the earlier entities
+                    pub spawned: std::vec::Vec<usize>,
+                    #parents_field
+                    #(#field_serde_skips pub #field_identifiers: std::vec::Vec<(usize, std::option::Option<#field_types>)>,)*
+                    #(#tag_serde_skips pub #tag_identifiers: std::vec::Vec<(usize, bool)>,)*
+                    #(#ignore_serde_skips pub #ignore_identifiers: std::option::Option<std::option::Option<#ignore_types>>,)*
+                }
+
+                impl #delta_name {
+                    /// Whether the delta changes nothing
+                    pub fn is_empty(&self) -> bool {
+                        self.despawned.is_empty() && self.spawned.is_empty()
+                            #parents_empty
+                            #(&& self.#field_identifiers.is_empty())*
+                            #(&& self.#tag_identifiers.is_empty())*
+                            #(&& self.#ignore_identifiers.is_none())*
+                    }
+                }
+            },
+            quote!{
+                /// A copy of the current state of the world, to diff the world against later
+                pub fn snapshot(&self) -> Self {
+                    Self {
+                        entities: std::sync::Arc::new(std::sync::RwLock::new(self.entities.read().unwrap().clone())),
+                        signatures: std::sync::Arc::new(std::sync::RwLock::new(self.signatures.read().unwrap().clone())),
+                        index_error: std::sync::Arc::new(std::sync::RwLock::new(self.index_error.read().unwrap().clone())),
+                        #snapshot_hierarchy
+                        #snapshot_physics
+                        #(#index_identifiers: std::sync::Arc::new(std::sync::RwLock::new(self.#index_identifiers.read().unwrap().clone())),)*
+                        #(#field_identifiers: std::sync::Arc::new(std::sync::RwLock::new(self.#field_identifiers.read().unwrap().clone())),)*
+                        #(#tag_identifiers: std::sync::Arc::new(std::sync::RwLock::new(self.#tag_identifiers.read().unwrap().clone())),)*
+                        #(#ignore_identifiers: std::sync::Arc::new(std::sync::RwLock::new(self.#ignore_identifiers.read().unwrap().clone())),)*
+                    }
+                }
+
+                /// The entities spawned and despawned and the components changed to turn this
+                /// world into the other world
+                pub fn diff(&self, #other: &Self) -> #delta_name {
+                    let #alignment = #core::delta::align_entities(&self.entities.read().unwrap(), &#other.entities.read().unwrap());
+                    let mut #delta = #delta_name {
+                        despawned: #alignment.despawned,
+                        spawned: #alignment.spawned,
+                        ..std::default::Default::default()
+                    };
+                    #diff_parents
+                    #(#delta.#field_identifiers = #core::delta::column_changes(&*self.#field_identifiers.read().unwrap(), &*#other.#field_identifiers.read().unwrap(), &#alignment.previous);)*
+                    #(#delta.#tag_identifiers = #core::delta::column_changes(&*self.#tag_identifiers.read().unwrap(), &*#other.#tag_identifiers.read().unwrap(), &#alignment.previous)
+                        .into_iter()
+                        .map(|v| (v.0, v.1.is_some()))
+                        .collect();)*
+                    #({
+                        let #component = #other.#ignore_identifiers.read().unwrap();
+                        if *self.#ignore_identifiers.read().unwrap() != *#component {
+                            #delta.#ignore_identifiers = Some(#component.clone());
+                        }
+                    })*
+                    #delta
+                }
+
+                /// The changes since an earlier snapshot of the world
+                pub fn delta_since(&self, snapshot: &Self) -> #delta_name {
+                    snapshot.diff(self)
+                }
+
+                /// Replay a delta onto the world, turning it into the state the delta was taken
+                /// against.
+                ///
+                /// Nothing is changed if the delta refers to entities the world doesn't have, would
+                /// give two entities the same value of a unique index or (with `hierarchy`) would
+                /// leave a cycle of parents.
+                pub fn apply_delta(&mut self, #delta: &#delta_name) -> std::result::Result<(), #core::error::WorldError> {
+                    let slots = self.entities.read().unwrap().len();
+                    let mut invalid_entity_ids: Vec<usize> = #delta.despawned.iter().copied().filter(|v| *v >= slots).collect();
+                    let mut despawned = #delta.despawned.clone();
+                    despawned.sort_unstable();
+                    despawned.dedup();
+                    let new_slots = (slots - despawned.len().min(slots)) + #delta.spawned.len();
+                    #(invalid_entity_ids.extend(#delta.#field_identifiers.iter().map(|v| v.0).filter(|v| *v >= new_slots));)*
+                    #(invalid_entity_ids.extend(#delta.#tag_identifiers.iter().map(|v| v.0).filter(|v| *v >= new_slots));)*
+                    #check_parents
+                    if !invalid_entity_ids.is_empty() {
+                        return Err(#core::error::WorldError::InvalidEntities(invalid_entity_ids));
+                    }
+                    #(self.#unique_index_identifiers.read().unwrap().check_changes(&despawned, #delta.#unique_identifiers.iter().map(|v| (v.0, v.1.as_ref())))?;)*
+
+                    // The delta lists the despawned descendants too, so they aren't removed again here
+                    for entity_id in despawned.into_iter().rev() {
+                        self.remove_entity_slot(entity_id);
+                    }
+                    let spawned = self.add_entities(#delta.spawned.len());
+                    {
+                        let mut entities_list = self.entities.write().unwrap();
+                        for (entity_id, value) in spawned.into_iter().zip(#delta.spawned.iter()) {
+                            entities_list[entity_id] = *value;
+                        }
+                    }
+
+                    // The entities given unique values leave the indexes first so they can swap values
+                    #({
+                        let mut #index = self.#unique_index_identifiers.write().unwrap();
+                        for (entity_id, _) in #delta.#unique_identifiers.iter() {
+                            #index.remove(*entity_id);
+                        }
+                    })*
+                    #(for (entity_id, #component) in #delta.#field_identifiers.iter() {
+                        match #component {
+                            Some(#component) => self.#setter_identifiers(*entity_id, #component.clone())#setter_tries,
+                            None => self.#clear_identifiers(*entity_id),
+                        }
+                    })*
+                    #(for (entity_id, tagged) in #delta.#tag_identifiers.iter() {
+                        if *tagged {
+                            self.#set_tag_identifiers(*entity_id);
+                        } else {
+                            self.#clear_tag_identifiers(*entity_id);
+                        }
+                    })*
+                    #(if let Some(#component) = &#delta.#ignore_identifiers {
+                        *self.#ignore_identifiers.write().unwrap() = #component.clone();
+                    })*
+                    #apply_parents
+                    Ok(())
+                }
+            },
+        )
+    } else {
+        (quote!{ }, quote!{ })
+    };
+
+    // Hashing needs every hashed component to implement `StateHash`, so worlds opt in with `hash`
+    let hash_world = if ignore_args.hash {
+        let hasher = Ident::new("hasher", Span::mixed_site());
+        let hash_parents = if ignore_args.hierarchy {
+            quote!{
+                #core::hash::StateHash::state_hash("parents", &mut #hasher);
+                #core::hash::StateHash::state_hash(self.hierarchy.read().unwrap().parents(), &mut #hasher);
+            }
+        } else {
+            quote!{ }
+        };
+        quote!{
+            /// A hash of the entities and every component of the world (except for fields marked
+            /// `#[skip_hash]`) in a stable order, which is the same across runs and platforms for
+            /// the same state of the world
+            pub fn state_hash(&self) -> u64 {
+                let mut #hasher = #core::hash::StateHasher::new();
+                #core::hash::StateHash::state_hash(&*self.entities.read().unwrap(), &mut #hasher);
+                #hash_parents
+                #(
+                    #core::hash::StateHash::state_hash(stringify!(#hashed_identifiers), &mut #hasher);
+                    #core::hash::hash_column(&*self.#hashed_identifiers.read().unwrap(), &mut #hasher);
+                )*
+                #(
+                    #core::hash::StateHash::state_hash(stringify!(#hashed_tag_identifiers), &mut #hasher);
+                    #core::hash::hash_column(&*self.#hashed_tag_identifiers.read().unwrap(), &mut #hasher);
+                )*
+                #(
+                    #core::hash::StateHash::state_hash(stringify!(#hashed_ignore_identifiers), &mut #hasher);
+                    #core::hash::StateHash::state_hash(&*self.#hashed_ignore_identifiers.read().unwrap(), &mut #hasher);
+                )*
+                std::hash::Hasher::finish(&#hasher)
+            }
+        }
+    } else {
+        quote!{ }
+    };
+
+    // Every world gets a registry of its components and access to them by name, parsing and
+    // formatting only the components whose types implement `FromStr` / `Debug`
+    let component_name_literals: Vec<LitStr> = field_identifiers.iter().map(|v| LitStr::new(&v.to_string(), Span::call_site())).collect();
+    let tag_name_literals: Vec<LitStr> = tag_identifiers.iter().map(|v| LitStr::new(&v.to_string(), Span::call_site())).collect();
+    let ignore_name_literals: Vec<LitStr> = ignore_identifiers.iter().map(|v| LitStr::new(&v.to_string(), Span::call_site())).collect();
+    let field_type_names: Vec<LitStr> = field_types.iter().map(|v| LitStr::new(&type_name(v), Span::call_site())).collect();
+    let ignore_type_names: Vec<LitStr> = ignore_types.iter().map(|v| LitStr::new(&type_name(v), Span::call_site())).collect();
+    let registry_world = {
+        let registry = quote!{ #core::registry };
+        let component = Ident::new("component", Span::mixed_site());
+        let parsed = Ident::new("parsed", Span::mixed_site());
+        let entries = Ident::new("entries", Span::mixed_site());
+        let bundle = Ident::new("bundle", Span::mixed_site());
+        let spawn_from_strs = spawned(quote!{ self.spawn_bundle(#bundle) }, quote!{ #core::error::RegistryError::from });
+        quote!{
+            /// Every component of the world with its type, per-entity components and tags in
+            /// signature bit order followed by the singular components
+            pub const REGISTRY: &'static [#registry::ComponentInfo] = &[
+                #(#registry::ComponentInfo {
+                    name: #component_name_literals,
+                    type_name: #field_type_names,
+                    kind: #registry::ComponentKind::Entity,
+                    type_id: std::any::TypeId::of::<#field_types>,
+                },)*
+                #(#registry::ComponentInfo {
+                    name: #tag_name_literals,
+                    type_name: "()",
+                    kind: #registry::ComponentKind::Tag,
+                    type_id: std::any::TypeId::of::<()>,
+                },)*
+                #(#registry::ComponentInfo {
+                    name: #ignore_name_literals,
+                    type_name: #ignore_type_names,
+                    kind: #registry::ComponentKind::Singular,
+                    type_id: std::any::TypeId::of::<#ignore_types>,
+                },)*
+            ];
+
+            /// The registry entry of a component
+            pub fn component_info(name: &str) -> Option<&'static #registry::ComponentInfo> {
+                Self::REGISTRY.iter().find(|v| v.name == name)
+            }
+
+            /// A per-entity component or tag of an entity by name, which keeps the component's
+            /// column locked for reading while it is held
+            pub fn get_dyn(&self, entity_id: usize, name: &str) -> Option<#registry::ComponentRef<'_>> {
+                match name {
+                    #(#component_name_literals => #registry::ComponentRef::column::<#field_types, _>(self.#field_identifiers.read().unwrap(), entity_id),)*
+                    #(#tag_name_literals => #registry::ComponentRef::column::<(), _>(self.#tag_identifiers.read().unwrap(), entity_id),)*
+                    _ => None,
+                }
+            }
+
+            /// A singular component by name, which keeps the component locked for reading while
+            /// it is held
+            pub fn get_singular_dyn(&self, name: &str) -> Option<#registry::ComponentRef<'_>> {
+                match name {
+                    #(#ignore_name_literals => #registry::ComponentRef::singular(self.#ignore_identifiers.read().unwrap()),)*
+                    _ => None,
+                }
+            }
+
+            /// The `Debug` output of a per-entity component or tag of an entity by name (or
+            /// `None` if the entity doesn't have it)
+            pub fn get_string(&self, entity_id: usize, name: &str) -> std::result::Result<Option<String>, #core::error::RegistryError> {
+                #[allow(unused_imports)]
+                use #registry::{DebugComponent as _, DebugComponentFallback as _};
+                match name {
+                    #(#component_name_literals => match #storage::get(&*self.#field_identifiers.read().unwrap(), entity_id) {
+                        Some(#component) => (&&#registry::Probe::<#field_types>::new())
+                            .debug_component(#component)
+                            .map(Some)
+                            .ok_or_else(|| #core::error::RegistryError::NotDebug(name.to_string())),
+                        None => Ok(None),
+                    },)*
+                    #(#tag_name_literals => Ok(self.#tag_identifiers.read().unwrap().contains(entity_id).then(|| String::from("()"))),)*
+                    _ => Err(#core::error::RegistryError::UnknownComponent(name.to_string())),
+                }
+            }
+
+            /// The `Debug` output of a singular component by name (or `None` if it isn't set)
+            pub fn get_singular_string(&self, name: &str) -> std::result::Result<Option<String>, #core::error::RegistryError> {
+                #[allow(unused_imports)]
+                use #registry::{DebugComponent as _, DebugComponentFallback as _};
+                match name {
+                    #(#ignore_name_literals => match &*self.#ignore_identifiers.read().unwrap() {
+                        Some(#component) => (&&#registry::Probe::<#ignore_types>::new())
+                            .debug_component(#component)
+                            .map(Some)
+                            .ok_or_else(|| #core::error::RegistryError::NotDebug(name.to_string())),
+                        None => Ok(None),
+                    },)*
+                    _ => Err(#core::error::RegistryError::UnknownComponent(name.to_string())),
+                }
+            }
+
+            /// Set a per-entity component of an entity by name from a string parsed with the
+            /// component's `FromStr` implementation (tags are set from `true` or `false`)
+            pub fn set_from_str(&mut self, entity_id: usize, name: &str, value: &str) -> std::result::Result<(), #core::error::RegistryError> {
+                #[allow(unused_imports)]
+                use #registry::{ParseComponent as _, ParseComponentFallback as _};
+                if Self::component_info(name).map_or(true, |v| v.kind == #registry::ComponentKind::Singular) {
+                    return Err(#core::error::RegistryError::UnknownComponent(name.to_string()));
+                }
+                if entity_id >= self.entities.read().unwrap().len() {
+                    return Err(#core::error::RegistryError::InvalidEntity(entity_id));
+                }
+
+                match name {
+                    #(#component_name_literals => {
+                        let #parsed = (&&#registry::Probe::<#field_types>::new())
+                            .parse_component(value)
+                            .ok_or_else(|| #core::error::RegistryError::NotFromStr(name.to_string()))?
+                            .map_err(|error| #core::error::RegistryError::Parse { component: name.to_string(), error })?;
+                        self.#setter_identifiers(entity_id, #parsed)#setter_tries;
+                        Ok(())
+                    },)*
+                    #(#tag_name_literals => {
+                        let #parsed: bool = value.parse()
+                            .map_err(|error: std::str::ParseBoolError| #core::error::RegistryError::Parse { component: name.to_string(), error: error.to_string() })?;
+                        if #parsed {
+                            self.#set_tag_identifiers(entity_id);
+                        } else {
+                            self.#clear_tag_identifiers(entity_id);
+                        }
+                        Ok(())
+                    },)*
+                    _ => unreachable!(),
+                }
+            }
+
+            /// Set a singular component by name from a string parsed with the component's
+            /// `FromStr` implementation
+            pub fn set_singular_from_str(&mut self, name: &str, value: &str) -> std::result::Result<(), #core::error::RegistryError> {
+                #[allow(unused_imports)]
+                use #registry::{ParseComponent as _, ParseComponentFallback as _};
+                match name {
+                    #(#ignore_name_literals => {
+                        let #parsed = (&&#registry::Probe::<#ignore_types>::new())
+                            .parse_component(value)
+                            .ok_or_else(|| #core::error::RegistryError::NotFromStr(name.to_string()))?
+                            .map_err(|error| #core::error::RegistryError::Parse { component: name.to_string(), error })?;
+                        self.#set_ignore_identifiers(#parsed);
+                        Ok(())
+                    },)*
+                    _ => Err(#core::error::RegistryError::UnknownComponent(name.to_string())),
+                }
+            }
+
+            /// Spawn an entity with per-entity components and tags set by name from strings
+            /// parsed like `set_from_str`, returning its id. Every value is parsed before the
+            /// entity is spawned, so nothing is spawned if any of them is invalid.
+            pub fn spawn_from_strs(&mut self, components: &[(&str, &str)]) -> std::result::Result<usize, #core::error::RegistryError> {
+                #[allow(unused_imports)]
+                use #registry::{ParseComponent as _, ParseComponentFallback as _};
+                let mut #bundle = #bundle_name::default();
+                for (name, value) in components.iter() {
+                    match *name {
+                        #(#component_name_literals => {
+                            #bundle.#field_identifiers = Some((&&#registry::Probe::<#field_types>::new())
+                                .parse_component(value)
+                                .ok_or_else(|| #core::error::RegistryError::NotFromStr(name.to_string()))?
+                                .map_err(|error| #core::error::RegistryError::Parse { component: name.to_string(), error })?);
+                        },)*
+                        #(#tag_name_literals => {
+                            #bundle.#tag_identifiers = value.parse()
+                                .map_err(|error: std::str::ParseBoolError| #core::error::RegistryError::Parse { component: name.to_string(), error: error.to_string() })?;
+                        },)*
+                        _ => return Err(#core::error::RegistryError::UnknownComponent(name.to_string())),
+                    }
+                }
+                #spawn_from_strs
+            }
+
+            /// Every component and tag the entity has with its `Debug` output (or its type for
+            /// components that don't implement `Debug`), or `None` if the entity doesn't exist
+            pub fn describe_entity(&self, entity_id: usize) -> Option<String> {
+                #[allow(unused_imports)]
+                use #registry::{DebugComponent as _, DebugComponentFallback as _};
+                if entity_id >= self.entities.read().unwrap().len() {
+                    return None;
+                }
+
+                #[allow(unused_mut)]
+                let mut #entries = Vec::new();
+                #(if let Some(#component) = #storage::get(&*self.#field_identifiers.read().unwrap(), entity_id) {
+                    #entries.push((
+                        #component_name_literals,
+                        (&&#registry::Probe::<#field_types>::new())
+                            .debug_component(#component)
+                            .unwrap_or_else(|| format!("<{}>", #field_type_names)),
+                    ));
+                })*
+                #(if self.#tag_identifiers.read().unwrap().contains(entity_id) {
+                    #entries.push((#tag_name_literals, String::from("()")));
+                })*
+                Some(format!("Entity {} {:#?}", entity_id, #registry::DebugEntries(#entries)))
+            }
+        }
+    };
+
+    // Printing a world shows how many entities have each component rather than every component,
+    // and tools like the inspector reach its components by name through `Reflect`
+    let debug_world = {
+        let registry = quote!{ #core::registry };
+        let component = Ident::new("component", Span::mixed_site());
+        quote!{
+            impl std::fmt::Debug for #item_name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    #[allow(unused_imports)]
+                    use #registry::{DebugComponent as _, DebugComponentFallback as _};
+                    f.debug_struct(stringify!(#item_name))
+                        .field("entities", &self.entities.read().unwrap().len())
+                        .field("components", &#registry::DebugEntries(vec![
+                            #((#component_name_literals, #storage::count(&*self.#field_identifiers.read().unwrap()).to_string()),)*
+                        ]))
+                        .field("tags", &#registry::DebugEntries(vec![
+                            #((#tag_name_literals, #storage::count(&*self.#tag_identifiers.read().unwrap()).to_string()),)*
+                        ]))
+                        .field("singulars", &#registry::DebugEntries(vec![
+                            #((#ignore_name_literals, match &*self.#ignore_identifiers.read().unwrap() {
+                                Some(#component) => format!(
+                                    "Some({})",
+                                    (&&#registry::Probe::<#ignore_types>::new())
+                                        .debug_component(#component)
+                                        .unwrap_or_else(|| format!("<{}>", #ignore_type_names)),
+                                ),
+                                None => String::from("None"),
+                            }),)*
+                        ]))
+                        .finish()
+                }
+            }
+
+            impl #registry::Reflect for #item_name {
+                fn registry() -> &'static [#registry::ComponentInfo] {
+                    Self::REGISTRY
+                }
+
+                fn entity_count(&self) -> usize {
+                    self.entities.read().unwrap().len()
+                }
+
+                fn add_entity(&mut self) -> usize {
+                    #item_name::add_entity(self)
+                }
+
+                fn has_component(&self, entity_id: usize, name: &str) -> bool {
+                    self.get_dyn(entity_id, name).is_some()
+                }
+
+                fn get_string(&self, entity_id: usize, name: &str) -> std::result::Result<Option<String>, #core::error::RegistryError> {
+                    #item_name::get_string(self, entity_id, name)
+                }
+
+                fn get_singular_string(&self, name: &str) -> std::result::Result<Option<String>, #core::error::RegistryError> {
+                    #item_name::get_singular_string(self, name)
+                }
+
+                fn set_from_str(&mut self, entity_id: usize, name: &str, value: &str) -> std::result::Result<(), #core::error::RegistryError> {
+                    #item_name::set_from_str(self, entity_id, name, value)
+                }
+
+                fn set_singular_from_str(&mut self, name: &str, value: &str) -> std::result::Result<(), #core::error::RegistryError> {
+                    #item_name::set_singular_from_str(self, name, value)
+                }
+
+                fn spawn_from_strs(&mut self, components: &[(&str, &str)]) -> std::result::Result<usize, #core::error::RegistryError> {
+                    #item_name::spawn_from_strs(self, components)
+                }
+            }
+        }
+    };
+
+    let builder_name = format_ident!("{}EntityBuilder", item_name);
+
     TokenStream::from(quote!{
         pub struct #item_name {
             entities: std::sync::Arc<std::sync::RwLock<std::vec::Vec<usize>>>,
+            /// Which components each entity has, kept in sync by the world's methods
+            pub signatures: std::sync::Arc<std::sync::RwLock<#core::storage::Signatures>>,
+            /// The last error updating the indexes after systems wrote components, until taken
+            pub index_error: std::sync::Arc<std::sync::RwLock<std::option::Option<#core::error::WorldError>>>,
+            #hierarchy_field
+            #physics_field
             #entity_fields
+            #(
+                #[doc = #index_docs]
+                pub #index_identifiers: std::sync::Arc<std::sync::RwLock<#index_types>>,
+            )*
+            #tag_fields
             #global_fields
         }
 
+        /// A set of optional components that can be spawned into the world as a single entity
+        #[derive(Default)]
+        pub struct #bundle_name {
+            #(pub #field_identifiers: std::option::Option<#field_types>,)*
+            #(pub #tag_identifiers: bool,)*
+        }
+
+        impl #bundle_name {
+            #(pub fn #with_identifiers(mut self, #field_identifiers: #field_types) -> Self {
+                self.#field_identifiers = Some(#field_identifiers);
+                self
+            })*
+
+            #(pub fn #with_tag_identifiers(mut self) -> Self {
+                self.#tag_identifiers = true;
+                self
+            })*
+        }
+
+        /// Builder for an entity that is inserted into the world when built
+        pub struct #builder_name<'a> {
+            world: &'a mut #item_name,
+            bundle: #bundle_name,
+        }
+
+        impl<'a> #builder_name<'a> {
+            #(pub fn #with_identifiers(mut self, #field_identifiers: #field_types) -> Self {
+                self.bundle.#field_identifiers = Some(#field_identifiers);
+                self
+            })*
+
+            #(pub fn #with_tag_identifiers(mut self) -> Self {
+                self.bundle.#tag_identifiers = true;
+                self
+            })*
+
+            /// Insert the entity into the world, returning its id
+            pub fn build(self) -> #spawn_result {
+                self.world.spawn_bundle(self.bundle)
+            }
+        }
+
         impl #item_name {
+            /// Names of the per-entity components and tags, in signature bit order
+            pub const COMPONENTS: &'static [&'static str] = &[#(#component_names),*];
+
+            /// Names of the per-entity components with an index or a spatial grid
+            pub const INDEXED: &'static [&'static str] = &[#(#indexed_names),*];
+
+            /// The signature bit of a per-entity component or tag (panics for unknown components
+            /// so systems using it in constants fail to compile)
+            pub const fn component_bit(name: &str) -> u32 {
+                match Self::name_position(Self::COMPONENTS, name) {
+                    Some(component) => component as u32,
+                    None => panic!(#component_bit_error),
+                }
+            }
+
+            /// Whether the component has an index or a spatial grid to update after systems
+            /// write it
+            pub const fn is_indexed(name: &str) -> bool {
+                Self::name_position(Self::INDEXED, name).is_some()
+            }
+
+            /// The position of the name in the names
+            const fn name_position(names: &[&str], name: &str) -> Option<usize> {
+                let name = name.as_bytes();
+                let mut position = 0;
+                while position < names.len() {
+                    let candidate = names[position].as_bytes();
+                    if candidate.len() == name.len() {
+                        let mut index = 0;
+                        while index < name.len() && candidate[index] == name[index] {
+                            index += 1;
+                        }
+                        if index == name.len() {
+                            return Some(position);
+                        }
+                    }
+                    position += 1;
+                }
+                None
+            }
+
             pub fn new() -> std::sync::Arc<std::sync::RwLock<Self>> {
                 std::sync::Arc::new(std::sync::RwLock::new(Self {
                     entities: std::sync::Arc::new(std::sync::RwLock::new(std::vec::Vec::new())),
+                    signatures: std::sync::Arc::new(std::sync::RwLock::new(#core::storage::Signatures::#signatures_constructor(#component_count))),
+                    index_error: std::sync::Arc::new(std::sync::RwLock::new(None)),
+                    #hierarchy_initializer
+                    #physics_initializer
                     #entity_initializers
+                    #(#index_identifiers: std::sync::Arc::new(std::sync::RwLock::new(#index_constructors)),)*
+                    #tag_initializers
                     #global_initializers
                 }))
             }
@@ -132,7 +1642,12 @@ pub fn world(attr: TokenStream, item: TokenStream) -> TokenStream {
             pub fn add_entity(&mut self) -> usize {
                 let entity_id = self.entities.read().unwrap().len() as usize;
                 self.entities.write().unwrap().push(entity_id);
-                #(self.#field_identifiers.write().unwrap().push(None));*;
+                #(#storage::push_slot(&mut *self.#field_identifiers.write().unwrap(), None));*;
+                #(#storage::push_slot(&mut *self.#tag_identifiers.write().unwrap(), None));*;
+                self.signatures.write().unwrap().push_entity(0);
+                #(self.#index_identifiers.write().unwrap().push_slot(None::<&#indexed_types>);)*
+                #hierarchy_lock
+                #push_hierarchy_slot
                 entity_id
             }
 
@@ -140,47 +1655,302 @@ pub fn world(attr: TokenStream, item: TokenStream) -> TokenStream {
                 let mut new_entity_ids = Vec::with_capacity(entities as usize);
                 let mut entities_list = self.entities.write().unwrap();
                 #(let mut #field_identifiers = self.#field_identifiers.write().unwrap());*;
+                #(let mut #tag_identifiers = self.#tag_identifiers.write().unwrap());*;
+                let mut signatures = self.signatures.write().unwrap();
+                #(let mut #index_identifiers = self.#index_identifiers.write().unwrap();)*
+                #hierarchy_lock
                 let start_len = entities_list.len();
+                #(#storage::reserve_slots(&mut *#field_identifiers, entities));*;
+                #(#storage::reserve_slots(&mut *#tag_identifiers, entities));*;
+                #(#index_identifiers.reserve_slots(entities);)*
+                signatures.reserve(entities);
 
                 for i in 0..entities {
                     let new_entity_id = start_len + i;
                     entities_list.push(new_entity_id);
-                     #(#field_identifiers.push(None));*;
+                     #(#storage::push_slot(&mut *#field_identifiers, None));*;
+                     #(#storage::push_slot(&mut *#tag_identifiers, None));*;
+                     signatures.push_entity(0);
+                     #(#index_identifiers.push_slot(None::<&#indexed_types>);)*
+                     #push_hierarchy_slot
                      new_entity_ids.push(new_entity_id);
                 }
 
                 new_entity_ids
             }
 
+            /// Start building an entity whose components are inserted all at once
+            pub fn spawn(&mut self) -> #builder_name<'_> {
+                #builder_name {
+                    world: self,
+                    bundle: #bundle_name::default(),
+                }
+            }
+
+            /// Add an entity with every component in the bundle under a single set of locks.
+            ///
+            /// In worlds with unique indexes nothing is added if another entity has one of the
+            /// bundle's unique values, the entities with it are returned in the error.
+            pub fn spawn_bundle(&mut self, bundle: #bundle_name) -> #spawn_result {
+                let mut entities_list = self.entities.write().unwrap();
+                #(let mut #field_identifiers = self.#field_identifiers.write().unwrap());*;
+                #(let mut #tag_identifiers = self.#tag_identifiers.write().unwrap());*;
+                let mut signatures = self.signatures.write().unwrap();
+                #(let mut #index_identifiers = self.#index_identifiers.write().unwrap();)*
+                #hierarchy_lock
+
+                let entity_id = entities_list.len();
+                #(if let Some(component) = bundle.#unique_identifiers.as_ref() {
+                    #unique_index_identifiers.check(entity_id, component)?;
+                })*
+                #(#index_identifiers.push_slot(bundle.#indexed_identifiers.as_ref());)*
+                entities_list.push(entity_id);
+                let mut signature: #core::storage::Signature = 0;
+                #(if bundle.#field_identifiers.is_some() {
+                    signature |= 1 << #component_bits;
+                })*
+                #(if bundle.#tag_identifiers {
+                    signature |= 1 << #tag_bits;
+                })*
+                signatures.push_entity(signature);
+                #push_hierarchy_slot
+                #spawn_archetype
+                #(#push_component_slots)*
+                #(#storage::push_slot(&mut *#tag_identifiers, bundle.#tag_identifiers.then_some(())));*;
+                #spawn_ok
+            }
+
+            /// Add an entity for every bundle, taking each lock once and returning the new entity ids.
+            ///
+            /// In worlds with unique indexes nothing is added if two of the entities would have
+            /// the same unique value.
+            pub fn spawn_batch(&mut self, bundles: impl std::iter::IntoIterator<Item = #bundle_name>) -> #batch_result {
+                #batch_bundles
+
+                let mut entities_list = self.entities.write().unwrap();
+                #(let mut #field_identifiers = self.#field_identifiers.write().unwrap());*;
+                #(let mut #tag_identifiers = self.#tag_identifiers.write().unwrap());*;
+                let mut signatures = self.signatures.write().unwrap();
+                #(let mut #index_identifiers = self.#index_identifiers.write().unwrap();)*
+                #hierarchy_lock
+                #batch_unique_checks
+                let mut new_entity_ids = Vec::with_capacity(additional);
+                entities_list.reserve(additional);
+                #(#index_identifiers.reserve_slots(additional);)*
+                #(#storage::reserve_slots(&mut *#field_identifiers, additional));*;
+                #(#storage::reserve_slots(&mut *#tag_identifiers, additional));*;
+                signatures.reserve(additional);
+
+                for bundle in bundles {
+                    let entity_id = entities_list.len();
+                    #(#index_identifiers.push_slot(bundle.#indexed_identifiers.as_ref());)*
+                    entities_list.push(entity_id);
+                    let mut signature: #core::storage::Signature = 0;
+                    #(if bundle.#field_identifiers.is_some() {
+                        signature |= 1 << #component_bits;
+                    })*
+                    #(if bundle.#tag_identifiers {
+                        signature |= 1 << #tag_bits;
+                    })*
+                    signatures.push_entity(signature);
+                    #push_hierarchy_slot
+                    #spawn_archetype
+                    #(#push_component_slots)*
+                    #(#storage::push_slot(&mut *#tag_identifiers, bundle.#tag_identifiers.then_some(())));*;
+                    new_entity_ids.push(entity_id);
+                }
+
+                #batch_ok
+            }
+
             pub fn remove_entity(&mut self, entity_id: usize) {
-                self.entities.write().unwrap().remove(entity_id as usize);
-                #(self.#field_identifiers.write().unwrap().remove(entity_id as usize));*;
+                #remove_descendants
             }
 
             pub fn remove_entities(&mut self, entity_ids: Vec<usize>) {
                 for entity_id in entity_ids {
-                    self.entities.write().unwrap().remove(entity_id as usize);
-                    #(self.#field_identifiers.write().unwrap().remove(entity_id as usize));*;
+                    self.remove_entity(entity_id);
+                }
+            }
+
+            /// Remove only the entity, shifting the ids of every later entity down by one
+            fn remove_entity_slot(&mut self, entity_id: usize) {
+                self.entities.write().unwrap().remove(entity_id as usize);
+                // The signatures go first so archetype columns can follow the archetypes
+                let mut signatures = self.signatures.write().unwrap();
+                signatures.remove_entity(entity_id);
+                #(#remove_slots)*
+                #(#storage::remove_slot(&mut *self.#tag_identifiers.write().unwrap(), entity_id));*;
+                drop(signatures);
+                #(self.#index_identifiers.write().unwrap().remove_slot(entity_id);)*
+                #remove_hierarchy_slot
+                #remove_physics_slot
+            }
+
+            #hierarchy_world
+
+            #transform_world
+
+            #physics_world
+
+            /// Update the index and spatial grid of a component after its column was written
+            /// directly (components without either are ignored).
+            ///
+            /// A value of a unique index that another entity has is still indexed, the entities
+            /// with it are returned in the error.
+            pub fn reindex(&self, component: &str) -> std::result::Result<(), #core::error::WorldError> {
+                let slots = self.entities.read().unwrap().len();
+                self.reindex_entities(component, 0..slots)
+            }
+
+            /// Update the index and spatial grid of a component for the given entities after
+            /// their components were written directly, as systems do after writing components.
+            ///
+            /// The component's column is locked before its index and grid, the order every method
+            /// and system takes them in, so callers must not hold either while waiting on the column.
+            #[allow(unused_variables)]
+            pub fn reindex_entities(&self, component: &str, #entity_ids: impl std::iter::IntoIterator<Item = usize> + Clone) -> std::result::Result<(), #core::error::WorldError> {
+                match component {
+                    #(#reindex_arms)*
+                    _ => Ok(()),
                 }
             }
 
-            #(pub fn #setter_identifiers(&mut self, entity_id: usize, #field_identifiers: #field_types) {
-                self.#field_identifiers.write().unwrap()[entity_id as usize] = Some(#field_identifiers);
+            /// Take the last error updating the indexes after systems wrote components (the
+            /// entities a system gave the same unique value, which are still indexed)
+            pub fn take_index_error(&self) -> std::option::Option<#core::error::WorldError> {
+                self.index_error.write().unwrap().take()
+            }
+
+            #(#lookup_functions)*
+
+            #relocate_entity
+
+            #save_world
+
+            #diff_world
+
+            #hash_world
+
+            #registry_world
+
+            #(pub fn #setter_identifiers(&mut self, entity_id: usize, #field_identifiers: #field_types) #setter_results {
+                let mut #column = self.#field_identifiers.write().unwrap();
+                let mut signatures = self.signatures.write().unwrap();
+                #index_locks
+                #index_checks
+                #index_inserts
+                #insert_component_bodies
+                #setter_oks
+            })*
+
+            #(pub fn #set_tag_identifiers(&mut self, entity_id: usize) {
+                let mut #column = self.#tag_identifiers.write().unwrap();
+                let mut signatures = self.signatures.write().unwrap();
+                #insert_tag_bodies
             })*
 
+            #(pub fn #clear_tag_identifiers(&mut self, entity_id: usize) {
+                let mut #column = self.#tag_identifiers.write().unwrap();
+                let mut signatures = self.signatures.write().unwrap();
+                #remove_tag_bodies
+            })*
+
+            #(pub fn #has_tag_identifiers(&self, entity_id: usize) -> bool {
+                self.#tag_identifiers.read().unwrap().contains(entity_id)
+            })*
+
+            #(
+                /// Tag every entity, taking the tag lock once.
+                ///
+                /// Valid entities are tagged even if others are invalid, the invalid entity ids
+                /// are returned in the error.
+                pub fn #insert_tag_batch_identifiers(&mut self, entity_ids: impl std::iter::IntoIterator<Item = usize>) -> std::result::Result<(), #core::error::WorldError> {
+                    let mut #column = self.#tag_identifiers.write().unwrap();
+                    let mut signatures = self.signatures.write().unwrap();
+                    let mut invalid_entity_ids = Vec::new();
+                    for entity_id in entity_ids {
+                        if entity_id < #storage::slots(&*#column) {
+                            #insert_tag_bodies
+                        } else {
+                            invalid_entity_ids.push(entity_id);
+                        }
+                    }
+
+                    if invalid_entity_ids.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(#core::error::WorldError::InvalidEntities(invalid_entity_ids))
+                    }
+                }
+            )*
+
             #(pub fn #set_ignore_identifiers(&mut self, #ignore_identifiers: #ignore_types) {
                 *self.#ignore_identifiers.write().unwrap() = Some(#ignore_identifiers);
             })*
 
-            #(pub fn #set_many_identifiers(&mut self, entity_ids: &Vec<usize>, mut #plural_identifiers: Vec<#field_types>) {
-                let mut component = self.#field_identifiers.write().unwrap();
-                for (#field_identifiers, entity_id) in #plural_identifiers.drain(..).zip(entity_ids.iter()) {
-                    component[*entity_id] = Some(#field_identifiers);
+            #(
+                /// Insert every (entity, component) pair, taking the component lock once.
+                ///
+                /// Pairs with valid entities are inserted even if others are invalid, the invalid
+                /// entity ids are returned in the error. Nothing is inserted if the pairs would
+                /// give two entities the same value of a unique index.
+                pub fn #insert_batch_identifiers(&mut self, #plural_identifiers: impl std::iter::IntoIterator<Item = (usize, #field_types)>) -> std::result::Result<(), #core::error::WorldError> {
+                    let mut #column = self.#field_identifiers.write().unwrap();
+                    #batch_checks
+                    let mut signatures = self.signatures.write().unwrap();
+                    #index_locks
+                    let mut invalid_entity_ids = Vec::new();
+                    for (entity_id, #field_identifiers) in #plural_identifiers {
+                        if entity_id < #storage::slots(&*#column) {
+                            #index_inserts
+                            #insert_component_bodies
+                        } else {
+                            invalid_entity_ids.push(entity_id);
+                        }
+                    }
+
+                    if invalid_entity_ids.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(#core::error::WorldError::InvalidEntities(invalid_entity_ids))
+                    }
                 }
-            })*
+            )*
+
+            #(
+                /// Set the component of every entity to the value at the same position.
+                ///
+                /// Nothing is set if an entity id is invalid (the invalid ids are returned in the
+                /// error) or if there isn't exactly one value per entity (every id is returned).
+                #[deprecated(note = #set_many_deprecations)]
+                pub fn #set_many_identifiers(&mut self, entity_ids: &Vec<usize>, mut #plural_identifiers: Vec<#field_types>) -> std::result::Result<(), #core::error::WorldError> {
+                    if entity_ids.len() != #plural_identifiers.len() {
+                        return Err(#core::error::WorldError::InvalidEntities(entity_ids.clone()));
+                    }
+                    let slots = self.entities.read().unwrap().len();
+                    let invalid_entity_ids: Vec<usize> = entity_ids.iter().copied().filter(|v| *v >= slots).collect();
+                    if !invalid_entity_ids.is_empty() {
+                        return Err(#core::error::WorldError::InvalidEntities(invalid_entity_ids));
+                    }
+                    #many_checks
+                    let mut #column = self.#field_identifiers.write().unwrap();
+                    let mut signatures = self.signatures.write().unwrap();
+                    #index_locks
+                    for (#field_identifiers, entity_id) in #plural_identifiers.drain(..).zip(entity_ids.iter().copied()) {
+                        #index_inserts
+                        #insert_component_bodies
+                    }
+                    Ok(())
+                }
+            )*
 
             #(pub fn #clear_identifiers(&mut self, entity_id: usize) {
-                self.#field_identifiers.write().unwrap()[entity_id as usize] = None;
+                let mut #column = self.#field_identifiers.write().unwrap();
+                let mut signatures = self.signatures.write().unwrap();
+                #index_locks
+                #index_removes
+                #remove_component_bodies
             })*
 
             #(pub fn #clear_ignore_identifiers(&mut self) {
@@ -188,13 +1958,20 @@ pub fn world(attr: TokenStream, item: TokenStream) -> TokenStream {
             })*
 
             #(pub fn #clear_many_identifiers(&mut self, entity_ids: &Vec<usize>) {
-                let mut component = self.#field_identifiers.write().unwrap();
-                for entity_id in entity_ids {
-                    component[*entity_id as usize] = None;
+                let mut #column = self.#field_identifiers.write().unwrap();
+                let mut signatures = self.signatures.write().unwrap();
+                #index_locks
+                for entity_id in entity_ids.iter().copied() {
+                    #index_removes
+                    #remove_component_bodies
                 }
             })*
         }
 
+        #delta_struct
+
+        #debug_world
+
         unsafe impl Send for #item_name {}
         unsafe impl Sync for #item_name {}
     })
@@ -231,8 +2008,14 @@ struct FunctionArgs {
     write_components: Vec<Ident>,
     global_write_components: Vec<Ident>,
     global_write_assignments: HashMap<Ident, Expr>,
+    with_components: Vec<Ident>,
+    without_components: Vec<Ident>,
     filters: Vec<ExprBinary>,
     enumerated: bool,
+    parallel: bool,
+    chunk_size: Option<Expr>,
+    reduce_components: Vec<Ident>,
+    reduce_combiners: HashMap<Ident, Expr>,
 }
 
 impl Parse for FunctionArgs {
@@ -243,8 +2026,14 @@ impl Parse for FunctionArgs {
         let mut write_components: Vec<Ident> = Vec::new();
         let mut global_write_components: Vec<Ident> = Vec::new();
         let mut global_write_assignments: HashMap<Ident, Expr> = HashMap::new();
+        let mut with_components: Vec<Ident> = Vec::new();
+        let mut without_components: Vec<Ident> = Vec::new();
         let mut filters: Vec<ExprBinary> = Vec::new();
         let mut enumerated: bool = false;
+        let mut parallel: bool = false;
+        let mut chunk_size: Option<Expr> = None;
+        let mut reduce_components: Vec<Ident> = Vec::new();
+        let mut reduce_combiners: HashMap<Ident, Expr> = HashMap::new();
 
         let parts = input.parse_terminated(Expr::parse, Token![,])?;
         for part in parts.iter() {
@@ -300,6 +2089,44 @@ impl Parse for FunctionArgs {
                                         _ => (),
                                     }
                                 },
+                                "with" => {
+                                    match assignment.right.as_ref() {
+                                        Expr::Path(path) => {
+                                            if let Some(segment) = path.path.segments.first() {
+                                                with_components.push(segment.ident.clone());
+                                            }
+                                        },
+                                        Expr::Array(array) => {
+                                            for element in array.elems.iter() {
+                                                if let Expr::Path(path) = element {
+                                                    if let Some(segment) = path.path.segments.first() {
+                                                        with_components.push(segment.ident.clone());
+                                                    }
+                                                }
+                                            }
+                                        },
+                                        _ => (),
+                                    }
+                                },
+                                "without" => {
+                                    match assignment.right.as_ref() {
+                                        Expr::Path(path) => {
+                                            if let Some(segment) = path.path.segments.first() {
+                                                without_components.push(segment.ident.clone());
+                                            }
+                                        },
+                                        Expr::Array(array) => {
+                                            for element in array.elems.iter() {
+                                                if let Expr::Path(path) = element {
+                                                    if let Some(segment) = path.path.segments.first() {
+                                                        without_components.push(segment.ident.clone());
+                                                    }
+                                                }
+                                            }
+                                        },
+                                        _ => (),
+                                    }
+                                },
                                 "filter" => {
                                     if let Expr::Array(array) = assignment.right.as_ref() {
                                         for element in array.elems.iter() {
@@ -359,12 +2186,43 @@ impl Parse for FunctionArgs {
                                     }
                                 },
                                 "enumerate" => enumerated = true,
+                                "parallel" => parallel = true,
+                                "par_chunk" => {
+                                    parallel = true;
+                                    chunk_size = Some(*assignment.right.clone());
+                                },
+                                "reduce" => {
+                                    let elements: Vec<&Expr> = match assignment.right.as_ref() {
+                                        Expr::Array(array) => array.elems.iter().collect(),
+                                        element => vec![element],
+                                    };
+                                    for element in elements {
+                                        match element {
+                                            Expr::Path(path) => {
+                                                if let Some(segment) = path.path.segments.first() {
+                                                    reduce_components.push(segment.ident.clone());
+                                                }
+                                            },
+                                            Expr::Assign(assignment) => {
+                                                if let Expr::Path(path) = assignment.left.as_ref() {
+                                                    if let Some(segment) = path.path.segments.first() {
+                                                        reduce_components.push(segment.ident.clone());
+                                                        reduce_combiners.insert(segment.ident.clone(), *assignment.right.clone());
+                                                    }
+                                                }
+                                            },
+                                            _ => (),
+                                        }
+                                    }
+                                },
                                 _ => (),
                             }
                         }
                     },
                     _ => return Err(Error::new(Span::call_site(), "Invalid Parameter to system macro")),
                 }
+            } else if matches!(part, Expr::Path(path) if path.path.is_ident("parallel")) {
+                parallel = true;
             } else {
                 return Err(Error::new(Span::call_site(), "Expected Assignments in Attribute"));
             }
@@ -374,6 +2232,14 @@ impl Parse for FunctionArgs {
             return Err(Error::new(Span::call_site(), "World Type was not Provided"));
         }
 
+        if parallel && !global_write_components.is_empty() {
+            return Err(Error::new(Span::call_site(), "Parallel Systems can't Write Singular Components, use `reduce` instead"));
+        }
+
+        if !parallel && !reduce_components.is_empty() {
+            return Err(Error::new(Span::call_site(), "Only Parallel Systems can `reduce` into Singular Components"));
+        }
+
         Ok(FunctionArgs {
             world_type: world_type.unwrap(),
             read_components,
@@ -381,8 +2247,14 @@ impl Parse for FunctionArgs {
             write_components,
             global_write_components,
             global_write_assignments,
+            with_components,
+            without_components,
             filters,
             enumerated,
+            parallel,
+            chunk_size,
+            reduce_components,
+            reduce_combiners,
         })
     }
 }
@@ -400,111 +2272,188 @@ pub fn system(attr: TokenStream, item: TokenStream) -> TokenStream {
     let global_read_components = function_args.global_read_components;
     let global_read_refs: Vec<Ident> = global_read_components.iter().map(|v| format_ident!("{}_ref", v)).collect();
     let write_components = function_args.write_components;
+    let with_components = function_args.with_components;
+    let without_components = function_args.without_components;
     let global_write_components = function_args.global_write_components;
     let global_write_refs: Vec<Ident> = global_write_components.iter().map(|v| format_ident!("{}_ref", v)).collect();
     let world_type = function_args.world_type;
 
-    let (mut items, mut iterators) = match read_components.len() {
-        0 => (quote!{ }, quote!{ }),
-        1 => {
-            let read_component = &read_components[0];
-            (
-                quote!{ (entity_id, #read_component) },
-                quote!{#read_component.iter().enumerate()}
-            )
-        },
-        _ => {
-            let read_component1 = &read_components[0];
-            let read_component2 = &read_components[1];
-            (
-                quote!{ ((entity_id, #read_component1), #read_component2) },
-                quote!{ #read_component1.iter().enumerate().zip(#read_component2.iter())}
-            )
-        },
-    };
+    let core = core_path();
+    let storage = quote!{ #core::storage::ComponentStorage };
 
-    if read_components.len() > 2 {
-        for read_component in read_components[2..].iter() {
-            items = quote!{ (#items, #read_component) };
-            iterators = quote!{#iterators.zip(#read_component.iter())};
+    let mut filter_condition = quote!{ };
+    if function_args.filters.len() > 0 {
+        let first_filter = &function_args.filters[0];
+        filter_condition = quote!{ if #first_filter };
+        for filter in function_args.filters[1..].iter() {
+            filter_condition = quote!{ #filter_condition && #filter };
         }
     }
 
-    if read_components.len() == 0 {
-        (items, iterators) = match write_components.len() {
-            0 => (quote!{ }, quote!{ }),
-            1 => {
-                let write_component = &write_components[0];
-                (
-                    quote!{ (entity_id, #write_component) },
-                    quote!{#write_component.iter_mut().enumerate()}
-                )
-            },
-            _ => {
-                let write_component1 = &write_components[0];
-                let write_component2 = &write_components[1];
-                (
-                    quote!{ ((entity_id, #write_component1), #write_component2) },
-                    quote!{#write_component1.iter_mut().enumerate().zip(#write_component2.iter_mut())}
-                )
-            },
-        };
-        if write_components.len() > 2 {
-            for write_component in write_components[2..].iter() {
-                items = quote!{ (#items, #write_component) };
-                iterators = quote!{#iterators.zip(#write_component.iter_mut())};
-            }
+    let entity_body = quote!{
+        #(let Some(#read_components) = #storage::get(&*#read_components, entity_id) else { continue; });*;
+        #(let Some(mut #write_components) = #storage::get_mut(&mut *#write_components, entity_id) else { continue; });*;
+
+        #filter_condition {
+            #body
         }
+    };
+
+    // Entities are found through the world's signatures, which check 64 entities at a time starting
+    // with the smallest participating component, so words without a match are skipped early
+    let signatures = Ident::new("signatures", Span::mixed_site());
+    let required = Ident::new("required", Span::mixed_site());
+    let excluded = Ident::new("excluded", Span::mixed_site());
+    let required_names: Vec<String> = read_components.iter().chain(write_components.iter()).chain(with_components.iter()).map(|v| v.to_string()).collect();
+    let excluded_names: Vec<String> = without_components.iter().map(|v| v.to_string()).collect();
+
+    let iterates_entities = !required_names.is_empty() || !excluded_names.is_empty() || function_args.enumerated;
+    // Systems writing indexed components keep the ids of the entities they visit, so only those
+    // are re-indexed afterwards
+    let reindex = Ident::new("reindex", Span::mixed_site());
+    let tracked = Ident::new("tracked", Span::mixed_site());
+    let track_written = |entity_ids: proc_macro2::TokenStream| if write_components.is_empty() {
+        quote!{ }
     } else {
-        for write_component in write_components.iter() {
-            items = quote!{ (#items, #write_component) };
-            iterators = quote!{#iterators.zip(#write_component.iter_mut())};
+        quote!{
+            if #tracked {
+                #reindex.written.extend(#entity_ids);
+            }
         }
-    }
+    };
+    let body = if iterates_entities && function_args.parallel {
+        // Parallel systems split the matching entities into chunks spread across the workers.
+        // Every entity is in exactly one chunk, so each worker only ever touches the components
+        // of its own entities, and each chunk reduces into its own copy of the reduced singulars
+        let entity_ids = Ident::new("entity_ids", Span::mixed_site());
+        let chunk = Ident::new("chunk", Span::mixed_site());
+        let results = Ident::new("results", Span::mixed_site());
+        let write_shared: Vec<Ident> = write_components.iter().map(|v| Ident::new(&format!("{}_shared", v), Span::mixed_site())).collect();
+        let reduce_components = &function_args.reduce_components;
+        let reduce_guards: Vec<Ident> = reduce_components.iter().map(|v| Ident::new(&format!("{}_guard", v), Span::mixed_site())).collect();
+        let reduce_totals: Vec<Ident> = reduce_components.iter().map(|v| Ident::new(&format!("{}_total", v), Span::mixed_site())).collect();
+        let reduce_locals: Vec<Ident> = reduce_components.iter().map(|v| Ident::new(&format!("{}_chunk", v), Span::mixed_site())).collect();
+        let reduce_combiners: Vec<proc_macro2::TokenStream> = reduce_components.iter().map(|v| match function_args.reduce_combiners.get(v) {
+            Some(combiner) => quote!{ (#combiner) },
+            None => quote!{ #core::parallel::add_assign },
+        }).collect();
+        let chunk_size = match &function_args.chunk_size {
+            Some(chunk_size) => quote!{ #chunk_size },
+            None => quote!{ #core::parallel::chunk_size(#entity_ids.len()) },
+        };
 
-    let mut filter = quote!{ };
-    let combined_length = read_components.len() + write_components.len();
-    match combined_length {
-        0 => (),
-        1 => filter = quote!{ .filter(|v| v.1.is_some()) },
-        2 => filter = quote!{ .filter(|v| v.0.1.is_some() && v.1.is_some()) },
-        _ => {
-            filter = quote!{ v.1.is_some() };
-            for i in 1..(combined_length-1) {
-                filter = quote!{ #filter && v };
-                for _ in 0..i {
-                    filter = quote!{ #filter.0 };
-                }
-                filter = quote!{ #filter.1.is_some() };
-            }
-            filter = quote!{ .filter(|v| #filter) };
-        },
-    }
+        let track_entity_ids = track_written(quote!{ #entity_ids.iter().copied() });
+        let chunk_results = if reduce_components.is_empty() {
+            quote!{ }
+        } else {
+            quote!{ (#(#reduce_locals,)*) }
+        };
+        let chunk_body = quote!{
+            {
+                #(let mut #reduce_locals = #core::parallel::default_like(&*#reduce_totals);)*
+                {
+                    #(let #reduce_components = &mut #reduce_locals;)*
+                    for &entity_id in #chunk {
+                        #(let Some(#read_components) = #storage::get(#read_components, entity_id) else { continue; });*;
+                        // SAFETY: every entity id is in exactly one chunk, and each column is only
+                        // borrowed once per entity
+                        #(let Some(mut #write_components) = (unsafe { #core::storage::SharedComponents::get_mut(&#write_shared, entity_id) }) else { continue; });*;
 
-    let mut filter_condition = quote!{ };
-    if function_args.filters.len() > 0 {
-        let first_filter = &function_args.filters[0];
-        filter_condition = quote!{ if #first_filter };
-        for filter in function_args.filters[1..].iter() {
-            filter_condition = quote!{ #filter_condition && #filter };
+                        #filter_condition {
+                            #body
+                        }
+                    }
+                }
+                #chunk_results
+            }
+        };
+        let reduce = if reduce_components.is_empty() {
+            quote!{ #core::parallel::for_each_chunk(&#entity_ids, #chunk_size, |#chunk| #chunk_body); }
+        } else {
+            quote!{
+                let #results = #core::parallel::for_each_chunk(&#entity_ids, #chunk_size, |#chunk| #chunk_body);
+                for (#(#reduce_locals,)*) in #results {
+                    #(#reduce_combiners(&mut *#reduce_totals, #reduce_locals);)*
+                }
+            }
+        };
+
+        quote!{
+            let #signatures = world.signatures.read().unwrap();
+            let #required: #core::storage::Signature = const { 0 #(| (1 << #world_type::component_bit(#required_names)))* };
+            let #excluded: #core::storage::Signature = const { 0 #(| (1 << #world_type::component_bit(#excluded_names)))* };
+            let #entity_ids: std::vec::Vec<usize> = #signatures.matching(#required, #excluded).collect();
+            #track_entity_ids
+
+            #(let #read_components = &*#read_components;)*
+            #(let #write_shared = #core::storage::ParallelStorage::shared_mut(&mut *#write_components);)*
+            #(let mut #reduce_guards = world.#reduce_components.write().unwrap();)*
+            #(let #reduce_totals = #reduce_guards.as_mut().expect("Global Components must not be None");)*
+
+            #reduce
         }
-    }
+    } else if iterates_entities && !(read_components.is_empty() && write_components.is_empty()) {
+        // The matching entities are visited in runs, and when every column keeps a run's
+        // components next to each other (archetype tables or dense columns) the body reads them
+        // straight out of slices instead of looking each entity up. A `break` in the body still
+        // stops the whole system, which is why each run counts one past its end.
+        let run = Ident::new("run", Span::mixed_site());
+        let len = Ident::new("len", Span::mixed_site());
+        let slices = Ident::new("slices", Span::mixed_site());
+        let index = Ident::new("index", Span::mixed_site());
+        let stopped = Ident::new("stopped", Span::mixed_site());
+        let read_slices: Vec<Ident> = read_components.iter().map(|v| Ident::new(&format!("{}_slice", v), Span::mixed_site())).collect();
+        let write_slices: Vec<Ident> = write_components.iter().map(|v| Ident::new(&format!("{}_slice", v), Span::mixed_site())).collect();
+        let track_run = track_written(quote!{ (0..#len).map(|v| #run.entity_id(v)) });
+        quote!{
+            let #signatures = world.signatures.read().unwrap();
+            let #required: #core::storage::Signature = const { 0 #(| (1 << #world_type::component_bit(#required_names)))* };
+            let #excluded: #core::storage::Signature = const { 0 #(| (1 << #world_type::component_bit(#excluded_names)))* };
 
-    let body = if read_components.len() + write_components.len() > 0 {
-        quote!{ 
-            for #items in #iterators #filter {
-                #(let #read_components = #read_components.as_ref().unwrap());*;
-                #(let mut #write_components = #write_components.as_mut().unwrap());*;
+            for #run in #signatures.runs(#required, #excluded) {
+                let #len = #run.len();
+                let mut #stopped = true;
+                #track_run
+                let #slices = (#(#storage::run(&*#read_components, &#run),)* #(#storage::run_mut(&mut *#write_components, &#run),)*);
+                if let (#(Some(#read_slices),)* #(Some(#write_slices),)*) = #slices {
+                    #(let #read_slices = &#read_slices[..#len];)*
+                    #(let #write_slices = &mut #write_slices[..#len];)*
+                    for #index in 0..#len + 1 {
+                        if #index == #len {
+                            #stopped = false;
+                            break;
+                        }
+                        let entity_id = #run.entity_id(#index);
+                        #(let #read_components = &#read_slices[#index];)*
+                        #(let mut #write_components = &mut #write_slices[#index];)*
 
-                #filter_condition {
-                    #body
+                        #filter_condition {
+                            #body
+                        }
+                    }
+                } else {
+                    for #index in 0..#len + 1 {
+                        if #index == #len {
+                            #stopped = false;
+                            break;
+                        }
+                        let entity_id = #run.entity_id(#index);
+                        #entity_body
+                    }
+                }
+                if #stopped {
+                    break;
                 }
             }
         }
-    } else if function_args.enumerated {
-        quote!{ 
-            for entity_id in 0..world.entities.read().unwrap().len() {
-                #body
+    } else if iterates_entities {
+        quote!{
+            let #signatures = world.signatures.read().unwrap();
+            let #required: #core::storage::Signature = const { 0 #(| (1 << #world_type::component_bit(#required_names)))* };
+            let #excluded: #core::storage::Signature = const { 0 #(| (1 << #world_type::component_bit(#excluded_names)))* };
+
+            for entity_id in #signatures.matching(#required, #excluded) {
+                #entity_body
             }
         }
     } else {
@@ -521,9 +2470,25 @@ pub fn system(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
 
 
+    // Written components are re-indexed once their locks are released (the guard is declared
+    // before the locks so it is dropped after them), however the body returns
+    let reindex_written = if write_components.is_empty() {
+        quote!{ }
+    } else {
+        let write_names: Vec<LitStr> = write_components.iter().map(|v| LitStr::new(&v.to_string(), Span::call_site())).collect();
+        quote!{
+            let #tracked: bool = const { false #(|| #world_type::is_indexed(#write_names))* };
+            #[allow(unused_mut)]
+            let mut #reindex = #core::index::Reindex::new(&world.index_error, |written: &[usize]| {
+                [#(world.reindex_entities(#write_names, written.iter().copied()),)*].into_iter().collect()
+            });
+        }
+    };
+
     TokenStream::from(quote!{
         pub fn #fn_name(world: std::sync::Arc<std::sync::RwLock<#world_type>>, #(#fn_args),*) {
             let world = world.read().unwrap();
+            #reindex_written
             #(let #read_components = world.#read_components.read().unwrap());*;
             #(let mut #write_components = world.#write_components.write().unwrap());*;
             #(let #global_read_refs = world.#global_read_components.read().unwrap());*;
@@ -0,0 +1,55 @@
+//!
+//! Spawning and Inserting Components in Batches
+//!
+
+use nate_engine_core::error::WorldError;
+use nate_engine_macros::world;
+
+#[world(tags=[enemy])]
+pub struct BatchWorld {
+    position: (i32, i32),
+    health: u32,
+}
+
+fn world_with(entities: usize) -> std::sync::Arc<std::sync::RwLock<BatchWorld>> {
+    let world = BatchWorld::new();
+    world.write().unwrap().spawn_batch((0..entities).map(|_| BatchWorldBundle::default()));
+    world
+}
+
+#[test]
+fn test_spawn_batch_returns_consecutive_ids() {
+    let world = world_with(1);
+    let mut world = world.write().unwrap();
+    let entity_ids = world.spawn_batch((0..3).map(|v| BatchWorldBundle::default().with_position((v, v)).with_enemy()));
+    assert_eq!(entity_ids, vec![1, 2, 3]);
+    assert_eq!(*world.position.read().unwrap(), vec![None, Some((0, 0)), Some((1, 1)), Some((2, 2))]);
+    assert_eq!(world.enemy.read().unwrap().iter().collect::<Vec<usize>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_insert_batch_reports_invalid_entities() {
+    let world = world_with(3);
+    let mut world = world.write().unwrap();
+    assert_eq!(world.insert_health_batch([(2, 20), (0, 10)]), Ok(()));
+    assert_eq!(world.insert_health_batch([(1, 15), (3, 30), (7, 70)]), Err(WorldError::InvalidEntities(vec![3, 7])));
+    // The valid pairs are still inserted
+    assert_eq!(*world.health.read().unwrap(), vec![Some(10), Some(15), Some(20)]);
+
+    assert_eq!(world.insert_enemy_batch([0, 5]), Err(WorldError::InvalidEntities(vec![5])));
+    assert!(world.has_enemy(0));
+}
+
+#[allow(deprecated)]
+#[test]
+fn test_set_many_checks_lengths_and_ids() {
+    let world = world_with(3);
+    let mut world = world.write().unwrap();
+    assert_eq!(world.set_positions(&vec![0, 2], vec![(1, 1)]), Err(WorldError::InvalidEntities(vec![0, 2])));
+    assert_eq!(world.set_positions(&vec![0, 4], vec![(1, 1), (2, 2)]), Err(WorldError::InvalidEntities(vec![4])));
+    // Nothing is set when the batch is rejected
+    assert_eq!(*world.position.read().unwrap(), vec![None; 3]);
+
+    assert_eq!(world.set_positions(&vec![2, 0], vec![(2, 2), (0, 0)]), Ok(()));
+    assert_eq!(*world.position.read().unwrap(), vec![Some((0, 0)), None, Some((2, 2))]);
+}
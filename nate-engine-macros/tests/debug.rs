@@ -0,0 +1,83 @@
+//!
+//! Printing Entities and Worlds
+//!
+
+use nate_engine_macros::world;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Inventory {
+    items: Vec<String>,
+}
+
+// Doesn't implement `Debug`, so it's printed as its type
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brain {
+    state: u8,
+}
+
+#[world(singular=[tick, seed], tags=[enemy, dead])]
+pub struct DebugWorld {
+    position: (isize, isize),
+    health: u32,
+    inventory: Inventory,
+    brain: Brain,
+
+    tick: u64,
+    seed: u64,
+}
+
+fn populate(world: &mut DebugWorld) -> usize {
+    let goblin = world.spawn()
+        .with_position((3, 4))
+        .with_health(5)
+        .with_inventory(Inventory { items: vec![String::from("dagger")] })
+        .with_brain(Brain { state: 0 })
+        .with_enemy()
+        .build();
+    world.spawn().with_position((0, 0)).with_health(10).build();
+    world.set_tick(42);
+    goblin
+}
+
+#[test]
+fn test_describe_entity_lists_present_components() {
+    let world = DebugWorld::new();
+    let mut world = world.write().unwrap();
+    let goblin = populate(&mut world);
+
+    assert_eq!(world.describe_entity(goblin).unwrap(), concat!(
+        "Entity 0 {\n",
+        "    position: (3, 4),\n",
+        "    health: 5,\n",
+        "    inventory: Inventory { items: [\"dagger\"] },\n",
+        "    brain: <Brain>,\n",
+        "    enemy: (),\n",
+        "}",
+    ));
+    assert_eq!(world.describe_entity(1).unwrap(), "Entity 1 {\n    position: (0, 0),\n    health: 10,\n}");
+    assert!(world.describe_entity(2).is_none());
+
+    // Later entities shift down into the removed id
+    world.remove_entity(goblin);
+    assert_eq!(world.describe_entity(0).unwrap(), "Entity 0 {\n    position: (0, 0),\n    health: 10,\n}");
+    assert!(world.describe_entity(1).is_none());
+}
+
+#[test]
+fn test_world_debug_counts_components() {
+    let world = DebugWorld::new();
+    let mut world = world.write().unwrap();
+    assert_eq!(
+        format!("{:?}", world),
+        "DebugWorld { entities: 0, components: {position: 0, health: 0, inventory: 0, brain: 0}, tags: {enemy: 0, dead: 0}, singulars: {tick: None, seed: None} }",
+    );
+
+    populate(&mut world);
+    assert_eq!(
+        format!("{:?}", world),
+        "DebugWorld { entities: 2, components: {position: 2, health: 2, inventory: 1, brain: 1}, tags: {enemy: 1, dead: 0}, singulars: {tick: Some(42), seed: None} }",
+    );
+    assert!(format!("{:#?}", world).contains("    singulars: {\n        tick: Some(42),\n        seed: None,\n    },\n"));
+}
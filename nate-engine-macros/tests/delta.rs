@@ -0,0 +1,90 @@
+//!
+//! Diffing Worlds and Replaying Deltas
+//!
+
+use nate_engine_core::error::WorldError;
+use nate_engine_macros::{system, world};
+
+#[world(diff, hierarchy, singular=[turn], tags=[enemy])]
+pub struct DeltaWorld {
+    position: (isize, isize),
+    velocity: (isize, isize),
+    #[storage(sparse)]
+    name: String,
+
+    turn: u32,
+}
+
+#[system(world=DeltaWorld, read=[velocity], write=[position])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+fn world() -> std::sync::Arc<std::sync::RwLock<DeltaWorld>> {
+    let world = DeltaWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_position((0, 0)).with_name(String::from("player")).build();
+        for v in 0..4 {
+            world.spawn().with_position((v, v)).with_velocity((1, 0)).with_enemy().build();
+        }
+        world.set_parent(2, 0).unwrap();
+        world.set_turn(0);
+    }
+    world
+}
+
+#[test]
+fn test_replica_follows_deltas() {
+    let world = world();
+    let mut replica = world.read().unwrap().snapshot();
+    assert!(replica.diff(&world.read().unwrap()).is_empty());
+
+    for turn in 1..=3 {
+        let before = world.read().unwrap().snapshot();
+        movement(world.clone());
+        {
+            let mut world = world.write().unwrap();
+            world.remove_entity(1);
+            let spawned = world.spawn().with_position((-turn, 0)).with_enemy().build();
+            world.set_parent(spawned, 0).unwrap();
+            world.clear_enemy(0);
+            world.set_name(0, format!("player {}", turn));
+            world.set_turn(turn as u32);
+        }
+
+        let delta = world.read().unwrap().delta_since(&before);
+        assert_eq!(delta.despawned, vec![1]);
+        assert_eq!(delta.spawned.len(), 1);
+        replica.apply_delta(&delta).unwrap();
+        assert!(replica.diff(&world.read().unwrap()).is_empty());
+    }
+
+    let world = world.read().unwrap();
+    assert_eq!(*replica.position.read().unwrap(), *world.position.read().unwrap());
+    assert_eq!(*replica.turn.read().unwrap(), Some(3));
+    assert_eq!(replica.hierarchy.read().unwrap().relations(), world.hierarchy.read().unwrap().relations());
+}
+
+#[test]
+fn test_invalid_deltas_change_nothing() {
+    let world = world();
+    let mut world = world.write().unwrap();
+    let before = world.snapshot();
+
+    let mut delta = DeltaWorldDelta {
+        despawned: vec![4],
+        ..Default::default()
+    };
+    delta.position.push((4, Some((9, 9))));
+    assert_eq!(world.apply_delta(&delta), Err(WorldError::InvalidEntities(vec![4])));
+
+    // Entity 0 can't become a child of its own child
+    let delta = DeltaWorldDelta {
+        parents: vec![(0, Some(2))],
+        ..Default::default()
+    };
+    assert_eq!(world.apply_delta(&delta), Err(WorldError::HierarchyCycle { child: 0, parent: 2 }));
+    assert!(before.diff(&world).is_empty());
+}
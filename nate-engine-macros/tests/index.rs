@@ -0,0 +1,112 @@
+//!
+//! Looking Up Entities by Component Value
+//!
+
+use nate_engine_core::error::WorldError;
+use nate_engine_macros::{system, world};
+
+#[world(tags=[enemy])]
+pub struct IndexWorld {
+    #[index]
+    cell: (isize, isize),
+    #[unique_index]
+    name: String,
+    velocity: (isize, isize),
+}
+
+#[system(world=IndexWorld, read=[velocity], write=[cell])]
+fn movement() {
+    cell.0 += velocity.0;
+    cell.1 += velocity.1;
+}
+
+#[system(world=IndexWorld, write=[name], with=[enemy])]
+fn rename() {
+    *name = String::from("enemy");
+}
+
+fn populate(world: &mut IndexWorld) {
+    world.spawn().with_name(String::from("player")).with_cell((0, 0)).build().unwrap();
+    for (name, x) in [("goblin", 3), ("orc", 5), ("troll", 7)] {
+        world.spawn()
+            .with_name(String::from(name))
+            .with_cell((x, 4))
+            .with_velocity((-1, 0))
+            .with_enemy()
+            .build()
+            .unwrap();
+    }
+}
+
+fn duplicate(entities: Vec<usize>) -> WorldError {
+    WorldError::DuplicateUnique { component: String::from("name"), entities }
+}
+
+#[test]
+fn test_duplicate_unique_values_are_rejected() {
+    let world = IndexWorld::new();
+    let mut world = world.write().unwrap();
+    populate(&mut world);
+
+    assert_eq!(world.set_name(0, String::from("orc")), Err(duplicate(vec![0, 2])));
+    assert_eq!(world.spawn().with_name(String::from("troll")).build(), Err(duplicate(vec![3, 4])));
+    assert_eq!(
+        world.spawn_batch([IndexWorldBundle::default().with_name(String::from("ogre")), IndexWorldBundle::default().with_name(String::from("ogre"))]),
+        Err(duplicate(vec![4, 5])),
+    );
+    assert_eq!(world.insert_name_batch([(0, String::from("goblin"))]), Err(duplicate(vec![0, 1])));
+
+    // Nothing changed
+    assert_eq!(world.entities.read().unwrap().len(), 4);
+    for (entity_id, name) in ["player", "goblin", "orc", "troll"].into_iter().enumerate() {
+        assert_eq!(world.entity_with_name(name), Some(entity_id));
+    }
+
+    // Swapping names or reusing the name of a removed entity is fine
+    assert_eq!(world.insert_name_batch([(1, String::from("orc")), (2, String::from("goblin"))]), Ok(()));
+    world.remove_entity(3);
+    assert_eq!(world.set_name(0, String::from("troll")), Ok(()));
+    assert_eq!(world.entity_with_name("troll"), Some(0));
+    assert_eq!(world.entity_with_name("player"), None);
+    assert_eq!(world.entity_with_name("orc"), Some(1));
+}
+
+#[test]
+fn test_index_follows_systems_and_removals() {
+    let world = IndexWorld::new();
+    populate(&mut world.write().unwrap());
+
+    movement(world.clone());
+    assert_eq!(world.read().unwrap().entities_with_cell(&(2, 4)), &[1]);
+    movement(world.clone());
+    assert_eq!(world.read().unwrap().entities_with_cell(&(3, 4)), &[2]);
+    assert!(world.read().unwrap().entities_with_cell(&(1, 4)).contains(&1));
+
+    let mut world = world.write().unwrap();
+    world.set_cell(0, (3, 4));
+    assert_eq!(world.entities_with_cell(&(3, 4)), &[2, 0]);
+    world.clear_cell(2);
+    assert_eq!(world.entities_with_cell(&(3, 4)), &[0]);
+
+    // The troll moves down into the goblin's id
+    world.remove_entity(1);
+    assert_eq!(world.entity_with_name("goblin"), None);
+    assert_eq!(world.entity_with_name("troll"), Some(2));
+    assert_eq!(world.entities_with_cell(&(5, 4)), &[2]);
+    assert!(world.entities_with_cell(&(1, 4)).is_empty());
+}
+
+#[test]
+fn test_duplicates_written_by_systems_are_kept() {
+    let world = IndexWorld::new();
+    populate(&mut world.write().unwrap());
+    assert_eq!(world.read().unwrap().take_index_error(), None);
+
+    rename(world.clone());
+    let world = world.read().unwrap();
+    assert_eq!(world.take_index_error(), Some(duplicate(vec![1, 2, 3])));
+    assert_eq!(world.take_index_error(), None);
+    // The duplicate is still indexed
+    assert_eq!(world.name_index.read().unwrap().get("enemy"), &[1, 2, 3]);
+    assert_eq!(world.entity_with_name("player"), Some(0));
+}
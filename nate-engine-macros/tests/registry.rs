@@ -0,0 +1,82 @@
+//!
+//! Accessing Components by Name Through the Registry
+//!
+
+use nate_engine_core::error::{RegistryError, WorldError};
+use nate_engine_core::registry::{ComponentKind, Reflect};
+use nate_engine_macros::world;
+
+// Doesn't implement `FromStr` or `Debug`
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brain {
+    state: u8,
+}
+
+#[world(singular=[difficulty], tags=[enemy])]
+pub struct RegistryWorld {
+    position: (isize, isize),
+    health: u32,
+    #[unique_index]
+    name: String,
+    brain: Brain,
+
+    difficulty: f32,
+}
+
+#[test]
+fn test_registry_lists_every_component() {
+    let components: Vec<(&str, ComponentKind)> = RegistryWorld::REGISTRY.iter().map(|v| (v.name, v.kind)).collect();
+    assert_eq!(components, vec![
+        ("position", ComponentKind::Entity),
+        ("health", ComponentKind::Entity),
+        ("name", ComponentKind::Entity),
+        ("brain", ComponentKind::Entity),
+        ("enemy", ComponentKind::Tag),
+        ("difficulty", ComponentKind::Singular),
+    ]);
+    assert!(RegistryWorld::component_info("health").unwrap().is::<u32>());
+}
+
+#[test]
+fn test_components_are_set_and_read_as_strings() {
+    let world = RegistryWorld::new();
+    let mut world = world.write().unwrap();
+    let entity_id = world.spawn().with_brain(Brain { state: 1 }).build().unwrap();
+
+    world.set_from_str(entity_id, "health", "25").unwrap();
+    world.set_from_str(entity_id, "enemy", "true").unwrap();
+    world.set_singular_from_str("difficulty", "1.5").unwrap();
+    assert_eq!(world.get_string(entity_id, "health"), Ok(Some(String::from("25"))));
+    assert_eq!(world.get_string(entity_id, "position"), Ok(None));
+    assert_eq!(world.get_string(entity_id, "enemy"), Ok(Some(String::from("()"))));
+    assert_eq!(world.get_singular_string("difficulty"), Ok(Some(String::from("1.5"))));
+    assert_eq!(world.get_dyn(entity_id, "health").unwrap().downcast_ref::<u32>(), Some(&25));
+    assert!(world.get_dyn(entity_id, "brain").unwrap().downcast_ref::<Brain>().is_some_and(|v| v.state == 1));
+
+    assert!(matches!(world.set_from_str(entity_id, "health", "lots"), Err(RegistryError::Parse { .. })));
+    assert_eq!(world.set_from_str(entity_id, "brain", "idle"), Err(RegistryError::NotFromStr(String::from("brain"))));
+    assert_eq!(world.get_string(entity_id, "brain"), Err(RegistryError::NotDebug(String::from("brain"))));
+    assert_eq!(world.set_from_str(entity_id, "mana", "1"), Err(RegistryError::UnknownComponent(String::from("mana"))));
+    assert_eq!(world.set_from_str(4, "health", "1"), Err(RegistryError::InvalidEntity(4)));
+    assert_eq!(world.get_string(entity_id, "health"), Ok(Some(String::from("25"))));
+}
+
+#[test]
+fn test_spawn_from_strs_adds_all_or_nothing() {
+    let world = RegistryWorld::new();
+    let mut world = world.write().unwrap();
+    let player = world.spawn_from_strs(&[("name", "player"), ("health", "10"), ("enemy", "false")]).unwrap();
+    assert_eq!(player, 0);
+    assert_eq!(*world.health.read().unwrap(), vec![Some(10)]);
+    assert!(!world.has_enemy(player));
+
+    assert!(matches!(world.spawn_from_strs(&[("name", "goblin"), ("health", "lots")]), Err(RegistryError::Parse { .. })));
+    assert_eq!(world.spawn_from_strs(&[("name", "goblin"), ("mana", "3")]), Err(RegistryError::UnknownComponent(String::from("mana"))));
+    assert_eq!(
+        world.spawn_from_strs(&[("name", "player")]),
+        Err(RegistryError::World(WorldError::DuplicateUnique { component: String::from("name"), entities: vec![0, 1] })),
+    );
+    assert_eq!(world.entity_count(), 1);
+    assert_eq!(world.entity_with_name("goblin"), None);
+}
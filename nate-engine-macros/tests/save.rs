@@ -0,0 +1,135 @@
+//!
+//! Saving Worlds and Loading them Back
+//!
+
+use nate_engine_core::error::SaveError;
+use nate_engine_core::save::{ComponentKind, ComponentSchema, Migrations, SaveFile};
+use nate_engine_core::storage::ComponentStorage;
+use nate_engine_macros::{system, world};
+
+#[world(singular=[turn, canvas], tags=[enemy])]
+pub struct SaveWorld {
+    position: (isize, isize),
+    #[storage(sparse)]
+    name: String,
+    #[skip_save]
+    last_position: (isize, isize),
+
+    turn: u32,
+    #[skip_save]
+    canvas: Vec<String>,
+}
+
+#[system(world=SaveWorld, write=[position], with=[enemy])]
+fn advance() {
+    position.0 += 1;
+}
+
+fn save(world: &SaveWorld) -> Vec<u8> {
+    let mut saved = Vec::new();
+    world.serialize_world(&mut serde_json::Serializer::new(&mut saved)).unwrap();
+    saved
+}
+
+#[test]
+fn test_loaded_world_matches_saved_world() {
+    let world = SaveWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_position((0, 0)).with_name(String::from("player")).build();
+        world.spawn().with_position((5, 3)).with_last_position((5, 2)).with_enemy().build();
+        world.spawn().with_name(String::from("chest")).build();
+        world.set_turn(7);
+        world.set_canvas(vec![String::from("@")]);
+    }
+    let saved = save(&world.read().unwrap());
+
+    let loaded = SaveWorld::new();
+    {
+        let mut loaded = loaded.write().unwrap();
+        loaded.spawn().with_position((9, 9)).build();
+        loaded.set_canvas(vec![String::from(".")]);
+        loaded.deserialize_world(&mut serde_json::Deserializer::from_slice(&saved)).unwrap();
+    }
+    {
+        let loaded = loaded.read().unwrap();
+        assert_eq!(*loaded.position.read().unwrap(), vec![Some((0, 0)), Some((5, 3)), None]);
+        let name = loaded.name.read().unwrap();
+        assert_eq!(name.entity_ids().map(|v| (v, name.get(v).unwrap().as_str())).collect::<Vec<_>>(), vec![(0, "player"), (2, "chest")]);
+        assert_eq!(loaded.enemy.read().unwrap().iter().collect::<Vec<usize>>(), vec![1]);
+        assert_eq!(*loaded.turn.read().unwrap(), Some(7));
+        // Skipped components aren't saved, and skipped singulars keep their value
+        assert_eq!(*loaded.last_position.read().unwrap(), vec![None; 3]);
+        assert_eq!(*loaded.canvas.read().unwrap(), Some(vec![String::from(".")]));
+        assert_eq!(save(&loaded), saved);
+    }
+
+    // Systems find the loaded entities by their components
+    advance(loaded.clone());
+    assert_eq!(loaded.read().unwrap().position.read().unwrap()[1], Some((6, 3)));
+}
+
+#[test]
+fn test_invalid_saves_leave_the_world_unchanged() {
+    let world = SaveWorld::new();
+    world.write().unwrap().spawn().with_position((1, 1)).build();
+
+    let mut world = world.write().unwrap();
+    let saved = br#"{"entities": [0, 1], "position": [[4, [0, 0]]], "name": [], "enemy": [], "turn": null}"#;
+    let err = world.deserialize_world(&mut serde_json::Deserializer::from_slice(saved)).unwrap_err();
+    assert!(err.to_string().starts_with("Invalid Entity Ids: [4]"));
+    assert!(world.deserialize_world(&mut serde_json::Deserializer::from_slice(b"[]")).is_err());
+    assert_eq!(*world.position.read().unwrap(), vec![Some((1, 1))]);
+}
+
+// Version 0 saved positions as `pos`, version 1 renamed them and version 2 added health
+#[world(version = 2, tags=[enemy])]
+pub struct VersionedWorld {
+    position: (isize, isize),
+    health: u32,
+}
+
+const VERSION_0_SAVE: &str = r#"{
+    "world": "VersionedWorld",
+    "version": 0,
+    "components": [
+        { "name": "pos", "type_name": "(isize,isize)", "kind": "Entity" },
+        { "name": "enemy", "type_name": "()", "kind": "Tag" }
+    ],
+    "data": { "entities": [0, 1], "pos": [[0, [0, 0]], [1, [5, 3]]], "enemy": [1] }
+}"#;
+
+fn migrations() -> Migrations {
+    let mut migrations = Migrations::new();
+    migrations.register(0, |save| save.rename_component("pos", "position"));
+    migrations.register(1, |save| {
+        save.add_component(ComponentSchema::new("health", "u32", ComponentKind::Entity), serde_json::json!([[1, 10]]));
+        Ok(())
+    });
+    migrations
+}
+
+#[test]
+fn test_load_applies_migrations() {
+    let save: SaveFile = serde_json::from_str(VERSION_0_SAVE).unwrap();
+    let world = VersionedWorld::new();
+    let mut world = world.write().unwrap();
+
+    assert_eq!(world.load(save.clone(), &Migrations::new()), Err(SaveError::MissingMigration(0)));
+    assert!(world.position.read().unwrap().is_empty());
+
+    world.load(save, &migrations()).unwrap();
+    assert_eq!(*world.position.read().unwrap(), vec![Some((0, 0)), Some((5, 3))]);
+    assert_eq!(*world.health.read().unwrap(), vec![None, Some(10)]);
+    assert!(world.has_enemy(1));
+
+    // Saves of the world are of its current version, which need no migrations
+    let save = world.save().unwrap();
+    assert_eq!(save.version, 2);
+    world.load(save, &Migrations::new()).unwrap();
+    assert_eq!(*world.health.read().unwrap(), vec![None, Some(10)]);
+
+    let mut newer = world.save().unwrap();
+    newer.version = 3;
+    assert_eq!(world.load(newer, &migrations()), Err(SaveError::NewerVersion { version: 3, supported: 2 }));
+}
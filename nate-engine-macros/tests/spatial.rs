@@ -0,0 +1,83 @@
+//!
+//! Neighbour Queries Over Positions
+//!
+
+use std::sync::{Arc, RwLock};
+
+use nate_engine_macros::{system, world};
+
+#[world(singular=[prey], tags=[sheep, wolf])]
+pub struct SpatialWorld {
+    #[spatial(cell_size = 4.0)]
+    position: (f32, f32),
+    velocity: (f32, f32),
+
+    prey: Vec<Vec<usize>>,
+}
+
+#[system(world=SpatialWorld, read=[velocity], write=[position])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+#[system(world=SpatialWorld, read=[position], with=[wolf], _write=[prey])]
+fn hunt() {
+    let grid = world.position_grid.read().unwrap();
+    prey.push(grid.within_radius(position, 3.0).into_iter().filter(|v| *v != entity_id).collect::<Vec<usize>>());
+}
+
+fn populate() -> Arc<RwLock<SpatialWorld>> {
+    let world = SpatialWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        world.spawn().with_position((0.0, 0.0)).with_velocity((1.0, 0.0)).with_wolf().build();
+        for position in [(5.0, 0.0), (6.0, 1.0), (-8.0, 3.0), (20.0, 20.0)] {
+            world.spawn().with_position(position).with_sheep().build();
+        }
+    }
+    world
+}
+
+#[test]
+fn test_grid_follows_systems() {
+    let world = populate();
+    assert_eq!(world.read().unwrap().position_grid.read().unwrap().within_radius(&(0.0, 0.0), 3.0), vec![0]);
+
+    movement(world.clone());
+    movement(world.clone());
+    {
+        let world = world.read().unwrap();
+        let grid = world.position_grid.read().unwrap();
+        assert_eq!(grid.point(0), Some((2.0, 0.0)));
+        assert_eq!(grid.within_radius(&(2.0, 0.0), 3.0), vec![0, 1]);
+        assert_eq!(grid.nearest_k(&(2.0, 0.0), 3), vec![0, 1, 2]);
+    }
+
+    // Systems see the grid as it is after the previous system's writes
+    world.write().unwrap().set_prey(Vec::new());
+    hunt(world.clone());
+    movement(world.clone());
+    movement(world.clone());
+    hunt(world.clone());
+    let world = world.read().unwrap();
+    assert_eq!(*world.prey.read().unwrap(), Some(vec![vec![1], vec![1, 2]]));
+    assert_eq!(world.position_grid.read().unwrap().in_rect(&(-10.0, -10.0), &(10.0, 10.0)).len(), 4);
+}
+
+#[test]
+fn test_grid_follows_setters_and_removals() {
+    let world = populate();
+    let mut world = world.write().unwrap();
+    world.set_position(4, (1.0, 1.0));
+    world.clear_position(1);
+    assert_eq!(world.position_grid.read().unwrap().within_radius(&(0.0, 0.0), 3.0), vec![0, 4]);
+
+    // The sheep at (-8, 3) moves down into id 2
+    world.remove_entity(2);
+    let grid = world.position_grid.read().unwrap();
+    assert_eq!(grid.len(), 3);
+    assert_eq!(grid.point(2), Some((-8.0, 3.0)));
+    assert_eq!(grid.nearest_k(&(0.0, 0.0), 2), vec![0, 3]);
+    assert_eq!(grid.nearest_k(&(-8.0, 3.0), 1), vec![2]);
+}
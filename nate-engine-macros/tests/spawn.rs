@@ -0,0 +1,52 @@
+//!
+//! Spawning Entities with the Entity Builder and Bundles
+//!
+
+use std::sync::{Arc, RwLock};
+
+use nate_engine_macros::{system, world};
+
+#[world(tags=[enemy])]
+pub struct SpawnWorld {
+    position: (i32, i32),
+    velocity: (i32, i32),
+    health: u32,
+}
+
+#[system(world=SpawnWorld, read=[velocity], write=[position])]
+fn movement() {
+    position.0 += velocity.0;
+    position.1 += velocity.1;
+}
+
+fn spawned() -> Arc<RwLock<SpawnWorld>> {
+    let world = SpawnWorld::new();
+    {
+        let mut world = world.write().unwrap();
+        assert_eq!(world.spawn().with_position((0, 0)).with_velocity((1, 2)).with_enemy().build(), 0);
+        assert_eq!(world.spawn_bundle(SpawnWorldBundle::default().with_position((5, 5)).with_health(3)), 1);
+        assert_eq!(world.spawn_bundle(SpawnWorldBundle { velocity: Some((-1, 0)), ..Default::default() }), 2);
+    }
+    world
+}
+
+#[test]
+fn test_spawned_entities_have_only_their_components() {
+    let world = spawned();
+    let world = world.read().unwrap();
+    assert_eq!(*world.position.read().unwrap(), vec![Some((0, 0)), Some((5, 5)), None]);
+    assert_eq!(*world.velocity.read().unwrap(), vec![Some((1, 2)), None, Some((-1, 0))]);
+    assert_eq!(*world.health.read().unwrap(), vec![None, Some(3), None]);
+    assert!(world.has_enemy(0) && !world.has_enemy(1) && !world.has_enemy(2));
+}
+
+#[test]
+fn test_systems_visit_spawned_entities_with_their_components() {
+    let world = spawned();
+    movement(world.clone());
+    movement(world.clone());
+
+    // Only entity 0 has both a position and a velocity
+    let world = world.read().unwrap();
+    assert_eq!(*world.position.read().unwrap(), vec![Some((2, 4)), Some((5, 5)), None]);
+}
@@ -0,0 +1,99 @@
+//!
+//! The Same Systems Over Every Storage Layout
+//!
+
+use nate_engine_core::storage::ComponentStorage;
+use nate_engine_macros::{system, world};
+
+#[world(tags=[frozen])]
+pub struct SlotsWorld {
+    position: (i64, i64),
+    velocity: (i64, i64),
+}
+
+#[world(tags=[frozen])]
+pub struct SparseWorld {
+    #[storage(sparse)]
+    position: (i64, i64),
+    #[storage(sparse)]
+    velocity: (i64, i64),
+}
+
+#[world(tags=[frozen])]
+pub struct DenseWorld {
+    #[storage(dense)]
+    position: (i64, i64),
+    #[storage(dense)]
+    velocity: (i64, i64),
+}
+
+#[world(storage = archetype, tags=[frozen])]
+pub struct ArchetypeWorld {
+    position: (i64, i64),
+    velocity: (i64, i64),
+}
+
+/// Spawn the same entities into the world, change some of them (moving them between runs,
+/// sets and tables), run its movement systems and return the positions
+macro_rules! simulate {
+    ($world:ident, $bundle:ident, $movement:ident, $drift:ident) => {{
+        #[system(world=$world, read=[velocity], write=[position], without=[frozen])]
+        fn $movement() {
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+        }
+
+        // Dense columns hand this system whole runs of positions as slices
+        #[system(world=$world, write=[position])]
+        fn $drift() {
+            position.1 -= 1;
+        }
+
+        let world = $world::new();
+        {
+            let mut world = world.write().unwrap();
+            world.spawn_batch((0..200).map(|v: i64| {
+                let bundle = $bundle::default().with_position((v, 0));
+                let bundle = if v % 3 == 0 { bundle } else { bundle.with_velocity((1, v % 5)) };
+                if v % 7 == 0 { bundle.with_frozen() } else { bundle }
+            }));
+            world.clear_velocity(10);
+            world.set_velocity(51, (2, 2));
+            world.clear_position(100);
+            world.clear_frozen(14);
+            world.remove_entity(0);
+        }
+        for _ in 0..2 {
+            $movement(world.clone());
+            $drift(world.clone());
+        }
+
+        let world = world.read().unwrap();
+        let position = world.position.read().unwrap();
+        (0..200).map(|v| position.get(v).copied()).collect::<Vec<Option<(i64, i64)>>>()
+    }};
+}
+
+#[test]
+fn test_layouts_give_the_same_results() {
+    // The entities as plain values, shifted down by the removal of entity 0
+    let expected: Vec<Option<(i64, i64)>> = (1..201).map(|v: i64| {
+        let velocity = match v {
+            10 => None,
+            51 => Some((2, 2)),
+            _ if v % 3 == 0 => None,
+            _ => Some((1, v % 5)),
+        };
+        let frozen = v % 7 == 0 && v != 14;
+        match (v, velocity) {
+            (100, _) | (200, _) => None,
+            (_, Some(velocity)) if !frozen => Some((v + 2 * velocity.0, 2 * velocity.1 - 2)),
+            _ => Some((v, -2)),
+        }
+    }).collect();
+
+    assert_eq!(simulate!(SlotsWorld, SlotsWorldBundle, slots_movement, slots_drift), expected);
+    assert_eq!(simulate!(SparseWorld, SparseWorldBundle, sparse_movement, sparse_drift), expected);
+    assert_eq!(simulate!(DenseWorld, DenseWorldBundle, dense_movement, dense_drift), expected);
+    assert_eq!(simulate!(ArchetypeWorld, ArchetypeWorldBundle, archetype_movement, archetype_drift), expected);
+}
@@ -18,6 +18,336 @@
 //! This will also generate getters and setters for each component as well as a default
 //! initializer for the world.
 //! 
+//! Fully formed entities can be spawned in one step (without other systems seeing a
+//! half-built entity) using the generated builder or bundle:
+//! ```ignore
+//! let player = world.spawn()
+//!     .with_position((0, 0))
+//!     .with_health(10)
+//!     .build();
+//! 
+//! let enemy = world.spawn_bundle(WorldBundle::default().with_position((5, 5)));
+//! ```
+//! 
+//! Components that only a few entities have can be kept in a sparse set instead of a slot per
+//! entity by annotating the field:
+//! ```ignore
+//! #[world]
+//! pub struct World {
+//!     position: (isize, isize),
+//!     #[storage(sparse)]
+//!     boss: String,
+//! }
+//! ```
+//!
+//! Components most entities have can be kept without an `Option` around each slot with
+//! `#[storage(dense)]`, which tracks presence in a separate bit set and hands out runs of
+//! consecutive entities as plain slices. Systems whose read and written components are all
+//! dense walk those slices instead of looking each entity up, and the runs of a single column
+//! are also available through `runs()` / `runs_mut()`:
+//! ```ignore
+//! #[world]
+//! pub struct World {
+//!     #[storage(dense)]
+//!     position: (isize, isize),
+//! }
+//! ```
+//!
+//! Zero-sized marker components (tags) are declared either as unit fields or through the
+//! `tags` attribute, and are stored as bit sets:
+//! ```ignore
+//! #[world(tags=[enemy, dead])]
+//! pub struct World {
+//!     position: (isize, isize),
+//!     player: (),
+//! }
+//! ```
+//!
+//! Worlds where many entities share the same set of components can group them into dense
+//! tables per component signature (archetype) instead. Components are moved between tables
+//! as they are set and cleared, and systems are written the same way but walk each matching
+//! table front to back as slices:
+//! ```ignore
+//! #[world(storage = archetype)]
+//! pub struct World {
+//!     position: (isize, isize),
+//!     velocity: (isize, isize),
+//! }
+//! ```
+//!
+//! Components whose types implement `Hash + Eq + Clone` can be indexed by value with `#[index]`
+//! or `#[unique_index]`. The indexes are updated by the world's methods and after systems write
+//! the component. Setters and spawns of components with a unique index return
+//! `WorldError::DuplicateUnique` without changing anything when another entity has the value
+//! (a duplicate written by a system is still indexed, and kept for `take_index_error`):
+//! ```ignore
+//! #[world]
+//! pub struct World {
+//!     #[index]
+//!     cell: (isize, isize),
+//!     #[unique_index]
+//!     name: String,
+//! }
+//!
+//! let here = world.entities_with_cell(&(3, 4));
+//! let player = world.entity_with_name("player");
+//! assert!(world.set_name(goblin, String::from("player")).is_err());
+//! ```
+//!
+//! Position components (pairs of numbers, arrays of two numbers or transforms) marked
+//! `#[spatial]` are kept in a spatial hash grid for neighbour queries. Cells should be about the
+//! size of the usual query radius, and the grid is locked after the position column (as systems
+//! reading the position do), the order the world locks them in:
+//! ```ignore
+//! #[world]
+//! pub struct World {
+//!     #[spatial(cell_size = 4.0)]
+//!     position: (f32, f32),
+//! }
+//!
+//! #[system(world=World, read=[position])]
+//! fn neighbours() {
+//!     let grid = world.position_grid.read().unwrap();
+//!     let close = grid.within_radius(position, 3.0);
+//!     let nearest = grid.nearest_k(position, 5);
+//!     let in_view = grid.in_rect(&(0.0, 0.0), &(80.0, 24.0));
+//! }
+//! ```
+//!
+//! With the `serde` feature worlds can be saved and loaded with any serde format through
+//! `serialize_world` / `deserialize_world`, as long as their components implement `Serialize`
+//! and `Deserialize`. Fields that don't need saving (like caches) can be left out:
+//! ```ignore
+//! #[world(singular=[canvas])]
+//! pub struct World {
+//!     position: (isize, isize),
+//!     #[skip_save]
+//!     canvas: [[bool; 10]; 10],
+//! }
+//!
+//! let mut saved = Vec::new();
+//! world.serialize_world(&mut serde_json::Serializer::new(&mut saved))?;
+//! world.deserialize_world(&mut serde_json::Deserializer::from_slice(&saved))?;
+//! ```
+//!
+//! `save` / `load` wrap the saved world in a `SaveFile` recording the world's name, schema
+//! version (set with `#[world(version = N)]`) and the names and types of its components, so
+//! saves of older versions can be upgraded by registered migrations when they are loaded:
+//! ```ignore
+//! let mut migrations = Migrations::new();
+//! migrations.register(0, |save| save.rename_component("pos", "position"));
+//!
+//! let save: SaveFile = serde_json::from_reader(file)?;
+//! world.load(save, &migrations)?;
+//! ```
+//!
+//! Entities can also be spawned from prefabs, templates of an entity's components keyed by the
+//! world's field names stored in JSON (or RON with the `ron` feature) files, and from scenes of
+//! many prefabs with their own overrides:
+//! ```ignore
+//! // goblin.json: { "name": "goblin", "components": { "position": [0, 0], "enemy": true } }
+//! let goblin = Prefab::load("prefabs/goblin.json")?;
+//! world.spawn_prefab(&goblin)?;
+//!
+//! // level.json: { "prefabs": ["goblin.json"], "entities": [
+//! //     { "prefab": "goblin", "overrides": { "position": [3, 4] } }
+//! // ] }
+//! let scene = Scene::load("prefabs/level.json")?;
+//! world.spawn_scene(&scene)?;
+//! ```
+//!
+//! Worlds whose components are all `Clone + PartialEq` can opt into diffing with `diff`, which
+//! generates a `{World}Delta` of the entities spawned and despawned and the components changed
+//! between two states of the world, that can be replayed onto another world (for example a
+//! replica over the network, or a replay):
+//! ```ignore
+//! #[world(diff)]
+//! pub struct World {
+//!     position: (isize, isize),
+//! }
+//!
+//! let before = world.snapshot();
+//! // ... run systems ...
+//! let delta = world.delta_since(&before);
+//! replica.apply_delta(&delta)?;
+//! ```
+//!
+//! Worlds whose components implement `StateHash` (implemented for the std types, including
+//! floats, and for `Hash` types through `state_hash_with_hash!`) can opt into `state_hash` with
+//! `hash`, which hashes every entity and component in a stable order. Comparing the hashes of
+//! two runs with the same seed and input catches non-determinism:
+//! ```ignore
+//! #[world(hash, singular=[canvas])]
+//! pub struct World {
+//!     position: (f32, f32),
+//!     #[skip_hash]
+//!     canvas: [[bool; 10]; 10],
+//! }
+//!
+//! println!("tick {}: {:016x}", tick, world.state_hash());
+//! ```
+//!
+//! Worlds can opt into parent/child relations with `hierarchy`. Removing an entity then removes
+//! its descendants too, and systems can go through the children of their entities:
+//! ```ignore
+//! #[world(hierarchy, tags=[turret])]
+//! pub struct World {
+//!     position: (isize, isize),
+//! }
+//!
+//! world.set_parent(barrel, turret)?;
+//!
+//! #[system(world=World, read=[position], with=[turret])]
+//! fn aim_barrels() {
+//!     for barrel in world.hierarchy.read().unwrap().children(entity_id) {
+//!         // ...
+//!     }
+//! }
+//!
+//! // Removes the turret and its barrels
+//! world.remove_entity(turret);
+//! ```
+//!
+//! Worlds with a `transform` (`Transform2D`, `GridTransform` or any type implementing
+//! `Transform`) also get `local_transform` and `global_transform` components, and the
+//! `propagate_transforms` system that computes the global transforms down the hierarchy. Only
+//! the subtrees whose local transforms or parents changed are recomputed:
+//! ```ignore
+//! #[world(transform = GridTransform, tags=[turret])]
+//! pub struct World {
+//!     name: String,
+//! }
+//!
+//! let systems = vec![(World::propagate_transforms as fn(_), 16_000), /* ... */];
+//! ```
+//!
+//! Worlds with `physics = position` (naming a position component like `(f32, f32)` or
+//! `LocalTransform<Transform2D>`) also get `velocity`, `acceleration`, `collider` and
+//! `rigid_body` components (declaring fields with those names is a compile error), a `physics`
+//! field with the step length, gravity and collision events, and `step_physics` simulating one
+//! fixed step (with `physics_system` running it as a system):
+//! ```ignore
+//! #[world(physics = position)]
+//! pub struct World {
+//!     position: (f32, f32),
+//! }
+//!
+//! *world.physics.write().unwrap() = Physics::new(1.0 / 60.0).with_gravity((0.0, -9.8));
+//! let systems = vec![(World::physics_system as fn(_), 16_667), /* ... */];
+//!
+//! for event in world.physics.write().unwrap().drain_events() {
+//!     if let CollisionEvent::Started(a, b) = event { /* ... */ }
+//! }
+//! ```
+//!
+//! Tile grids for pathfinding are `NavGrid` singulars with a cost per tile. `find_path` runs
+//! A* for a single agent, and `flow_field` gives a field shared by every agent heading to the
+//! same goal, cached until the grid's costs change:
+//! ```ignore
+//! #[world(singular=[nav, goal])]
+//! pub struct World {
+//!     nav: NavGrid,
+//!     goal: (usize, usize),
+//!     cell: (usize, usize),
+//! }
+//!
+//! #[system(world=World, write=[cell], _read=[nav, goal])]
+//! fn walk_to_goal() {
+//!     if let Some(next) = nav.flow_field(*goal).next(*cell) {
+//!         *cell = next;
+//!     }
+//! }
+//! ```
+//!
+//! Levels are `Tilemap` singulars of tile layers, object layers and tile types with properties
+//! and collision flags (tiles with a `solid` property are solid). With the `serde` feature maps
+//! are loaded from Tiled's JSON format, and their objects spawn the prefabs named by their
+//! classes. Maps draw onto a `GlyphGrid` with the glyphs in their tiles' `glyph`, `fg` and `bg`
+//! properties:
+//! ```ignore
+//! let map = Tilemap::load_tiled("maps/dungeon.json")?;
+//! let scene = map.object_scene([player_prefab, rat_prefab], Some("position"));
+//! world.spawn_scene(&scene)?;
+//!
+//! #[system(world=World, _read=[map], _write=[canvas])]
+//! fn draw_map() {
+//!     map.render(canvas, (0, 0));
+//! }
+//! ```
+//!
+//! With the `tui` feature, a `TerminalRenderer` draws a `GlyphGrid` singular to the terminal
+//! every frame and forwards key presses to an `Input` singular for systems to drain. The
+//! terminal is restored before `run` returns, or as soon as the program panics (stopping
+//! rendering), and Ctrl+C or the quit key (`q` by default) stop the engine as a normal shutdown:
+//! ```ignore
+//! let renderer = TerminalRenderer::new(|world: &World| world.canvas.clone())?
+//!     .with_input(|world: &World| world.input.clone());
+//! let mut engine = Engine::new(30, 3, world, systems, Box::new(renderer));
+//!
+//! #[system(world=World, _write=[input], write=[position])]
+//! fn move_player() {
+//!     for key in input.drain() {
+//!         match key {
+//!             Key::Left => position.0 -= 1,
+//!             Key::Right => position.0 += 1,
+//!             _ => (),
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Every world also gets a `REGISTRY` of its components (name, type name, kind and `TypeId`)
+//! for tools like inspectors and consoles, along with access to components by name:
+//! ```ignore
+//! for component in World::REGISTRY {
+//!     println!("{} {}", component.name, component.type_name);
+//! }
+//!
+//! let health = world.get_dyn(entity_id, "health").unwrap();
+//! assert_eq!(health.downcast_ref::<u32>(), Some(&10));
+//! // Parsed with the component's `FromStr` implementation
+//! world.set_from_str(entity_id, "health", "25")?;
+//! // Formatted with the component's `Debug` implementation
+//! println!("{:?}", world.get_string(entity_id, "position")?);
+//! ```
+//!
+//! Entities and worlds can be printed for test failures and bug reports:
+//! ```ignore
+//! // Every component the entity has with its `Debug` output
+//! println!("{}", world.describe_entity(entity_id).unwrap());
+//! // The entity count, how many entities have each component and the singular components
+//! println!("{:#?}", world);
+//! ```
+//!
+//! With the `inspector` feature, a running engine can be inspected and changed from another
+//! terminal with the `nengine-inspect` client of `nate-engine-core`:
+//! ```ignore
+//! let engine = Engine::new(30, 4, world, systems, renderer)
+//!     .with_system_names(["movement", "canvas_update"]);
+//! let inspector = engine.start_inspector(inspector::DEFAULT_PORT)?;
+//! engine.run();
+//!
+//! // $ nengine-inspect entity 3
+//! // $ nengine-inspect set 3 health 25
+//! // $ nengine-inspect disable movement
+//! // $ nengine-inspect watch 500
+//! ```
+//!
+//! A developer console runs text commands against the world between ticks, with the game's own
+//! commands alongside the built-in ones (`list`, `get`, `set`, `spawn`, `pause`, `step`, ...):
+//! ```ignore
+//! let console = Arc::new(Console::new().with_command("heal", "heal ENTITY", "Restore health", |world: &mut World, args, output| {
+//!     // ...
+//!     Ok(())
+//! }));
+//! let engine = Engine::new(30, 4, world, systems, renderer).with_console(console.clone());
+//!
+//! // From the input handling
+//! console.submit("list with health without dead");
+//! // From the renderer
+//! for line in console.output().lines() { /* ... */ }
+//! ```
+//!
 //! # Declaring Systems
 //! ```
 //! #[system(world=World, read=[velocity], write=[position], _read=[game_state], _write=[canvas])]
@@ -31,6 +361,27 @@
 //! the entity's positions are mutable and their velocities are immutable.  The world game_state is
 //! also readable and the world canvas is writable.
 //! 
+//! Systems can also be limited to entities with (or without) components they don't access:
+//! ```ignore
+//! #[system(world=World, write=[health], with=[enemy], without=[dead])]
+//! pub fn damage_enemies() {
+//!     *health -= 1;
+//! }
+//! ```
+//!
+//! Systems over many entities can split them into chunks processed across the engine's workers
+//! with `parallel` (or `par_chunk=N` to pick the chunk size). Outside of an engine the chunks
+//! run on the pool given to `parallel::with_pool`, or one after another without one. Each chunk
+//! only writes the components of its own entities, and singular components can't be written
+//! directly, instead each chunk gets its own default value of a `reduce` component which is
+//! added (or combined with the given function) into the singular once every chunk is done:
+//! ```ignore
+//! #[system(world=World, read=[health], par_chunk=4_096, reduce=[total_health])]
+//! pub fn sum_health() {
+//!     *total_health += *health;
+//! }
+//! ```
+//!
 //! # Examples
 //! 
 //! One example of using the engine is accessible [here](examples/toy_example.rs).
@@ -122,12 +473,15 @@
 //! 
 //!     {
 //!         let mut world = world.write().unwrap();
-//!         // Create Entities in the World
-//!         let entity_ids = world.add_entities(100);
+//!         // Create Entities in the World with their Components
+//!         let entity_ids = world.spawn_batch((0..100).map(|_| {
+//!             ExampleWorldBundle::default()
+//!                 .with_position(...)
+//!                 ...
+//!         }));
 //! 
-//!         // Add Components to Entities
-//!         world.set_positions(&entity_ids, ...);
-//!         ...
+//!         // Add Components to Existing Entities
+//!         world.insert_health_batch(entity_ids.iter().map(|id| (*id, 10)))?;
 //! 
 //!         // Initialize Singular Components
 //!         world.set_game_state(GameState::Playing);
@@ -157,6 +511,15 @@
 #[allow(rustdoc::invalid_rust_codeblocks)]
 
 /// Re-export of Nate's Engine Core
-pub use nate_engine_core::{Engine, Renderer};
+pub use nate_engine_core::{Engine, Renderer, console, delta, error, glyph, hash, hierarchy, index, metrics, navigation, parallel, physics, registry, spatial, state_hash_with_hash, storage, tilemap, transform};
+/// Re-export of saving and loading worlds from Nate's Engine Core
+#[cfg(feature = "serde")]
+pub use nate_engine_core::{prefab, save, serde, serde_json};
+/// Re-export of the inspector from Nate's Engine Core
+#[cfg(feature = "inspector")]
+pub use nate_engine_core::inspector;
+/// Re-export of the terminal renderer from Nate's Engine Core
+#[cfg(feature = "tui")]
+pub use nate_engine_core::terminal;
 /// Re-export of Nate's Engine Macros
 pub use nate_engine_macros::{world, system};